@@ -0,0 +1,14 @@
+
+# Dynamic completion of instance ids, profiles, infrastructure projects and resources, and Consul services
+_ceres_dynamic() {
+    local candidates
+    candidates=$(ceres __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2> /dev/null | cut -f 1)
+    if [[ -n "${candidates}" ]]; then
+        COMPREPLY=($(compgen -W "${candidates}" -- "${COMP_WORDS[COMP_CWORD]}"))
+        return 0
+    fi
+
+    _ceres "$@"
+}
+
+complete -F _ceres_dynamic -o bashdefault -o default ceres
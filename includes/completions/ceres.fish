@@ -0,0 +1,9 @@
+
+# Dynamic completion of instance ids, profiles, infrastructure projects and resources, and Consul services
+function __ceres_complete
+    set -l words (commandline -opc)
+    set -e words[1]
+    ceres __complete -- $words (commandline -ct) 2> /dev/null
+end
+
+complete -c ceres -a '(__ceres_complete)'
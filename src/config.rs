@@ -6,9 +6,16 @@ use provider;
 #[derive(Config, Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct CeresConfig {
     pub default_profile: String,
+    /// Path to a custom price table for cost estimations; defaults to the bundled price table
+    #[serde(default)]
+    pub price_table:     Option<String>,
     pub github:          GitHub,
     pub pivotal:         Pivotal,
     pub logging:         Logging,
+    #[serde(default)]
+    pub cache:           Cache,
+    #[serde(default)]
+    pub history:         History,
     pub status_pages:    HashMap<String, StatusPage>,
     pub profiles:        HashMap<String, Profile>,
 }
@@ -32,6 +39,35 @@ pub struct Logging {
     pub ceres:   String,
 }
 
+/// Settings of the on-disk cache of instance descriptions; cf. `provider::cache`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Cache {
+    /// Directory of the cache files; defaults to `$XDG_CACHE_HOME/ceres` or `~/.cache/ceres`
+    pub dir: Option<String>,
+    /// Maximum age of cached instance descriptions in sec; 0, the default, disables the cache
+    pub ttl: u64,
+}
+
+/// Settings of the local run history; cf. `history`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct History {
+    /// Directory of the run history; defaults to `$XDG_DATA_HOME/ceres/runs` or `~/.local/share/ceres/runs`
+    pub dir:      Option<String>,
+    /// Number of latest runs to keep; older runs are removed when a run is recorded. 0 keeps all runs.
+    pub max_runs: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            dir:      None,
+            max_runs: 100,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct GitHub {
     pub token: String,
@@ -57,6 +93,7 @@ pub struct Profile {
     pub consul:         Option<Consul>,
     pub health:         HealthCheck,
     pub centerdevice:   Option<CenterDevice>,
+    pub bastion:        Option<Bastion>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -78,6 +115,30 @@ pub struct StoryTracker {
 pub enum Provider {
     #[serde(rename = "aws")]
     Aws(provider::aws::Aws),
+    #[serde(rename = "inventory")]
+    Inventory(provider::inventory::Inventory),
+}
+
+impl Provider {
+    /// Creates the provider backend for this provider configuration.
+    pub fn backend(&self) -> Box<dyn provider::Provider> {
+        match *self {
+            Provider::Aws(ref aws) => Box::new(aws.clone()),
+            Provider::Inventory(ref inventory) => Box::new(inventory.clone()),
+        }
+    }
+}
+
+/// Jump host ssh connections to instances are routed through; either `host` or `instance` must be set
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Bastion {
+    /// Host name or IP address of the bastion
+    pub host:     Option<String>,
+    /// Instance selector of a bastion instance, e.g., `tag:Intent=bastion`; reached by its public IP address
+    pub instance: Option<String>,
+    /// Remote login name on the bastion; defaults to the profile's `ssh_user`
+    pub user:     Option<String>,
+    pub port:     Option<u16>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -122,11 +183,13 @@ mod tests {
     #[test]
     fn serialize_deserialize_round_trip() {
         let aws_provider = provider::aws::Aws {
-            access_key_id:     String::from("a key id"),
-            secret_access_key: String::from("an access key"),
-            token:             Some("a-token".to_string()),
-            region:            Region::EuCentral1,
-            role_arn:          String::from("a_role_arn"),
+            access_key_id:        String::from("a key id"),
+            secret_access_key:    String::from("an access key"),
+            token:                Some("a-token".to_string()),
+            region:               Region::EuCentral1,
+            role_arn:             String::from("a_role_arn"),
+            additional_regions:   vec![Region::EuWest1],
+            additional_role_arns: vec![String::from("another_role_arn")],
         };
         let issue_tracker = IssueTracker {
             github_org:                  "MyOrg".to_owned(),
@@ -161,6 +224,11 @@ mod tests {
             consul: Some(consul),
             health,
             centerdevice: Some(centerdevice),
+            bastion: Some(Bastion {
+                host: Some("bastion.example.com".to_owned()),
+                port: Some(2222),
+                ..Default::default()
+            }),
         };
         let mut profiles = HashMap::new();
         profiles.insert("prod".to_owned(), prod_profile);
@@ -181,7 +249,10 @@ mod tests {
         };
         let config = CeresConfig {
             default_profile: "prod".to_owned(),
+            price_table: None,
             logging,
+            cache: Cache::default(),
+            history: History::default(),
             github,
             pivotal,
             status_pages,
@@ -212,10 +283,15 @@ mod tests {
             .is_some()
             .is_equal_to("path/to/your/infrastructure/aws/prod/directory".to_owned());
 
-        let &Provider::Aws(aws) = &default_profile.provider.as_ref().expect("no AWS provider");
+        let aws = match default_profile.provider {
+            Some(Provider::Aws(ref aws)) => aws,
+            _ => panic!("no AWS provider"),
+        };
         assert_that(&aws.access_key_id).is_equal_to("XXXXX".to_owned());
         assert_that(&aws.secret_access_key).is_equal_to("XXXXX".to_owned());
         assert_that(&aws.region).is_equal_to(Region::EuCentral1);
         assert_that(&aws.role_arn).is_equal_to("arn:aws:iam::XXXXX:role/OrganizationAccountAccessRole".to_owned());
+        assert_that(&aws.additional_regions).is_empty();
+        assert_that(&aws.additional_role_arns).is_empty();
     }
 }
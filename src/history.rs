@@ -0,0 +1,337 @@
+//! Local history of command runs.
+//!
+//! `instances run`, `ops asp run`, and `infrastructure * build` log the output of their commands to temporary files
+//! which vanish or are hard to find later on. `RunHistory` persists each run into a directory of its own: `run.json`
+//! holds the ceres command line, profile, user, start and end time as well as the command and exit status of each
+//! target; the logs of the targets are copied to `logs/`. This gives an audit trail of who ran what against which
+//! profile and allows to inspect and retry runs with `ceres runs`. Only the latest runs are kept; cf. `prune`.
+
+use chrono::{DateTime, Utc};
+use serde_json;
+use std::{
+    env,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
+use tempfile::NamedTempFile;
+
+use utils::command::{Command, CommandResult, ExitStatus};
+
+/// Id selecting the latest run
+pub const LAST_RUN_ID: &str = "last";
+
+const RUN_FILE: &str = "run.json";
+const LOGS_DIR: &str = "logs";
+
+/// `RunHistory` stores runs below `dir`.
+#[derive(Debug, Clone)]
+pub struct RunHistory {
+    dir: PathBuf,
+}
+
+/// A recorded run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id:       String,
+    /// Command line of the ceres invocation
+    pub command:  Vec<String>,
+    pub profile:  String,
+    pub user:     Option<String>,
+    pub started:  DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    pub targets:  Vec<TargetRecord>,
+}
+
+/// The result of a single command of a run, e.g., on one instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TargetRecord {
+    pub id:          String,
+    pub command:     Option<CommandRecord>,
+    pub exit_status: ExitStatus,
+    /// Copy of the command's log in the run history
+    pub log:         PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub cmd:     String,
+    pub args:    Option<Vec<String>>,
+    pub cwd:     Option<String>,
+    /// Timeout in sec
+    pub timeout: Option<u64>,
+}
+
+impl RunRecord {
+    pub fn failed_targets(&self) -> Vec<&TargetRecord> {
+        self.targets.iter().filter(|x| !x.exit_status.success()).collect()
+    }
+
+    pub fn succeeded(&self) -> bool { self.failed_targets().is_empty() }
+
+    /// Converts the targets into command results for the output of `utils::run::output_results`.
+    pub fn results(&self) -> Vec<CommandResult> {
+        self.targets
+            .iter()
+            .map(|x| CommandResult {
+                id:          x.id.clone(),
+                log:         x.log.clone(),
+                exit_status: x.exit_status,
+                command:     None,
+            })
+            .collect()
+    }
+}
+
+impl TargetRecord {
+    /// Rebuilds the recorded command logging to `log`.
+    pub fn to_command(&self, log: PathBuf) -> Option<Command> {
+        self.command.as_ref().map(|c| Command {
+            id: self.id.clone(),
+            cmd: c.cmd.clone(),
+            args: c.args.clone(),
+            cwd: c.cwd.clone(),
+            log,
+            timeout: c.timeout.map(Duration::from_secs),
+        })
+    }
+}
+
+impl<'a> From<&'a Command> for CommandRecord {
+    fn from(command: &'a Command) -> Self {
+        CommandRecord {
+            cmd:     command.cmd.clone(),
+            args:    command.args.clone(),
+            cwd:     command.cwd.clone(),
+            timeout: command.timeout.map(|x| x.as_secs()),
+        }
+    }
+}
+
+impl RunHistory {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> RunHistory { RunHistory { dir: dir.into() } }
+
+    /// Records `results` of a run of `command` started at `started` and copies their logs into the history.
+    pub fn record(
+        &self,
+        command: Vec<String>,
+        profile: &str,
+        started: DateTime<Utc>,
+        results: &[CommandResult],
+    ) -> Result<RunRecord> {
+        let base_id = format!("{}-{}", started.format("%Y%m%dT%H%M%S"), process::id());
+        let mut id = base_id.clone();
+        for i in 1.. {
+            if !self.dir.join(&id).exists() {
+                break;
+            }
+            id = format!("{}.{}", base_id, i);
+        }
+        let run_dir = self.dir.join(&id);
+        let logs_dir = run_dir.join(LOGS_DIR);
+        fs::create_dir_all(&logs_dir).chain_err(|| ErrorKind::HistoryFileError(path_to_string(&logs_dir)))?;
+
+        let mut targets = Vec::new();
+        for (i, result) in results.iter().enumerate() {
+            let log = logs_dir.join(format!("{:03}-{}.log", i, sanitize(&result.id)));
+            if let Err(e) = fs::copy(&result.log, &log) {
+                warn!("Failed to copy log '{}' into run history because {}.", path_to_string(&result.log), e);
+            }
+            targets.push(TargetRecord {
+                id: result.id.clone(),
+                command: result.command.as_ref().map(CommandRecord::from),
+                exit_status: result.exit_status,
+                log,
+            });
+        }
+
+        let record = RunRecord {
+            id,
+            command,
+            profile: profile.to_owned(),
+            user: env::var("USER").or_else(|_| env::var("USERNAME")).ok(),
+            started,
+            finished: Utc::now(),
+            targets,
+        };
+        save(&run_dir, &record)?;
+
+        Ok(record)
+    }
+
+    /// Lists all recorded runs from the oldest to the latest; unreadable runs are skipped.
+    pub fn list(&self) -> Result<Vec<RunRecord>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == ::std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::with_chain(e, ErrorKind::HistoryFileError(path_to_string(&self.dir)))),
+        };
+
+        let mut runs = Vec::new();
+        for entry in entries {
+            let path = entry.chain_err(|| ErrorKind::HistoryFileError(path_to_string(&self.dir)))?.path();
+            if !path.join(RUN_FILE).is_file() {
+                continue;
+            }
+            match load(&path) {
+                Ok(run) => runs.push(run),
+                Err(e) => warn!("Ignoring run '{}' because {}.", path_to_string(&path), e),
+            }
+        }
+        runs.sort_by(|a, b| a.started.cmp(&b.started).then_with(|| a.id.cmp(&b.id)));
+
+        Ok(runs)
+    }
+
+    /// Removes all but the latest `max_runs` runs and returns the ids of the removed runs.
+    pub fn prune(&self, max_runs: usize) -> Result<Vec<String>> {
+        let runs = self.list()?;
+        let obsolete = runs.len().saturating_sub(max_runs);
+
+        let mut removed = Vec::new();
+        for run in runs.into_iter().take(obsolete) {
+            let run_dir = self.dir.join(&run.id);
+            fs::remove_dir_all(&run_dir).chain_err(|| ErrorKind::HistoryFileError(path_to_string(&run_dir)))?;
+            removed.push(run.id);
+        }
+
+        Ok(removed)
+    }
+
+    /// Gets the run `id`; `last` gets the latest run.
+    pub fn get(&self, id: &str) -> Result<RunRecord> {
+        if id == LAST_RUN_ID {
+            return self
+                .list()?
+                .pop()
+                .ok_or_else(|| Error::from_kind(ErrorKind::NoSuchRun(id.to_owned())));
+        }
+
+        let run_dir = self.dir.join(id);
+        if id.contains('/') || !run_dir.join(RUN_FILE).is_file() {
+            return Err(Error::from_kind(ErrorKind::NoSuchRun(id.to_owned())));
+        }
+
+        load(&run_dir)
+    }
+}
+
+fn load(run_dir: &Path) -> Result<RunRecord> {
+    let path = run_dir.join(RUN_FILE);
+    let content = fs::read_to_string(&path).chain_err(|| ErrorKind::HistoryFileError(path_to_string(&path)))?;
+
+    serde_json::from_str(&content).chain_err(|| ErrorKind::HistoryFileError(path_to_string(&path)))
+}
+
+fn save(run_dir: &Path, record: &RunRecord) -> Result<()> {
+    let path = run_dir.join(RUN_FILE);
+
+    // Write to a temporary file first, so listing runs never sees a partially written run
+    let mut file = NamedTempFile::new_in(run_dir).chain_err(|| ErrorKind::HistoryFileError(path_to_string(&path)))?;
+    serde_json::to_writer_pretty(&mut file, record).chain_err(|| ErrorKind::HistoryFileError(path_to_string(&path)))?;
+    file.persist(&path)
+        .chain_err(|| ErrorKind::HistoryFileError(path_to_string(&path)))?;
+
+    Ok(())
+}
+
+/// Replaces all characters of a command id which are unsafe in file names.
+fn sanitize(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+fn path_to_string(path: &Path) -> String { path.to_string_lossy().to_string() }
+
+error_chain! {
+    errors {
+        HistoryFileError(path: String) {
+            description("Failed to access run history.")
+            display("Failed to access run history at '{}'.", path)
+        }
+        NoSuchRun(id: String) {
+            description("No such run.")
+            display("No run '{}' in run history.", id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+    use std::io::Write;
+    use tempfile;
+
+    fn command_result(id: &str, exit_code: u32, log: &Path) -> CommandResult {
+        CommandResult {
+            id:          id.to_owned(),
+            log:         log.to_path_buf(),
+            exit_status: ExitStatus::Exited(exit_code),
+            command:     Some(Command {
+                id:      id.to_owned(),
+                cmd:     "ssh".to_owned(),
+                args:    Some(vec!["10.0.0.1".to_owned(), "uptime".to_owned()]),
+                cwd:     None,
+                log:     log.to_path_buf(),
+                timeout: Some(Duration::from_secs(300)),
+            }),
+        }
+    }
+
+    #[test]
+    fn record_and_get_last_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = RunHistory::new(dir.path().join("runs"));
+        let mut log = NamedTempFile::new().unwrap();
+        writeln!(log, "up 42 days").unwrap();
+        let results = vec![command_result("i-1", 0, log.path()), command_result("i-2", 1, log.path())];
+
+        let recorded = history
+            .record(vec!["ceres".to_owned(), "instances".to_owned()], "prod", Utc::now(), &results)
+            .unwrap();
+        let run = history.get(LAST_RUN_ID).unwrap();
+
+        assert_that(&run.id).is_equal_to(&recorded.id);
+        assert_that(&run.profile).is_equal_to("prod".to_owned());
+        assert_that(&run.failed_targets().iter().map(|x| x.id.as_str()).collect::<Vec<_>>()).is_equal_to(vec!["i-2"]);
+        assert_that(&fs::read_to_string(&run.targets[0].log).unwrap()).is_equal_to("up 42 days\n".to_owned());
+        let command = run.targets[1].to_command(PathBuf::from("new.log")).unwrap();
+        assert_that(&command.args).is_equal_to(Some(vec!["10.0.0.1".to_owned(), "uptime".to_owned()]));
+        assert_that(&command.timeout).is_equal_to(Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn prune_keeps_latest_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = RunHistory::new(dir.path());
+        let log = NamedTempFile::new().unwrap();
+        let results = vec![command_result("i-1", 0, log.path())];
+        let ids: Vec<String> = (0..3)
+            .map(|i| {
+                let started = Utc::now() + ::chrono::Duration::seconds(i);
+                history.record(vec!["ceres".to_owned()], "prod", started, &results).unwrap().id
+            })
+            .collect();
+
+        let removed = history.prune(2).unwrap();
+
+        assert_that(&removed).is_equal_to(vec![ids[0].clone()]);
+        let kept: Vec<String> = history.list().unwrap().into_iter().map(|x| x.id).collect();
+        assert_that(&kept).is_equal_to(ids[1..].to_vec());
+        assert_that(&history.get(&ids[0])).is_err();
+    }
+
+    #[test]
+    fn get_unknown_run_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = RunHistory::new(dir.path());
+
+        assert_that(&history.get(LAST_RUN_ID)).is_err();
+        assert_that(&history.get("20201017T101500-1")).is_err();
+        assert_that(&history.list()).is_ok().has_length(0);
+    }
+}
@@ -4,6 +4,7 @@ extern crate chrono_humanize;
 extern crate clams;
 extern crate clams_derive;
 extern crate clap;
+extern crate console;
 #[macro_use]
 extern crate error_chain;
 extern crate failure;
@@ -116,6 +117,7 @@ macro_rules! main_module {
 }
 
 pub mod config;
+pub mod history;
 pub mod modules;
 pub mod output;
 pub mod provider;
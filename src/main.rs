@@ -73,6 +73,9 @@ fn run() -> Result<()> {
         color:          !args.is_present("no-color"),
         active_profile: args.value_of("profile").unwrap().to_owned(), // Safe unwrap
         active_config:  config_path,
+        all_profiles:   args.is_present("all-profiles"),
+        refresh:        args.is_present("refresh"),
+        offline:        args.is_present("offline"),
     };
     info!(
         "Active profile={}, default profile={}",
@@ -83,6 +86,7 @@ fn run() -> Result<()> {
         println!("Active Config: {:#?}", config);
     }
 
+    modules::check_all_profiles(&args, &run_config)?;
     modules::call(&args, &run_config, &config).map_err(|e| e.into())
 }
 
@@ -113,6 +117,22 @@ fn build_cli() -> App<'static, 'static> {
                 .default_value("default")
                 .help("Sets profile to use"),
         )
+        .arg(
+            Arg::with_name("all-profiles")
+                .long("all-profiles")
+                .help("Runs read-only commands for all profiles"),
+        )
+        .arg(
+            Arg::with_name("refresh")
+                .long("refresh")
+                .conflicts_with("offline")
+                .help("Ignores cached instance descriptions"),
+        )
+        .arg(
+            Arg::with_name("offline")
+                .long("offline")
+                .help("Uses cached instance descriptions only regardless of their age"),
+        )
         .arg(
             Arg::with_name("verbosity")
                 .short("v")
@@ -151,13 +171,27 @@ fn generate_completion(args: &ArgMatches) -> Result<()> {
     let shell = args
         .value_of("shell")
         .ok_or_else(|| ErrorKind::CliArgsParsingError("shell argument is missing".to_string()))?;
+    let mut script = Vec::new();
     build_cli().gen_completions_to(
         bin_name,
         shell
             .parse::<Shell>()
             .map_err(|_| ErrorKind::CliArgsParsingError("completion script generation failed".to_string()))?,
-        &mut std::io::stdout(),
+        &mut script,
     );
+    let script = String::from_utf8_lossy(&script);
+
+    // The static completions are extended by dynamic completions served by the hidden module `__complete`.
+    let (script, dynamic) = match shell {
+        "bash" => (&*script, include_str!("../includes/completions/ceres.bash")),
+        "fish" => (&*script, include_str!("../includes/completions/ceres.fish")),
+        // The zsh script ends by calling the static completion which the dynamic completion calls instead.
+        _ => (
+            script.trim_end().trim_end_matches(r#"_ceres "$@""#),
+            include_str!("../includes/completions/ceres.zsh"),
+        ),
+    };
+    print!("{}{}", script, dynamic);
 
     Ok(())
 }
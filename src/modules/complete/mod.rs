@@ -0,0 +1,336 @@
+//! Candidates for dynamic shell completion.
+//!
+//! The completion scripts generated by `ceres completions` call `ceres __complete -- <words>` with the words of the
+//! command line up to and including the word to complete. This module derives what to complete from these words and
+//! prints one candidate per line, optionally followed by a tab and a description. If there is nothing to complete
+//! dynamically, nothing is printed and the scripts fall back to the static completions.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use service_world::consul::Consul;
+use std::io::{self, Write};
+
+use config::{CeresConfig as Config, Profile};
+use modules::*;
+use modules::infrastructure::{find_resources, resources_prefix};
+use provider::DescribeInstances;
+use run_config::RunConfig;
+
+pub const NAME: &str = "__complete";
+
+/// Sub commands of `instances` taking instance ids as arguments
+const INSTANCE_COMMANDS: &[&str] = &["run", "ssh", "start", "stop", "terminate"];
+/// Options of `INSTANCE_COMMANDS` taking a value
+const INSTANCE_VALUE_OPTIONS: &[&str] = &[
+    "-l",
+    "--login-name",
+    "-f",
+    "--filter",
+    "-o",
+    "--output",
+    "--ssh-opt",
+    "--timeout",
+    "--wait-timeout",
+    // cf. `rolling_args` and `retries_args`
+    "--batch-size",
+    "--max-failures",
+    "--pause",
+    "--gate-command",
+    "--retries",
+    "--retry-backoff",
+    "--retry-failed-from",
+];
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .setting(AppSettings::Hidden)
+            .about("Prints dynamic completion candidates for shell completion scripts")
+            .arg(
+                Arg::with_name("words")
+                    .multiple(true)
+                    .allow_hyphen_values(true)
+                    .help("Words of the command line up to and including the word to complete"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+/// What to complete for a command line
+#[derive(Debug, PartialEq)]
+pub enum Completion {
+    ConsulServices,
+    Instances,
+    Profiles,
+    Projects {
+        resources_prefix: &'static str,
+    },
+    Resources {
+        resources_prefix: &'static str,
+        project:          Option<String>,
+    },
+}
+
+impl Completion {
+    /// Derives what to complete from the command line `words` of which the last one is the word to complete.
+    pub fn from_words<T: AsRef<str>>(words: &[T]) -> Option<Completion> {
+        let words: Vec<&str> = words.iter().map(AsRef::as_ref).collect();
+        let (current, preceding) = words.split_last()?;
+        let previous = preceding.last().cloned().unwrap_or("");
+
+        if previous == "--profile" {
+            return Some(Completion::Profiles);
+        }
+
+        if has_commands(preceding, &["consul", "list"]) && (previous == "-s" || previous == "--services") {
+            return Some(Completion::ConsulServices);
+        }
+
+        for infrastructure in &["asp", "images", "resources"] {
+            if !has_commands(preceding, &["infrastructure", *infrastructure, "build"]) {
+                continue;
+            }
+            let resources_prefix = resources_prefix(infrastructure)?;
+            match previous {
+                "-p" | "--project" => return Some(Completion::Projects { resources_prefix }),
+                "-r" | "--resource" => {
+                    let project = option_value(preceding, &["-p", "--project"]).map(ToOwned::to_owned);
+                    return Some(Completion::Resources {
+                        resources_prefix,
+                        project,
+                    });
+                }
+                _ => return None,
+            }
+        }
+
+        let is_instance_command = INSTANCE_COMMANDS
+            .iter()
+            .any(|command| has_commands(preceding, &["instances", *command]));
+        if is_instance_command
+            && !preceding.contains(&"--")
+            && !current.starts_with('-')
+            && !INSTANCE_VALUE_OPTIONS.contains(&previous)
+        {
+            return Some(Completion::Instances);
+        }
+
+        None
+    }
+}
+
+/// A completion candidate with an optional description, e.g., the name of an instance
+#[derive(Debug, PartialEq)]
+pub struct Candidate {
+    pub value:       String,
+    pub description: Option<String>,
+}
+
+impl Candidate {
+    fn new<T: Into<String>>(value: T) -> Candidate {
+        Candidate {
+            value:       value.into(),
+            description: None,
+        }
+    }
+}
+
+/// Checks whether `commands` occur in `words` in this order.
+fn has_commands(words: &[&str], commands: &[&str]) -> bool {
+    let mut words = words.iter();
+    commands.iter().all(|command| words.any(|word| word == command))
+}
+
+/// Returns the value of the last occurrence of any of the options `names` in `words`.
+fn option_value<'a>(words: &[&'a str], names: &[&str]) -> Option<&'a str> {
+    words
+        .windows(2)
+        .filter(|pair| names.contains(&pair[0]))
+        .map(|pair| pair[1])
+        .last()
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    let words: Vec<&str> = args.values_of("words").unwrap_or_else(Default::default).collect();
+    let completion = match Completion::from_words(&words) {
+        Some(completion) => completion,
+        None => return Ok(()),
+    };
+    debug!("Completing {:?}.", completion);
+
+    let profile_name = match option_value(&words, &["--profile"]) {
+        Some(name) if name != "default" => name,
+        _ => run_config.active_profile_name(config),
+    };
+
+    // Only completing profiles works without a valid profile
+    let profile = || {
+        config
+            .get_profile(profile_name)
+            .chain_err(|| ErrorKind::ProfileFailed(profile_name.to_owned()))
+    };
+    let candidates = match completion {
+        Completion::ConsulServices => consul_service_candidates(profile()?)?,
+        Completion::Instances => instance_candidates(profile_name, profile()?, run_config, config)?,
+        Completion::Profiles => profile_candidates(config),
+        Completion::Projects { resources_prefix } => {
+            let base_dir = base_dir(&words, profile()?)?;
+            let mut projects: Vec<_> = find_resources(base_dir, resources_prefix)
+                .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?
+                .into_iter()
+                .map(|r| r.project)
+                .collect();
+            projects.sort();
+            projects.dedup();
+            projects.into_iter().map(Candidate::new).collect()
+        }
+        Completion::Resources {
+            resources_prefix,
+            project,
+        } => {
+            let base_dir = base_dir(&words, profile()?)?;
+            let mut resources: Vec<_> = find_resources(base_dir, resources_prefix)
+                .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?
+                .into_iter()
+                .filter(|r| project.as_ref().map(|p| p == &r.project).unwrap_or(true))
+                .map(|r| r.name)
+                .collect();
+            resources.sort();
+            resources.dedup();
+            resources.into_iter().map(Candidate::new).collect()
+        }
+    };
+
+    output_candidates(&candidates).chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))
+}
+
+fn base_dir<'a>(words: &[&'a str], profile: &'a Profile) -> Result<&'a str> {
+    option_value(words, &["--base-dir"])
+        .or_else(|| profile.local_base_dir.as_ref().map(String::as_str))
+        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("local_base_dir".to_string())))
+}
+
+fn profile_candidates(config: &Config) -> Vec<Candidate> {
+    let mut profiles: Vec<_> = config.profiles.keys().map(Candidate::new).collect();
+    profiles.sort_by(|a, b| a.value.cmp(&b.value));
+
+    profiles
+}
+
+fn instance_candidates(
+    profile_name: &str,
+    profile: &Profile,
+    run_config: &RunConfig,
+    config: &Config,
+) -> Result<Vec<Candidate>> {
+    let provider = get_profile_provider(profile_name, profile, &provider_cache(run_config, config))?;
+    let instances = provider
+        .describe_instances()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    let candidates = instances
+        .into_iter()
+        .filter(|i| i.state.as_ref().map(|s| s != "terminated").unwrap_or(true))
+        .filter_map(|i| {
+            let name = i.tags.as_ref().and_then(|tags| tags.get("Name")).and_then(Clone::clone);
+            i.instance_id.map(|id| Candidate {
+                value:       id,
+                description: name,
+            })
+        })
+        .collect();
+
+    Ok(candidates)
+}
+
+fn consul_service_candidates(profile: &Profile) -> Result<Vec<Candidate>> {
+    let url = profile
+        .consul
+        .as_ref()
+        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("consul".to_string())))?
+        .urls
+        .first()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    let catalog = Consul::new(url.to_string())
+        .catalog_by(None, None)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut services: Vec<_> = catalog.services().map(|s| Candidate::new(s.to_string())).collect();
+    services.sort_by(|a, b| a.value.cmp(&b.value));
+
+    Ok(services)
+}
+
+fn output_candidates(candidates: &[Candidate]) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for candidate in candidates {
+        match candidate.description {
+            Some(ref description) => writeln!(stdout, "{}\t{}", candidate.value, description)?,
+            None => writeln!(stdout, "{}", candidate.value)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    #[test]
+    fn from_words_profiles() {
+        let words = ["--profile", ""];
+
+        let res = Completion::from_words(&words);
+
+        assert_that(&res).is_equal_to(Some(Completion::Profiles));
+    }
+
+    #[test]
+    fn from_words_instances() {
+        let words = ["--profile", "prod", "instances", "stop", "--dry", "i-1", "i"];
+
+        let res = Completion::from_words(&words);
+
+        assert_that(&res).is_equal_to(Some(Completion::Instances));
+    }
+
+    #[test]
+    fn from_words_no_instances_for_options_and_commands() {
+        assert_that(&Completion::from_words(&["instances", "ssh", "-"])).is_none();
+        assert_that(&Completion::from_words(&["instances", "run", "-o", ""])).is_none();
+        assert_that(&Completion::from_words(&["instances", "run", "--batch-size", ""])).is_none();
+        assert_that(&Completion::from_words(&["instances", "run", "--retry-failed-from", ""])).is_none();
+        assert_that(&Completion::from_words(&["instances", "run", "i-1", "--", ""])).is_none();
+        assert_that(&Completion::from_words(&["instances", "list", ""])).is_none();
+    }
+
+    #[test]
+    fn from_words_resources_of_project() {
+        let words = ["infrastructure", "images", "build", "-p", "web", "--resource", ""];
+
+        let res = Completion::from_words(&words);
+
+        assert_that(&res).is_equal_to(Some(Completion::Resources {
+            resources_prefix: "packer/resources",
+            project:          Some("web".to_owned()),
+        }));
+    }
+
+    #[test]
+    fn from_words_consul_services() {
+        let words = ["consul", "list", "-s", ""];
+
+        let res = Completion::from_words(&words);
+
+        assert_that(&res).is_equal_to(Some(Completion::ConsulServices));
+    }
+}
@@ -4,7 +4,7 @@ use service_world::consul::{Consul, Catalog};
 use config::CeresConfig as Config;
 use run_config::RunConfig;
 use modules::*;
-use modules::consul::NodeField;
+use modules::consul::{NodeField, ProfileCatalog};
 use output::OutputType;
 use output::consul::{JsonOutputCatalogResult, OutputCatalogResult, PlainOutputCatalogResult, TableOutputCatalogResult};
 
@@ -59,29 +59,31 @@ impl Module for SubModule {
 }
 
 fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
-    let profile = match run_config.active_profile.as_ref() {
-        "default" => config.get_default_profile(),
-        s => config.get_profile(s),
-    }.chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
-
     let services = args.values_of_lossy("services");
     let tags = args.values_of_lossy("tags");
-    let url = profile.consul
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("consul".to_string())))?
-        .urls
-        .first()
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
 
     info!("Quering for services = {}, tags = {}",
         services.as_ref().map(|x| x.join(",")).unwrap_or_else(|| "()".to_owned()),
         tags.as_ref().map(|x| x.join(",")).unwrap_or_else(|| "()".to_owned())
     );
-    let catalog = query_consul(url.to_string(), services, tags)
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    let catalogs = for_each_profile(run_config, config, move |_, profile| {
+        let url = profile.consul
+            .as_ref()
+            .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("consul".to_string())))?
+            .urls
+            .first()
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+        query_consul(url.to_string(), services.clone(), tags.clone())
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+    })?;
+    let catalogs: Vec<_> = catalogs
+        .into_iter()
+        .map(|(profile, catalog)| ProfileCatalog { profile, catalog })
+        .collect();
 
     info!("Outputting catalog");
-    output_instances(args, run_config, config, &catalog)?;
+    output_instances(args, run_config, config, &catalogs)?;
 
     Ok(())
 }
@@ -96,9 +98,9 @@ fn query_consul(url: String, services: Option<Vec<String>>, tags: Option<Vec<Str
 
 fn output_instances(
     args: &ArgMatches,
-    _: &RunConfig,
+    run_config: &RunConfig,
     _: &Config,
-    catalog: &Catalog,
+    catalogs: &[ProfileCatalog],
 ) -> Result<()> {
     let output_type = args.value_of("output").unwrap() // Safe
         .parse::<OutputType>()
@@ -107,33 +109,43 @@ fn output_instances(
 
     match output_type {
         OutputType::Human => {
-            let opts = args.value_of("output-options").unwrap(); // Safe unwrap
-            let output = human_output(opts)?;
+            let opts = output_options(args, run_config);
+            let output = human_output(&opts, run_config)?;
 
             output
-                .output(&mut stdout, catalog)
+                .output(&mut stdout, catalogs)
                 .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
         },
         OutputType::Json => {
-            let output = JsonOutputCatalogResult;
+            let output = JsonOutputCatalogResult { by_profile: run_config.all_profiles };
 
             output
-                .output(&mut stdout, catalog)
+                .output(&mut stdout, catalogs)
                 .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
         },
         OutputType::Plain => {
-            let opts = args.value_of("output-options").unwrap(); // Safe unwrap
-            let output = plain_output(opts)?;
+            let opts = output_options(args, run_config);
+            let output = plain_output(&opts, run_config)?;
 
             output
-                .output(&mut stdout, catalog)
+                .output(&mut stdout, catalogs)
                 .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
         },    }
 }
 
-fn human_output(output_opts: &str) -> Result<TableOutputCatalogResult> {
+fn output_options(args: &ArgMatches, run_config: &RunConfig) -> String {
+    let opts = args.value_of("output-options").unwrap(); // Safe unwrap
+    // Nodes of different profiles are indistinguishable without their profile
+    if run_config.all_profiles && args.occurrences_of("output-options") == 0 {
+        format!("Profile,{}", opts)
+    } else {
+        opts.to_owned()
+    }
+}
+
+fn human_output(output_opts: &str, run_config: &RunConfig) -> Result<TableOutputCatalogResult> {
     let output = if output_opts.contains("all") {
-        Default::default()
+        TableOutputCatalogResult { fields: all_fields(TableOutputCatalogResult::default().fields, run_config) }
     } else {
         TableOutputCatalogResult { fields: output_fields(output_opts)? }
     };
@@ -142,9 +154,9 @@ fn human_output(output_opts: &str) -> Result<TableOutputCatalogResult> {
     Ok(output)
 }
 
-fn plain_output(output_opts: &str) -> Result<PlainOutputCatalogResult> {
+fn plain_output(output_opts: &str, run_config: &RunConfig) -> Result<PlainOutputCatalogResult> {
     let output = if output_opts.contains("all") {
-        Default::default()
+        PlainOutputCatalogResult { fields: all_fields(PlainOutputCatalogResult::default().fields, run_config) }
     } else {
         PlainOutputCatalogResult { fields: output_fields(output_opts)? }
     };
@@ -153,6 +165,15 @@ fn plain_output(output_opts: &str) -> Result<PlainOutputCatalogResult> {
     Ok(output)
 }
 
+/// Adds the profile to all fields if the nodes of several profiles are output.
+fn all_fields(mut fields: Vec<NodeField>, run_config: &RunConfig) -> Vec<NodeField> {
+    if run_config.all_profiles {
+        fields.insert(0, NodeField::Profile);
+    }
+
+    fields
+}
+
 fn output_fields(field_str: &str) -> Result<Vec<NodeField>> {
     let fields: ::std::result::Result<Vec<_>, _> = field_str
         .split(',')
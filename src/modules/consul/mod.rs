@@ -1,9 +1,18 @@
 sub_module!("consul", "Do stuff of consul", list);
 
+use service_world::consul::Catalog;
 use std::str::FromStr;
 
+/// Consul catalog of a profile
+#[derive(Serialize)]
+pub struct ProfileCatalog {
+    pub profile: String,
+    pub catalog: Catalog,
+}
+
 #[derive(Debug)]
 pub enum NodeField {
+    Profile,
     Id,
     Name,
     MetaData(Option<Vec<String>>),
@@ -21,6 +30,7 @@ impl FromStr for NodeField {
 
     fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
         match s {
+            "Profile" => Ok(NodeField::Profile),
             "Id" => Ok(NodeField::Id),
             "Name" => Ok(NodeField::Name),
             s if s.starts_with("MetaData") => {
@@ -10,9 +10,9 @@ use std::collections::HashMap;
 use std::path::Path;
 use tokio_core;
 
-use config::CeresConfig as Config;
+use config::{CeresConfig as Config, Profile};
 use run_config::RunConfig;
-use modules::{Result as ModuleResult, Error as ModuleError, ErrorKind as ModuleErrorKind, Module};
+use modules::{for_each_profile, Result as ModuleResult, Error as ModuleError, ErrorKind as ModuleErrorKind, Module};
 use modules::health::errors::*;
 use output::OutputType;
 use output::health::*;
@@ -31,6 +31,7 @@ pub const ENDPOINTS: &[&str] = &[
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct HealthCheck {
+   pub profile: String,
    pub name: String,
    pub result: HealthCheckResult,
 }
@@ -78,17 +79,28 @@ impl Module for SubModule {
 }
 
 fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
-   let profile = match run_config.active_profile.as_ref() {
-      "default" => config.get_default_profile(),
-      s => config.get_profile(s),
-   }.chain_err(|| ErrorKind::FailedToParseCmd("profile".to_string()))?;
-   let base_domain = &profile.health.base_domain;
-
    let output_type = args.value_of("output").unwrap() // Safe
        .parse::<OutputType>()
        .chain_err(|| ErrorKind::FailedToParseOutputType)?;
 
    info!("Checking Health");
+   let health_checks = for_each_profile(run_config, config, |profile_name, profile| {
+      check_health(profile_name, profile)
+         .map_err(|e| ModuleError::with_chain(e, ModuleErrorKind::ModuleFailed(NAME.to_owned())))
+   }).chain_err(|| ErrorKind::FailedQueryHeatlhCheck("failed to check profiles".to_owned()))?;
+   let health_checks: Vec<_> = health_checks.into_iter().flat_map(|(_, checks)| checks).collect();
+
+   trace!("{:#?}", health_checks);
+
+   info!("Outputting Health Checks");
+   output_page_status(output_type, run_config.all_profiles, &health_checks)?;
+
+   Ok(())
+}
+
+fn check_health(profile_name: &str, profile: &Profile) -> Result<Vec<HealthCheck>> {
+   let base_domain = &profile.health.base_domain;
+
    let mut core = tokio_core::reactor::Core::new()
       .chain_err(|| ErrorKind::FailedQueryHeatlhCheck("failed to create reactor".to_owned()))?;
    let mut client = ReqwestClient::builder();
@@ -101,17 +113,11 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
 
    let queries = ENDPOINTS.iter().map(|name| {
       let url = format!("https://{}.{}/healthcheck", name, base_domain);
-      query_health(&client, name, &url)
+      query_health(&client, profile_name, name, &url)
    });
    let work = futures_ordered(queries).collect();
-   let health_checks = core.run(work)?;
-
-   trace!("{:#?}", health_checks);
 
-   info!("Outputting Health Checks");
-   output_page_status(output_type, &health_checks)?;
-
-   Ok(())
+   core.run(work)
 }
 
 fn load_cert_from_file<P: AsRef<Path>>(path: P) -> Result<Certificate> {
@@ -125,8 +131,9 @@ fn load_cert_from_file<P: AsRef<Path>>(path: P) -> Result<Certificate> {
     Ok(cert)
 }
 
-fn query_health(client: &ReqwestClient, name: &'static str, url: &str) -> impl Future<Item = HealthCheck, Error = Error> {
+fn query_health(client: &ReqwestClient, profile: &str, name: &'static str, url: &str) -> impl Future<Item = HealthCheck, Error = Error> {
    trace!("Quering health for {}", url);
+   let profile = profile.to_owned();
 
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert(CONNECTION, "close".parse().unwrap());
@@ -158,36 +165,39 @@ fn query_health(client: &ReqwestClient, name: &'static str, url: &str) -> impl F
             .map_err(|e| Error::with_chain(e, ErrorKind::FailedQueryHeatlhCheck("failed to parse response".to_owned())));
          result(res)
       })
-      .map(move |checks| HealthCheck { name: name.to_string(), result: HealthCheckResult::Ok(checks) } )
-      .or_else(move |e| {
-         let reason = format!("{}", e);
-         Ok(HealthCheck { name: name.to_string(), result: HealthCheckResult::Failed(reason) })
+      .then(move |res| {
+         let result = match res {
+            Ok(checks) => HealthCheckResult::Ok(checks),
+            Err(e) => HealthCheckResult::Failed(format!("{}", e)),
+         };
+         Ok::<_, Error>(HealthCheck { profile, name: name.to_string(), result })
       })
 }
 
 fn output_page_status(
     output_type: OutputType,
+    show_profile: bool,
     health_checks: &[HealthCheck]
 ) -> Result<()> {
    let mut stdout = ::std::io::stdout();
 
     match output_type {
         OutputType::Human => {
-            let output = TableOutputHealthCheck;
+            let output = TableOutputHealthCheck { show_profile };
 
             output
                 .output(&mut stdout, health_checks)
                 .chain_err(|| ErrorKind::FailedOutput)
         },
         OutputType::Json => {
-            let output = JsonOutputHealthCheck;
+            let output = JsonOutputHealthCheck { show_profile };
 
             output
                 .output(&mut stdout, health_checks)
                 .chain_err(|| ErrorKind::FailedOutput)
         },
         OutputType::Plain => {
-            let output = PlainOutputHealthCheck;
+            let output = PlainOutputHealthCheck { show_profile };
 
             output
                 .output(&mut stdout, health_checks)
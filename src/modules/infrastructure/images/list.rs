@@ -1,2 +1,150 @@
-list_resources!("list available infrastructure images", "packer/resources");
+use clap::{App, Arg, ArgMatches, SubCommand};
 
+use config::CeresConfig as Config;
+use modules::{get_provider, Error as ModuleError, ErrorKind as ModuleErrorKind, Module, Result as ModuleResult};
+use modules::infrastructure::errors::*;
+use modules::infrastructure::{correlate_images, find_resources, ImageListEntry, Resource};
+use output::OutputType;
+use output::infrastructure::{
+    JsonOutputImageList,
+    JsonOutputResourceListResult,
+    OutputImageList,
+    OutputResourceListResult,
+    PlainOutputImageList,
+    PlainOutputResourceListResult,
+    TableOutputImageList,
+    TableOutputResourceListResult,
+};
+use run_config::RunConfig;
+
+pub const NAME: &str = "list";
+const RESOURCES_PREFIX: &str = "packer/resources";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("list available infrastructure images")
+            .arg(
+                Arg::with_name("base-dir")
+                    .long("base-dir")
+                    .takes_value(true)
+                    .help("Overwrites base dir from ceres configuration file"),
+            )
+            .arg(
+                Arg::with_name("amis")
+                    .long("amis")
+                    .help("Lists the images (AMIs) owned by the account and correlates them with the image resources"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json", "plain"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> ModuleResult<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+            .map_err(|e| ModuleError::with_chain(e, ModuleErrorKind::ModuleFailed(NAME.to_owned())))
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    let profile = match run_config.active_profile.as_ref() {
+        "default" => config.get_default_profile(),
+        s => config.get_profile(s),
+    }.chain_err(|| ErrorKind::FailedToLoadProfile)?;
+
+    let local_base_dir = if let Some(base_dir) = args.value_of("base-dir") {
+        base_dir
+    } else {
+        profile.local_base_dir.as_ref()
+        .ok_or(Error::from_kind(ErrorKind::NoLocalBaseDir))?
+    };
+
+    let resources = find_resources(local_base_dir, RESOURCES_PREFIX)?;
+
+    if !args.is_present("amis") {
+        info!("Outputting resource list");
+        return output_list(args, &resources);
+    }
+
+    let provider = get_provider(run_config, config).chain_err(|| ErrorKind::FailedToQueryProvider)?;
+    info!("Querying images");
+    let images = provider.describe_images().chain_err(|| ErrorKind::FailedToQueryProvider)?;
+    info!("Querying instances");
+    let instances = provider.describe_instances().chain_err(|| ErrorKind::FailedToQueryProvider)?;
+
+    let entries = correlate_images(resources, images, &instances);
+
+    info!("Outputting image list");
+    output_image_list(args, &entries)
+}
+
+fn output_list(args: &ArgMatches, resources: &[Resource]) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::FailedToParseOutputType)?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputResourceListResult;
+
+            output
+                .output(&mut stdout, resources)
+                .chain_err(|| ErrorKind::FailedOutput)
+        },
+        OutputType::Json => {
+            let output = JsonOutputResourceListResult;
+
+            output
+                .output(&mut stdout, resources)
+                .chain_err(|| ErrorKind::FailedOutput)
+        },
+        OutputType::Plain => {
+            let output = PlainOutputResourceListResult;
+
+            output
+                .output(&mut stdout, resources)
+                .chain_err(|| ErrorKind::FailedOutput)
+        },
+    }
+}
+
+fn output_image_list(args: &ArgMatches, entries: &[ImageListEntry]) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::FailedToParseOutputType)?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputImageList;
+
+            output
+                .output(&mut stdout, entries)
+                .chain_err(|| ErrorKind::FailedOutput)
+        },
+        OutputType::Json => {
+            let output = JsonOutputImageList;
+
+            output
+                .output(&mut stdout, entries)
+                .chain_err(|| ErrorKind::FailedOutput)
+        },
+        OutputType::Plain => {
+            let output = PlainOutputImageList;
+
+            output
+                .output(&mut stdout, entries)
+                .chain_err(|| ErrorKind::FailedOutput)
+        },
+    }
+}
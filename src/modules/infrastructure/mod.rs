@@ -1,5 +1,8 @@
+use ignore::WalkBuilder;
 use std::path::{Component, Path, PathBuf};
 
+use provider::{ImageDescriptor, InstanceDescriptor};
+
 // This mod's errors need an individual namespace because the sub_module macro imports the
 // module::errors into this scope which leads to name / type conflicts.
 mod errors {
@@ -21,6 +24,10 @@ mod errors {
                 description("Failed to parse resources from path")
                 display("Failed to parse resources from path '{}'", path)
             }
+            FailedToQueryProvider {
+                description("Failed to query provider")
+                display("Failed to query provider")
+            }
             FailedOutput {
                 description("Failed to output")
                 display("Failed to output")
@@ -45,7 +52,7 @@ mod errors {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Resource {
     pub project: String,
     pub name: String,
@@ -78,16 +85,169 @@ impl Resource {
     }
 }
 
+/// Finds all resources, i.e., directories containing a Makefile below `resources_prefix` in projects of `base_dir`.
+pub fn find_resources<P: AsRef<Path>>(base_dir: P, resources_prefix: &str) -> errors::Result<Vec<Resource>> {
+    let base_dir = base_dir.as_ref();
+    let walker = WalkBuilder::new(base_dir).build();
+
+    let paths: errors::Result<Vec<PathBuf>> = walker
+        .filter(|x| // Does the path point to a Makefile?
+            x.is_ok() &&
+            x.as_ref().unwrap().path().ends_with("Makefile")
+        )
+        .filter(|x| // Does the path to the Makefile contain the _resources prefix_
+            x.as_ref().unwrap().path().parent().is_some() && // Safe see above
+            x.as_ref().unwrap().path().parent().unwrap().to_string_lossy().contains(resources_prefix)
+        )
+        .map(|x|
+            x
+            .map(|d| d.path().parent().unwrap().to_path_buf()) // Safe
+            .map_err(|e| errors::Error::with_chain(e, errors::ErrorKind::FailedToFindResources)))
+        .filter(|x| { // Does the parent directory contain a file "project.cfg"
+            if let Ok(x) = x {
+                let mut p = x.clone();
+                p.pop(); // Will be true since at least two parents are guaranteed; see above.
+                p.push("project.cfg");
+                p.exists() && p.is_file()
+            } else {
+                false
+            }
+        })
+        .collect();
+
+    paths?
+        .iter()
+        .flat_map(|x| x.strip_prefix(base_dir))
+        .map(Resource::from_path)
+        .collect()
+}
+
+/// Returns the resources prefix of the infrastructure sub module `name`, e.g., `asp`.
+pub fn resources_prefix(name: &str) -> Option<&'static str> {
+    match name {
+        "asp" => Some("ansible-setup-package/resources"),
+        "images" => Some("packer/resources"),
+        "resources" => Some("terraform/resources"),
+        _ => None,
+    }
+}
+
+/// Marks noteworthy relations between image resources and the images (AMIs) baked from them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageFlag {
+    /// The resource has never been baked into an image.
+    NeverBaked,
+    /// The image does not belong to any known resource.
+    NoResource,
+    /// The image is not used by any running instance.
+    Unused,
+}
+
+impl ImageFlag {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ImageFlag::NeverBaked => "never-baked",
+            ImageFlag::NoResource => "no-resource",
+            ImageFlag::Unused => "unused",
+        }
+    }
+}
+
+/// `ImageListEntry` relates an image resource to one image baked from it; either side may be missing.
+#[derive(Debug, Serialize)]
+pub struct ImageListEntry {
+    pub resource:          Option<Resource>,
+    pub image:             Option<ImageDescriptor>,
+    pub running_instances: usize,
+    pub flags:             Vec<ImageFlag>,
+}
+
+/// Correlates image resources with images by naming convention.
+///
+/// An image belongs to a resource if the image's name starts with the resource's name followed by a '-', e.g.,
+/// `elk_elasticsearch-2020-06-30T12-00-00` belongs to resource `elk_elasticsearch`. If several resources match, the
+/// image belongs to the one with the longest name, e.g., `elk-kibana-2020-06-30` to `elk-kibana` rather than `elk`.
+/// Images of the same resource are ordered from newest to oldest; images without a resource come last.
+pub fn correlate_images(
+    resources: Vec<Resource>,
+    images: Vec<ImageDescriptor>,
+    instances: &[InstanceDescriptor],
+) -> Vec<ImageListEntry> {
+    let running_instances = |image_id: &str| {
+        instances
+            .iter()
+            .filter(|x| x.image_id.as_ref().map(|x| x == image_id).unwrap_or(false))
+            .filter(|x| x.state.as_ref().map(|x| x == "running").unwrap_or(false))
+            .count()
+    };
+    let image_entry = |resource: Option<Resource>, image: ImageDescriptor| {
+        let running_instances = running_instances(&image.image_id);
+        let mut flags = Vec::new();
+        if resource.is_none() {
+            flags.push(ImageFlag::NoResource);
+        }
+        if running_instances == 0 {
+            flags.push(ImageFlag::Unused);
+        }
+        ImageListEntry {
+            resource,
+            image: Some(image),
+            running_instances,
+            flags,
+        }
+    };
+
+    let mut images = images;
+    images.sort_by(|a, b| b.creation_date.cmp(&a.creation_date));
+
+    let mut baked_by_resource: Vec<Vec<ImageDescriptor>> = resources.iter().map(|_| Vec::new()).collect();
+    let mut unowned = Vec::new();
+    for image in images {
+        let owner = resources
+            .iter()
+            .enumerate()
+            .filter(|&(_, resource)| {
+                let prefix = format!("{}-", resource.name);
+                image.name.as_ref().map(|x| x.starts_with(&prefix)).unwrap_or(false)
+            })
+            .max_by_key(|&(_, resource)| resource.name.len())
+            .map(|(i, _)| i);
+        match owner {
+            Some(i) => baked_by_resource[i].push(image),
+            None => unowned.push(image),
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (resource, baked) in resources.into_iter().zip(baked_by_resource) {
+        if baked.is_empty() {
+            entries.push(ImageListEntry {
+                resource:          Some(resource),
+                image:             None,
+                running_instances: 0,
+                flags:             vec![ImageFlag::NeverBaked],
+            });
+        } else {
+            for image in baked {
+                entries.push(image_entry(Some(resource.clone()), image));
+            }
+        }
+    }
+    for image in unowned {
+        entries.push(image_entry(None, image));
+    }
+
+    entries
+}
 
 macro_rules! list_resources {
     ($description:tt,$resources_prefix:tt) => {
         use clap::{App, Arg, ArgMatches, SubCommand};
-        use ignore::WalkBuilder;
-        use std::path::{Path, PathBuf};
 
         use config::CeresConfig as Config;
         use modules::{Result as ModuleResult, Error as ModuleError, ErrorKind as ModuleErrorKind, Module};
-        use modules::infrastructure::Resource;
+        use modules::infrastructure::{find_resources, Resource};
         use modules::infrastructure::errors::*;
         use output::OutputType;
         use output::infrastructure::{JsonOutputResourceListResult, OutputResourceListResult, PlainOutputResourceListResult, TableOutputResourceListResult};
@@ -139,12 +299,7 @@ macro_rules! list_resources {
                 .ok_or(Error::from_kind(ErrorKind::NoLocalBaseDir))?
             };
 
-            let asps: Result<Vec<_>> = find_resources(local_base_dir)?
-                .iter()
-                .flat_map(|x| x.strip_prefix(local_base_dir))
-                .map(Resource::from_path)
-                .collect();
-            let asps = asps?;
+            let asps = find_resources(local_base_dir, $resources_prefix)?;
 
             info!("Outputting resource list");
             output_list(args, run_config, config, &asps)?;
@@ -152,35 +307,6 @@ macro_rules! list_resources {
             Ok(())
         }
 
-        fn find_resources<P: AsRef<Path>>(base_dir: P) -> Result<Vec<PathBuf>> {
-            let walker = WalkBuilder::new(base_dir).build();
-
-            walker
-                .filter(|x| // Does the path point to a Makefile?
-                    x.is_ok() &&
-                    x.as_ref().unwrap().path().ends_with("Makefile")
-                )
-                .filter(|x| // Does the path to the Makefile contain the _resources prefix_
-                    x.as_ref().unwrap().path().parent().is_some() && // Safe see above
-                    x.as_ref().unwrap().path().parent().unwrap().to_string_lossy().contains($resources_prefix)
-                )
-                .map(|x|
-                    x
-                    .map(|d| d.path().parent().unwrap().to_path_buf()) // Safe
-                    .map_err(|e| Error::with_chain(e, ErrorKind::FailedToFindResources)))
-                .filter(|x| { // Does the parent directory contain a file "project.cfg"
-                    if let Ok(x) = x {
-                        let mut p = x.clone();
-                        p.pop(); // Will be true since at least two parents are guaranteed; see above.
-                        p.push("project.cfg");
-                        p.exists() && p.is_file()
-                    } else {
-                        false
-                    }
-                })
-                .collect()
-        }
-
         fn output_list(
             args: &ArgMatches,
             _: &RunConfig,
@@ -222,13 +348,14 @@ macro_rules! list_resources {
 
 macro_rules! build_resource {
     ($description:tt,$resources_prefix:tt,$($command:tt),+) => {
+        use chrono::Utc;
         use clap::{App, Arg, ArgMatches, SubCommand};
-        use itertools::Itertools;
         use std::path::Path;
         use std::time::Duration;
 
         use config::{CeresConfig as Config};
         use modules::{Result as ModuleResult, Error as ModuleError, ErrorKind as ModuleErrorKind, Module};
+        use modules::{invalidate_provider_cache, record_run};
         use modules::infrastructure::Resource;
         use modules::infrastructure::errors::*;
         use output::OutputType;
@@ -348,7 +475,14 @@ macro_rules! build_resource {
             let commands = commands?;
 
             debug!("Running commands.");
-            let results = run_commands(commands, progress_bar)?;
+            let started = Utc::now();
+            let mut results = Vec::new();
+            let run_result = run_commands(commands, progress_bar, &mut results);
+            // Builds may create, change, or destroy instances behind the provider's back
+            invalidate_provider_cache(run_config, config);
+            // Record the commands run so far even if running a command failed
+            record_run(run_config, config, started, &results);
+            run_result?;
 
             debug!("Outputting results.");
             run::output_results(output_type, show_all, results.as_slice())
@@ -389,25 +523,102 @@ macro_rules! build_resource {
             Ok(c)
         }
 
-        #[allow(unstable_name_collisions)] // flatten from itertools
-        fn run_commands(commands: Vec<Command>, progress_bar: bool) -> Result<Vec<CommandResult>> {
-            let mut results = Vec::new();
+        /// Runs the commands one after another until one fails and appends their results to `results`.
+        fn run_commands(commands: Vec<Command>, progress_bar: bool, results: &mut Vec<CommandResult>) -> Result<()> {
             for c in commands.into_iter() {
                 let res = run::run(vec![c], progress_bar)
                     .chain_err(|| ErrorKind::FailedToRunCommand)?;
-                if res.iter().filter(|x| !x.exit_status.success()).count() > 0 {
-                    results.push(res);
+                let failed = res.iter().any(|x| !x.exit_status.success());
+                results.extend(res);
+                if failed {
                     break;
-                } else {
-                    results.push(res);
                 }
             }
-            let results: Vec<_> = Itertools::flatten(results.into_iter()).collect();
 
-            Ok(results)
+            Ok(())
         }
     }
 }
 
 sub_module!("infrastructure", "Do stuff with infrastructure repos", asp, images, resources);
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{TimeZone, Utc};
+    use spectral::prelude::*;
+
+    fn resource(name: &str) -> Resource {
+        Resource {
+            project: "logimon".to_owned(),
+            name:    name.to_owned(),
+        }
+    }
+
+    fn image(id: &str, name: &str, created_day: u32) -> ImageDescriptor {
+        ImageDescriptor {
+            account:       None,
+            creation_date: Some(Utc.ymd(2020, 6, created_day).and_hms(0, 0, 0)),
+            image_id:      id.to_owned(),
+            name:          Some(name.to_owned()),
+            region:        None,
+            state:         Some("available".to_owned()),
+            tags:          None,
+        }
+    }
+
+    fn instance(image_id: &str, state: &str) -> InstanceDescriptor {
+        InstanceDescriptor {
+            image_id: Some(image_id.to_owned()),
+            state: Some(state.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn summary(entries: &[ImageListEntry]) -> Vec<(Option<&str>, Option<&str>, Vec<ImageFlag>)> {
+        entries
+            .iter()
+            .map(|x| {
+                (
+                    x.resource.as_ref().map(|r| r.name.as_str()),
+                    x.image.as_ref().map(|i| i.image_id.as_str()),
+                    x.flags.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn correlate_images_by_name_prefix() {
+        let resources = vec![
+            resource("elk"),
+            resource("elk_elasticsearch"),
+            resource("elk_kibana"),
+            resource("mongodb"),
+            resource("elk-kibana"),
+        ];
+        let images = vec![
+            image("ami-1", "elk_elasticsearch-20200601", 1),
+            image("ami-2", "elk_elasticsearch-20200602", 2),
+            image("ami-3", "mongodb_config-20200603", 3),
+            image("ami-4", "elk_kibana-20200604", 4),
+            image("ami-5", "elk-kibana-20200605", 5),
+            image("ami-6", "elk-20200606", 6),
+        ];
+        let instances = vec![instance("ami-2", "running"), instance("ami-4", "stopped")];
+
+        let entries = correlate_images(resources, images, &instances);
+
+        assert_that(&summary(&entries)).is_equal_to(vec![
+            (Some("elk"), Some("ami-6"), vec![ImageFlag::Unused]),
+            (Some("elk_elasticsearch"), Some("ami-2"), vec![]),
+            (Some("elk_elasticsearch"), Some("ami-1"), vec![ImageFlag::Unused]),
+            (Some("elk_kibana"), Some("ami-4"), vec![ImageFlag::Unused]),
+            (Some("mongodb"), None, vec![ImageFlag::NeverBaked]),
+            (Some("elk-kibana"), Some("ami-5"), vec![ImageFlag::Unused]),
+            (None, Some("ami-3"), vec![ImageFlag::NoResource, ImageFlag::Unused]),
+        ]);
+        assert_that(&entries[1].running_instances).is_equal_to(1);
+    }
+}
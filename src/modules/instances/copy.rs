@@ -0,0 +1,187 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::time::Duration;
+
+use config::CeresConfig as Config;
+use modules::*;
+use output::OutputType;
+use run_config::RunConfig;
+use utils::copy::{self, CopyOptions, Location, Tool, Transfer};
+use utils::run;
+
+pub const NAME: &str = "copy";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("copy files to or from instances")
+            .arg(
+                Arg::with_name("source")
+                    .required(true)
+                    .help("Copies from this 'local:<path>' or 'instance:<path>'"),
+            )
+            .arg(
+                Arg::with_name("destination")
+                    .required(true)
+                    .help("Copies to this 'local:<path>' or 'instance:<path>'"),
+            )
+            .arg(
+                Arg::with_name("instance_ids")
+                    .required_unless("filter")
+                    .multiple(true)
+                    .help("Copies to or from instances with these ids, 'name:<glob>', or 'tag:<key>=<value>'; or '-' to read json from stdin"),
+            )
+            .arg(
+                Arg::with_name("filter")
+                    .long("filter")
+                    .short("f")
+                    .takes_value(true)
+                    .help("Selects instances by filter expression; cf. instances list"),
+            )
+            .arg(
+                Arg::with_name("login-name")
+                    .long("login-name")
+                    .short("l")
+                    .takes_value(true)
+                    .help("Sets remote login name"),
+            )
+            .arg(
+                Arg::with_name("recursive")
+                    .long("recursive")
+                    .short("r")
+                    .help("Copies directories recursively"),
+            )
+            .arg(
+                Arg::with_name("rsync")
+                    .long("rsync")
+                    .help("Uses rsync instead of scp"),
+            )
+            .arg(
+                Arg::with_name("no-progress-bar")
+                    .long("no-progress-bar")
+                    .help("Do not show progressbar during copying"),
+            )
+            .arg(
+                Arg::with_name("fail-on-fail")
+                    .long("fail-on-fail")
+                    .help("Exits with code != 1 if copying fails for any instance"),
+            )
+            .arg(
+                Arg::with_name("public-ip")
+                    .short("p")
+                    .long("public-ip")
+                    .help("Uses public IP address of instance for connection"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json"])
+                    .help("Selects output format"),
+            )
+            .arg(
+                Arg::with_name("show-all")
+                    .long("show-all")
+                    .help("Show all copy results; by default show only results of failed copies"),
+            )
+            .arg(
+                Arg::with_name("ssh-opts")
+                    .long("ssh-opt")
+                    .multiple(true)
+                    .takes_value(true)
+                    .help("Passes an option to ssh; translated to scp where scp differs, e.g., -p to -P"),
+            )
+            .arg(
+                Arg::with_name("timeout")
+                    .long("timeout")
+                    .takes_value(true)
+                    .default_value("300")
+                    .help("Timeout in sec for copying to finish"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    let profile = match run_config.active_profile.as_ref() {
+        "default" => config.get_default_profile(),
+        s => config.get_profile(s),
+    }.chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    // Parse my args
+    let source: Location = args.value_of("source").unwrap() // Safe unwrap
+        .parse()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let destination: Location = args.value_of("destination").unwrap() // Safe unwrap
+        .parse()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let transfer = Transfer::new(source, destination)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let options = CopyOptions {
+        tool:      if args.is_present("rsync") { Tool::Rsync } else { Tool::Scp },
+        recursive: args.is_present("recursive"),
+    };
+
+    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
+    let public_ip = args.is_present("public-ip");
+    let login_name = args.value_of("login-name").map(ToOwned::to_owned).or_else(|| profile.ssh_user.clone());
+
+    let ssh_opts: Vec<&str> = args.values_of("ssh-opts").unwrap_or_else(Default::default).collect();
+
+    let timeout = Duration::from_secs(
+        args.value_of("timeout").unwrap() // safe unwrap
+        .parse()
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
+    );
+
+    let progress_bar = !args.is_present("no-progress-bar");
+    let fail_on_fail = args.is_present("fail-on-fail");
+
+    let show_all = args.is_present("show-all");
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    // Run me
+    info!("Querying description for instances.");
+    let instances = describe_selected_instances(&instance_ids, args.value_of("filter"), run_config, config)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    let ssh_opts = ssh_opts_with_bastion(run_config, config, &ssh_opts)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let ssh_opts: Vec<&str> = ssh_opts.iter().map(String::as_str).collect();
+
+    debug!("Building copy commands.");
+    let commands = copy::build_copy_command_to_instances(
+        &instances,
+        public_ip,
+        login_name.as_ref(),
+        &ssh_opts,
+        &transfer,
+        options,
+        timeout,
+    ).chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    copy::create_local_dirs(&instances, &transfer).chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    info!("Copying.");
+    let results = run::run(commands, progress_bar)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    run::output_results(output_type, show_all, results.as_slice())
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    if fail_on_fail && results.iter().any(|r| !r.exit_status.success()) {
+        return Err(Error::from_kind(ErrorKind::ModuleFailed(NAME.to_owned())));
+    }
+
+    Ok(())
+}
+
@@ -0,0 +1,106 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use modules::instances::list::{filter_instances, list_instances};
+use output::OutputType;
+use output::instances::{
+    JsonOutputCostSummaries,
+    OutputCostSummaries,
+    PlainOutputCostSummaries,
+    TableOutputCostSummaries,
+};
+use provider::pricing::{summarize_costs, CostSummary};
+
+pub const NAME: &str = "cost";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("Estimate costs of instances")
+            .arg(
+                Arg::with_name("filter")
+                    .long("filter")
+                    .short("f")
+                    .takes_value(true)
+                    .help("Filters instances by description fields"),
+            )
+            .arg(
+                Arg::with_name("group-by")
+                    .long("group-by")
+                    .short("g")
+                    .takes_value(true)
+                    .help("Groups costs by the value of a tag, e.g., 'Project'"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json", "plain"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Querying description for instances.");
+    let instances = list_instances(args, run_config, config, true)?;
+
+    info!("Filtering instance descriptions");
+    let instances = filter_instances(args, run_config, config, instances)?;
+
+    info!("Summarizing costs");
+    let summaries = summarize_costs(&instances, args.value_of("group-by"));
+
+    info!("Outputting cost summaries");
+    output_summaries(args, run_config, config, &summaries)?;
+
+    Ok(())
+}
+
+fn output_summaries(
+    args: &ArgMatches,
+    _: &RunConfig,
+    _: &Config,
+    summaries: &[CostSummary],
+) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let group_title = args.value_of("group-by").unwrap_or("Group").to_owned();
+            let output = TableOutputCostSummaries { group_title };
+
+            output
+                .output(&mut stdout, summaries)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputCostSummaries;
+
+            output
+                .output(&mut stdout, summaries)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => {
+            let output = PlainOutputCostSummaries;
+
+            output
+                .output(&mut stdout, summaries)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::Utc;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::{sync::Arc, time::Duration};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use output::OutputType;
+use output::instances::{JsonOutputImages, OutputImages, PlainOutputImages, TableOutputImages};
+use provider::{backup_tags, CreateImage, Image, Provider};
+use utils::cli::read_instance_ids;
+use utils::wait::wait_for;
+
+pub const NAME: &str = "create-image";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("create images (AMIs) of instances")
+            .arg(
+                Arg::with_name("instance_ids")
+                    .multiple(true)
+                    .required(true)
+                    .help("Instance Ids to create images of; or '-' to read json with instance ids from stdin"),
+            )
+            .arg(
+                Arg::with_name("name-prefix")
+                    .long("name-prefix")
+                    .takes_value(true)
+                    .default_value("backup")
+                    .help("Sets the prefix of the image names; names have the form <prefix>-<instance id>-<timestamp>"),
+            )
+            .arg(
+                Arg::with_name("no-reboot")
+                    .long("no-reboot")
+                    .help("Does not reboot the instances; the file system consistency of the images is not guaranteed"),
+            )
+            .arg(
+                Arg::with_name("dry")
+                    .long("dry")
+                    .short("d")
+                    .help("Makes a dry run without actually creating images"),
+            )
+            .arg(
+                Arg::with_name("wait")
+                    .long("wait")
+                    .conflicts_with("dry")
+                    .help("Waits until the images are available"),
+            )
+            .arg(
+                Arg::with_name("wait-timeout")
+                    .long("wait-timeout")
+                    .takes_value(true)
+                    .default_value("1800")
+                    .help("Timeout in sec for images to become available"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json", "plain"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Creating images.");
+    let images = create_images(args, run_config, config)?;
+
+    info!("Outputting images.");
+    output_images(args, run_config, config, &images)?;
+
+    Ok(())
+}
+
+fn create_images(
+    args: &ArgMatches,
+    run_config: &RunConfig,
+    config: &Config,
+) -> Result<Vec<Image>> {
+    let provider: Arc<dyn Provider> = Arc::from(get_provider(run_config, config)?);
+
+    let name_prefix = args.value_of("name-prefix").unwrap(); // Safe
+    let no_reboot = args.is_present("no-reboot");
+    let dry = args.is_present("dry");
+
+    if dry {
+        warn!("Running in dry mode -- no changes will be executed.");
+    }
+
+    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
+    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    let created_at = Utc::now();
+    let mut images = Vec::new();
+    for instance_id in &instance_ids {
+        let name = format!("{}-{}-{}", name_prefix, instance_id, created_at.format("%Y%m%d%H%M%S"));
+        let tags = backup_tags(instance_id, &created_at);
+        let image = provider
+            .create_image(dry, instance_id, &name, no_reboot, &tags)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        images.push(image);
+    }
+
+    if args.is_present("wait") {
+        let timeout = Duration::from_secs(
+            args.value_of("wait-timeout").unwrap() // safe unwrap
+            .parse()
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
+        );
+        let progress_bar = args.value_of("output") == Some("human");
+        let image_ids: Vec<_> = images.iter().map(|x| x.image_id.clone()).collect();
+
+        info!("Waiting for images to become available.");
+        let results = wait_for(&image_ids, "available", timeout, progress_bar, move |image_id| {
+            provider.image_state(image_id)
+        })
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        for (image, result) in images.iter_mut().zip(results) {
+            image.state = result.state;
+        }
+    }
+
+    Ok(images)
+}
+
+fn output_images(
+    args: &ArgMatches,
+    _: &RunConfig,
+    _: &Config,
+    images: &[Image],
+) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputImages;
+
+            output
+                .output(&mut stdout, images)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputImages;
+
+            output
+                .output(&mut stdout, images)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => {
+            let output = PlainOutputImages;
+
+            output
+                .output(&mut stdout, images)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use clap::{App, Arg, ArgMatches, SubCommand};
 
-use config::{CeresConfig as Config, Provider};
+use config::CeresConfig as Config;
 use run_config::RunConfig;
 use modules::*;
 use output::OutputType;
 use output::instances::{JsonOutputInstances, OutputInstances, PlainOutputInstances, TableOutputInstances};
 use provider::{DescribeInstances, InstanceDescriptor, InstanceDescriptorFields};
-use provider::filter;
+use provider::{filter, pricing::PriceTable, sort::SortBy};
 
 pub const NAME: &str = "list";
 
@@ -41,6 +41,12 @@ impl Module for SubModule {
                     )
                     .help("Selects the instance description fields to human output"),
             )
+            .arg(
+                Arg::with_name("sort-by")
+                    .long("sort-by")
+                    .takes_value(true)
+                    .help("Sorts instances by description field; append ':desc' for descending order"),
+            )
     }
 
     fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
@@ -51,10 +57,19 @@ impl Module for SubModule {
 
 fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
     info!("Querying description for instances.");
-    let instances = list_instances(args, run_config, config)?;
+    let with_costs = needs_costs(args, run_config)?;
+    let instances = list_instances(args, run_config, config, with_costs)?;
 
     info!("Filtering instance descriptions");
-    let instances = filter_instances(args, run_config, config, instances)?;
+    let mut instances = filter_instances(args, run_config, config, instances)?;
+
+    if let Some(sort_by) = args.value_of("sort-by") {
+        info!("Sorting instance descriptions");
+        let sort_by = sort_by
+            .parse::<SortBy>()
+            .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+        sort_by.sort(&mut instances);
+    }
 
     info!("Outputting instance descriptions");
     output_instances(args, run_config, config, &instances)?;
@@ -62,25 +77,73 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
     Ok(())
 }
 
-fn list_instances(
+/// Returns whether the estimated costs of instances are output, filtered, or sorted by; only then the price table is
+/// loaded.
+fn needs_costs(args: &ArgMatches, run_config: &RunConfig) -> Result<bool> {
+    let cost = InstanceDescriptorFields::Cost;
+    if args.value_of("output") == Some("json") || output_fields(args, run_config)?.contains(&cost) {
+        return Ok(true);
+    }
+    if let Some(filter_str) = args.value_of("filter") {
+        let filter = filter_str
+            .parse::<filter::Filter>()
+            .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+        if filter.uses_field(&cost) {
+            return Ok(true);
+        }
+    }
+    if let Some(sort_by) = args.value_of("sort-by") {
+        let sort_by = sort_by
+            .parse::<SortBy>()
+            .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+        if sort_by.field() == &cost {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Lists the instances of all selected profiles including their profile names and, if `with_costs` is set, their
+/// estimated costs.
+pub fn list_instances(
     _: &ArgMatches,
     run_config: &RunConfig,
     config: &Config,
+    with_costs: bool,
 ) -> Result<Vec<InstanceDescriptor>> {
-    let profile = match run_config.active_profile.as_ref() {
-        "default" => config.get_default_profile(),
-        s => config.get_profile(s),
-    }.chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
-    let Provider::Aws(provider) = profile.provider
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
-
-    provider
-        .describe_instances()
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+    let price_table = if with_costs {
+        let price_table = PriceTable::load(config.price_table.as_ref().map(String::as_str))
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        Some(price_table)
+    } else {
+        None
+    };
+
+    let cache = provider_cache(run_config, config);
+    let instances_by_profile = for_each_profile(run_config, config, move |profile_name, profile| {
+        let provider = get_profile_provider(profile_name, profile, &cache)?;
+        provider
+            .describe_instances()
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+    })?;
+
+    let instances = instances_by_profile
+        .into_iter()
+        .flat_map(|(profile_name, instances)| {
+            let price_table = &price_table;
+            instances.into_iter().map(move |mut instance| {
+                instance.profile = Some(profile_name.clone());
+                instance.cost = price_table.as_ref().and_then(|x| x.hourly_cost(&instance));
+                instance
+            })
+        })
+        .collect();
+
+    Ok(instances)
 }
 
-fn filter_instances(
+pub fn filter_instances(
     args: &ArgMatches,
     _: &RunConfig,
     _: &Config,
@@ -103,7 +166,7 @@ fn filter_instances(
 
 fn output_instances(
     args: &ArgMatches,
-    _: &RunConfig,
+    run_config: &RunConfig,
     _: &Config,
     instances: &[InstanceDescriptor],
 ) -> Result<()> {
@@ -114,12 +177,7 @@ fn output_instances(
 
     match output_type {
         OutputType::Human => {
-            let fields: ::std::result::Result<Vec<_>, _> = args.value_of("output-options").unwrap() // Safe unwrap
-                .split(',')
-                .map(|s| s.parse::<InstanceDescriptorFields>())
-                .collect();
-            let fields =
-                fields.map_err(|e| Error::with_chain(e, ErrorKind::ModuleFailed(NAME.to_owned())))?;
+            let fields = output_fields(args, run_config)?;
             let output = TableOutputInstances { fields };
 
             output
@@ -134,12 +192,7 @@ fn output_instances(
                 .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
         },
         OutputType::Plain => {
-            let fields: ::std::result::Result<Vec<_>, _> = args.value_of("output-options").unwrap() // Safe unwrap
-                .split(',')
-                .map(|s| s.parse::<InstanceDescriptorFields>())
-                .collect();
-            let fields =
-                fields.map_err(|e| Error::with_chain(e, ErrorKind::ModuleFailed(NAME.to_owned())))?;
+            let fields = output_fields(args, run_config)?;
             let output = PlainOutputInstances { fields };
 
             output
@@ -149,3 +202,19 @@ fn output_instances(
     }
 }
 
+fn output_fields(args: &ArgMatches, run_config: &RunConfig) -> Result<Vec<InstanceDescriptorFields>> {
+    let output_options = args.value_of("output-options").unwrap(); // Safe unwrap
+    // Instances of different profiles are indistinguishable without their profile
+    let output_options = if run_config.all_profiles && args.occurrences_of("output-options") == 0 {
+        format!("Profile,{}", output_options)
+    } else {
+        output_options.to_owned()
+    };
+
+    let fields: ::std::result::Result<Vec<_>, _> = output_options
+        .split(',')
+        .map(|s| s.parse::<InstanceDescriptorFields>())
+        .collect();
+
+    fields.map_err(|e| Error::with_chain(e, ErrorKind::ModuleFailed(NAME.to_owned())))
+}
@@ -1,2 +1,65 @@
-sub_module!("instances", "Do stuff with instances", list, run, ssh, start, stop, terminate);
+sub_module!("instances", "Do stuff with instances", copy, cost, create_image, list, reboot, resize, run, snapshot, ssh, start, stop, tag, terminate);
 
+use clams::console::ask_for_confirmation;
+use std::{sync::Arc, time::Duration};
+
+use provider::{describe_instances_by_ids, DescribeInstance, DescribeInstances, InstanceId, StateChange};
+use utils::wait::wait_for_state;
+
+/// Asks for confirmation to `action`, e.g., `terminate`, the instances `instance_ids` which are listed by id and Name
+/// tag, so selectors like `name:<glob>` can be checked before anything happens.
+fn confirm_instances<P: DescribeInstances + DescribeInstance + ?Sized>(
+    provider: &P,
+    action: &str,
+    instance_ids: &[InstanceId],
+) -> Result<bool> {
+    let instances = describe_instances_by_ids(provider, instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    let listing: Vec<_> = instances
+        .iter()
+        .map(|i| {
+            let name = i
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.get("Name"))
+                .and_then(|name| name.as_ref())
+                .map(String::as_str);
+            format!(
+                "  {}  {}",
+                i.instance_id.as_ref().map(String::as_str).unwrap_or("-"),
+                name.unwrap_or("-")
+            )
+        })
+        .collect();
+    let prompt = format!(
+        "Going to {} {} instances:\n{}\nPlease type 'yes' to continue: ",
+        action,
+        instances.len(),
+        listing.join("\n")
+    );
+
+    Ok(ask_for_confirmation(&prompt, "yes").unwrap())
+}
+
+/// Waits until the instances of `state_changes` have reached `target_state` or the `wait-timeout` has passed; progress
+/// bars are shown for human output.
+fn wait_for_instances(
+    args: &ArgMatches,
+    run_config: &RunConfig,
+    config: &Config,
+    state_changes: &[StateChange],
+    target_state: &str,
+) -> Result<()> {
+    let provider = get_provider(run_config, config)?;
+
+    let timeout = args.value_of("wait-timeout").unwrap() // Safe unwrap
+        .parse()
+        .map(Duration::from_secs)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    let progress_bar = args.value_of("output") == Some("human");
+    let instance_ids: Vec<_> = state_changes.iter().map(|x| x.instance_id.clone()).collect();
+
+    wait_for_state(Arc::from(provider), &instance_ids, target_state, timeout, progress_bar)
+        .map(|_| ())
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+}
@@ -0,0 +1,85 @@
+use clams::console::ask_for_confirmation;
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use provider::RebootInstances;
+use utils::cli::read_instance_ids;
+
+pub const NAME: &str = "reboot";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("reboot instances")
+            .arg(
+                Arg::with_name("instance_ids")
+                    .multiple(true)
+                    .required(true)
+                    .help("Instance Ids to reboot; or '-' to read json with instance ids from stdin"),
+            )
+            .arg(
+                Arg::with_name("dry")
+                    .long("dry")
+                    .short("d")
+                    .conflicts_with("yes")
+                    .help("Makes a dry run without actually rebooting the instances"),
+            )
+            .arg(
+                Arg::with_name("yes")
+                    .long("yes-i-really-really-mean-it")
+                    .conflicts_with("dry")
+                    .help("Don't ask me for veryification"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Rebooting instances.");
+    reboot_instances(args, run_config, config)
+}
+
+fn reboot_instances(
+    args: &ArgMatches,
+    run_config: &RunConfig,
+    config: &Config,
+) -> Result<()> {
+    let provider = get_provider(run_config, config)?;
+
+    let dry = args.is_present("dry");
+    let yes = args.is_present("yes");
+
+    match (dry, yes) {
+        (true, _) => {
+            warn!("Running in dry mode -- no changes will be executed.");
+        }
+        (false, false) => {
+            if !ask_for_confirmation("Going to reboot instances. Please type 'yes' to continue: ", "yes").unwrap()
+            {
+                return Err(Error::from_kind(ErrorKind::ModuleFailed(String::from(
+                    NAME,
+                ))));
+            }
+        }
+        (false, true) => {}
+    }
+
+    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
+    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    provider
+        .reboot_instances(dry, &instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    info!("Requested reboot of instances {}.", instance_ids.join(", "));
+
+    Ok(())
+}
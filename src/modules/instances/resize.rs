@@ -0,0 +1,219 @@
+use clams::console::ask_for_confirmation;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::{sync::Arc, time::Duration};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use output::OutputType;
+use output::instances::{JsonOutputStateChanges, OutputStateChanges, TableOutputStatusChanges};
+use provider::{DescribeInstance, ModifyInstanceType, Provider, StartInstances, StateChange, StopInstances};
+use utils::cli::read_instance_ids;
+use utils::wait::wait_for_state;
+
+pub const NAME: &str = "resize";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("change the instance type of instances")
+            .arg(
+                Arg::with_name("instance_ids")
+                    .multiple(true)
+                    .required(true)
+                    .help("Instance Ids to resize; or '-' to read json with instance ids from stdin"),
+            )
+            .arg(
+                Arg::with_name("type")
+                    .long("type")
+                    .short("t")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Sets the new instance type, e.g., 'm5.xlarge'"),
+            )
+            .arg(
+                Arg::with_name("dry")
+                    .long("dry")
+                    .short("d")
+                    .conflicts_with("yes")
+                    .help("Makes a dry run without actually resizing the instances"),
+            )
+            .arg(
+                Arg::with_name("wait-timeout")
+                    .long("wait-timeout")
+                    .takes_value(true)
+                    .default_value("300")
+                    .help("Timeout in sec for instances to stop and to start again"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json"])
+                    .help("Selects output format"),
+            )
+            .arg(
+                Arg::with_name("yes")
+                    .long("yes-i-really-really-mean-it")
+                    .conflicts_with("dry")
+                    .help("Don't ask me for veryification"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Resizing instances.");
+    let changes = resize_instances(args, run_config, config)?;
+
+    info!("Outputting instance state changes.");
+    output_changes(args, run_config, config, &changes)?;
+
+    Ok(())
+}
+
+/// Stops running instances, changes the instance type of all instances, and starts the formerly running instances
+/// again.
+///
+/// Instances that are not running in the first place stay stopped.
+fn resize_instances(
+    args: &ArgMatches,
+    run_config: &RunConfig,
+    config: &Config,
+) -> Result<Vec<StateChange>> {
+    let provider: Arc<dyn Provider> = Arc::from(get_provider(run_config, config)?);
+
+    let instance_type = args.value_of("type").unwrap(); // Safe
+    let dry = args.is_present("dry");
+    let yes = args.is_present("yes");
+
+    match (dry, yes) {
+        (true, _) => {
+            warn!("Running in dry mode -- no changes will be executed.");
+        }
+        (false, false) => {
+            let prompt = format!(
+                "Going to stop, resize to '{}', and start instances. Please type 'yes' to continue: ",
+                instance_type
+            );
+            if !ask_for_confirmation(&prompt, "yes").unwrap() {
+                return Err(Error::from_kind(ErrorKind::ModuleFailed(String::from(
+                    NAME,
+                ))));
+            }
+        }
+        (false, true) => {}
+    }
+
+    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
+    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    let timeout = Duration::from_secs(
+        args.value_of("wait-timeout").unwrap() // safe unwrap
+        .parse()
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
+    );
+    let progress_bar = args.value_of("output") == Some("human");
+
+    let mut running = Vec::new();
+    for instance_id in &instance_ids {
+        let instance = provider
+            .describe_instance(instance_id)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        if instance.state.as_ref().map(String::as_str) == Some("running") {
+            running.push(instance_id.to_owned());
+        }
+    }
+
+    if dry {
+        let changes = provider
+            .stop_instances(true, false, &instance_ids)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        for instance_id in &instance_ids {
+            info!("Checking change of instance type of {} to '{}'.", instance_id, instance_type);
+            provider
+                .modify_instance_type(true, instance_id, instance_type)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        }
+        return Ok(changes);
+    }
+
+    if !running.is_empty() {
+        info!("Stopping instances {}.", running.join(", "));
+        provider
+            .stop_instances(false, false, &running)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        wait_for_state(Arc::clone(&provider), &running, "stopped", timeout, progress_bar)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    }
+
+    // Keep going if changing the type of an instance fails, so all formerly running instances are started again.
+    let mut failed = Vec::new();
+    for instance_id in &instance_ids {
+        info!("Changing instance type of {} to '{}'.", instance_id, instance_type);
+        if let Err(e) = provider.modify_instance_type(false, instance_id, instance_type) {
+            let cause = e.iter().last().map(|x| x.to_string()).unwrap_or_default();
+            error!("Failed to change instance type of {} because {}", instance_id, cause);
+            failed.push(instance_id.to_owned());
+        }
+    }
+
+    let changes = if running.is_empty() {
+        Vec::new()
+    } else {
+        info!("Starting instances {}.", running.join(", "));
+        let changes = provider
+            .start_instances(false, &running)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        wait_for_state(provider, &running, "running", timeout, progress_bar)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        changes
+    };
+
+    if !failed.is_empty() {
+        return Err(Error::from_kind(ErrorKind::ResizeFailed(failed.join(", "))));
+    }
+
+    Ok(changes)
+}
+
+fn output_changes(
+    args: &ArgMatches,
+    _: &RunConfig,
+    _: &Config,
+    state_changes: &[StateChange],
+) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputStatusChanges {};
+
+            output
+                .output(&mut stdout, state_changes)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputStateChanges;
+
+            output
+                .output(&mut stdout, state_changes)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => {
+            unimplemented!("'Plain' output is not supported for this module");
+        }
+    }
+}
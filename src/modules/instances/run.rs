@@ -1,13 +1,17 @@
+use chrono::Utc;
 use clap::{App, Arg, ArgMatches, SubCommand};
-use std::time::Duration;
+use std::{
+    fs::File,
+    path::{Path, MAIN_SEPARATOR},
+    time::Duration,
+};
 
-use config::{CeresConfig as Config, Profile, Provider};
+use config::CeresConfig as Config;
 use modules::*;
 use output::OutputType;
-use provider::{DescribeInstance, InstanceDescriptor};
+use provider::InstanceDescriptor;
 use run_config::RunConfig;
-use utils::cli::read_instance_ids;
-use utils::run;
+use utils::run::{self, RollingResults};
 use utils::ssh;
 
 pub const NAME: &str = "run";
@@ -20,9 +24,23 @@ impl Module for SubModule {
             .about("run command on instances")
             .arg(
                 Arg::with_name("instance_ids")
-                    .required(true)
+                    .required_unless_one(&["filter", "retry-failed-from"])
                     .multiple(true)
-                    .help("Runs command on instances with these instance id; or '-' to read json with instance ids from stdin"),
+                    .help("Runs command on instances with these ids, 'name:<glob>', or 'tag:<key>=<value>'; or '-' to read json from stdin"),
+            )
+            .arg(
+                Arg::with_name("filter")
+                    .long("filter")
+                    .short("f")
+                    .takes_value(true)
+                    .help("Selects instances by filter expression; cf. instances list"),
+            )
+            .arg(
+                Arg::with_name("retry-failed-from")
+                    .long("retry-failed-from")
+                    .takes_value(true)
+                    .conflicts_with("instance_ids")
+                    .help("Runs command on the failed instances of these JSON results or this recorded run"),
             )
             .arg(
                 Arg::with_name("command_args")
@@ -47,6 +65,11 @@ impl Module for SubModule {
                     .long("fail-on-fail")
                     .help("Exits with code != 1 if any remote command fails"),
             )
+            .arg(
+                Arg::with_name("stream")
+                    .long("stream")
+                    .help("Prints output lines live to stderr prefixed by instance id instead of a progress bar"),
+            )
             .arg(
                 Arg::with_name("public-ip")
                     .short("p")
@@ -81,6 +104,8 @@ impl Module for SubModule {
                     .default_value("300")
                     .help("Timeout in sec for command to finish"),
             )
+            .args(&retries_args())
+            .args(&rolling_args())
     }
 
     fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
@@ -96,9 +121,14 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
     }.chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
 
     // Parse my args
-    let instance_ids: Vec<&str> =  args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
-    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    let failed_ids = match args.value_of("retry-failed-from") {
+        Some(source) => Some(read_failed_ids(source, config)?),
+        None => None,
+    };
+    let instance_ids: Vec<&str> = match failed_ids {
+        Some(ref ids) => ids.iter().map(String::as_str).collect(),
+        None => args.values_of("instance_ids").unwrap_or_else(Default::default).collect(),
+    };
     let public_ip = args.is_present("public-ip");
 
     let ssh_opts: Vec<&str> = args.values_of("ssh-opts").unwrap_or_else(Default::default).collect();
@@ -110,8 +140,15 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
         .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
     );
 
+    let retries = retries_from_args(args)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
     let progress_bar = !args.is_present("no-progress-bar");
+    let stream = args.is_present("stream");
     let fail_on_fail = args.is_present("fail-on-fail");
+    let rolling = rolling_from_args(args)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let gate_command = args.value_of("gate-command");
 
     let show_all = args.is_present("show-all");
     let output_type = args.value_of("output").unwrap() // Safe
@@ -119,20 +156,59 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
         .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
 
     // Run me
-    info!("Querying description for instances.");
-    let instances = describe_instances(&instance_ids, &profile)?;
+    if instance_ids.is_empty() && failed_ids.is_some() {
+        info!("No failed instances to retry.");
+        return Ok(());
+    }
 
-    debug!("Building ssh commands.");
-    let commands = ssh::build_ssh_command_to_instances(&instances, public_ip, profile.ssh_user.as_ref(), &ssh_opts, &remote_commands_args, timeout)
+    info!("Querying description for instances.");
+    let instances = describe_selected_instances(&instance_ids, args.value_of("filter"), run_config, config)
         .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
 
+    let ssh_opts = ssh_opts_with_bastion(run_config, config, &ssh_opts)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let ssh_opts: Vec<&str> = ssh_opts.iter().map(String::as_str).collect();
+
     info!("Running commands.");
-    let results = run::run(commands, progress_bar)
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    let started = Utc::now();
+    let RollingResults { results, skipped, error } = run::run_in_batches(
+        &instances,
+        &rolling,
+        |batch| {
+            debug!("Building ssh commands.");
+            let commands = ssh::build_ssh_command_to_instances(
+                batch,
+                public_ip,
+                profile.ssh_user.as_ref(),
+                &ssh_opts,
+                &remote_commands_args,
+                timeout,
+            )?;
+            run::run_with_retries(commands, &retries, |commands| {
+                if stream {
+                    run::run_with_stream(commands)
+                } else {
+                    run::run(commands, progress_bar)
+                }
+            })
+        },
+        |batch, _| match gate_command {
+            Some(command) => run::run_gate_command(command, &instance_ids_of(batch)),
+            None => Ok(()),
+        },
+    );
+    record_run(run_config, config, started, &results);
 
     run::output_results(output_type, show_all, results.as_slice())
         .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    run::output_skipped(&instance_ids_of(skipped));
 
+    if let Some(e) = error {
+        return Err(Error::with_chain(e, ErrorKind::ModuleFailed(NAME.to_owned())));
+    }
+    if !skipped.is_empty() {
+        return Err(Error::from_kind(ErrorKind::ModuleFailed(NAME.to_owned())));
+    }
     if fail_on_fail && results.iter().any(|r| !r.exit_status.success()) {
         return Err(Error::from_kind(ErrorKind::ModuleFailed(NAME.to_owned())));
     }
@@ -140,16 +216,27 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
     Ok(())
 }
 
-fn describe_instances(instance_ids: &[String], profile: &Profile) -> Result<Vec<InstanceDescriptor>> {
-    let Provider::Aws(provider) = profile.provider
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
-    let res: Result<Vec<InstanceDescriptor>> = instance_ids.iter().
-        map(|id| provider
-            .describe_instance(id)
-            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
-        ).collect();
-
-    res
+/// Reads the ids of the instances whose commands failed from a file with JSON results as output by `--output json` or
+/// from the recorded run `source`; cf. `runs`.
+fn read_failed_ids(source: &str, config: &Config) -> Result<Vec<String>> {
+    let results = if Path::new(source).is_file() {
+        let file = File::open(source).chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+        run::read_results(file).chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?
+    } else if looks_like_path(source) {
+        return Err(Error::from_kind(ErrorKind::NoSuchResultsFile(source.to_owned())));
+    } else {
+        run_history(config)
+            .get(source)
+            .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?
+            .results()
+    };
+
+    Ok(run::failed_ids(&results).into_iter().map(ToOwned::to_owned).collect())
 }
 
+/// Run ids neither contain a path separator nor end in `.json`, so such a `source` is meant as results file.
+fn looks_like_path(source: &str) -> bool { source.contains(MAIN_SEPARATOR) || source.ends_with(".json") }
+
+fn instance_ids_of(instances: &[InstanceDescriptor]) -> Vec<&str> {
+    instances.iter().flat_map(|i| i.instance_id.as_ref()).map(String::as_str).collect()
+}
@@ -0,0 +1,157 @@
+use chrono::Utc;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::{sync::Arc, time::Duration};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use output::OutputType;
+use output::instances::{JsonOutputSnapshots, OutputSnapshots, PlainOutputSnapshots, TableOutputSnapshots};
+use provider::{backup_tags, CreateSnapshots, Provider, Snapshot};
+use utils::cli::read_instance_ids;
+use utils::wait::wait_for;
+
+pub const NAME: &str = "snapshot";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("snapshot all volumes of instances")
+            .arg(
+                Arg::with_name("instance_ids")
+                    .multiple(true)
+                    .required(true)
+                    .help("Instance Ids to snapshot; or '-' to read json with instance ids from stdin"),
+            )
+            .arg(
+                Arg::with_name("dry")
+                    .long("dry")
+                    .short("d")
+                    .help("Makes a dry run without actually creating snapshots"),
+            )
+            .arg(
+                Arg::with_name("wait")
+                    .long("wait")
+                    .conflicts_with("dry")
+                    .help("Waits until the snapshots are completed"),
+            )
+            .arg(
+                Arg::with_name("wait-timeout")
+                    .long("wait-timeout")
+                    .takes_value(true)
+                    .default_value("1800")
+                    .help("Timeout in sec for snapshots to complete"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json", "plain"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Creating snapshots.");
+    let snapshots = create_snapshots(args, run_config, config)?;
+
+    info!("Outputting snapshots.");
+    output_snapshots(args, run_config, config, &snapshots)?;
+
+    Ok(())
+}
+
+fn create_snapshots(
+    args: &ArgMatches,
+    run_config: &RunConfig,
+    config: &Config,
+) -> Result<Vec<Snapshot>> {
+    let provider: Arc<dyn Provider> = Arc::from(get_provider(run_config, config)?);
+
+    let dry = args.is_present("dry");
+
+    if dry {
+        warn!("Running in dry mode -- no changes will be executed.");
+    }
+
+    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
+    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    let created_at = Utc::now();
+    let mut snapshots = Vec::new();
+    for instance_id in &instance_ids {
+        let tags = backup_tags(instance_id, &created_at);
+        let instance_snapshots = provider
+            .create_snapshots(dry, instance_id, &tags)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        snapshots.extend(instance_snapshots);
+    }
+
+    if args.is_present("wait") {
+        let timeout = Duration::from_secs(
+            args.value_of("wait-timeout").unwrap() // safe unwrap
+            .parse()
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
+        );
+        let progress_bar = args.value_of("output") == Some("human");
+        let snapshot_ids: Vec<_> = snapshots.iter().map(|x| x.snapshot_id.clone()).collect();
+
+        info!("Waiting for snapshots to complete.");
+        let results = wait_for(&snapshot_ids, "completed", timeout, progress_bar, move |snapshot_id| {
+            provider.snapshot_state(snapshot_id)
+        })
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        for (snapshot, result) in snapshots.iter_mut().zip(results) {
+            snapshot.state = result.state;
+        }
+    }
+
+    Ok(snapshots)
+}
+
+fn output_snapshots(
+    args: &ArgMatches,
+    _: &RunConfig,
+    _: &Config,
+    snapshots: &[Snapshot],
+) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputSnapshots;
+
+            output
+                .output(&mut stdout, snapshots)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputSnapshots;
+
+            output
+                .output(&mut stdout, snapshots)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => {
+            let output = PlainOutputSnapshots;
+
+            output
+                .output(&mut stdout, snapshots)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use clap::{App, Arg, ArgMatches, SubCommand};
 use std::net::IpAddr;
 
-use config::{CeresConfig as Config, Provider};
+use config::CeresConfig as Config;
 use run_config::RunConfig;
 use modules::*;
-use provider::{DescribeInstance, InstanceDescriptor};
+use provider::{describe_instances_by_ids, DescribeInstance, DescribeInstances, InstanceDescriptor};
 use utils;
+use utils::cli::ask_for_choice;
+use utils::picker::pick;
 
 pub const NAME: &str = "ssh";
 
@@ -17,8 +19,14 @@ impl Module for SubModule {
             .about("SSH to an instance")
             .arg(
                 Arg::with_name("instance_id")
-                    .required(true)
-                    .help("Connects to the instance with this instance id"),
+                    .help("Selects the instance by id, 'name:<glob>', or 'tag:<key>=<value>'; picks one if omitted"),
+            )
+            .arg(
+                Arg::with_name("filter")
+                    .long("filter")
+                    .short("f")
+                    .takes_value(true)
+                    .help("Selects the instance by filter expression; picks one if several match"),
             )
             .arg(
                 Arg::with_name("command_args")
@@ -67,19 +75,85 @@ fn describe_instance(
     run_config: &RunConfig,
     config: &Config,
 ) -> Result<InstanceDescriptor> {
-    let profile = match run_config.active_profile.as_ref() {
-        "default" => config.get_default_profile(),
-        s => config.get_profile(s),
-    }.chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
-    let Provider::Aws(provider) = profile.provider
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
+    let provider = get_provider(run_config, config)?;
+
+    let selectors: Vec<&str> = args.value_of("instance_id").into_iter().collect();
+    let filter = args.value_of("filter");
+    let instances = if selectors.is_empty() && filter.is_none() {
+        let mut instances: Vec<_> = provider
+            .describe_instances()
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
+            .into_iter()
+            .filter(|i| i.state.as_ref().map(|s| s != "terminated").unwrap_or(true))
+            .collect();
+        instances.sort_by(|a, b| name_of(a).cmp(&name_of(b)));
+        instances
+    } else {
+        let instance_ids = select_instance_ids(provider.as_ref(), &selectors, filter)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        if instance_ids.len() == 1 {
+            return provider
+                .describe_instance(&instance_ids[0])
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)));
+        }
+        describe_instances_by_ids(provider.as_ref(), &instance_ids)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
+    };
+
+    pick_instance(instances)
+}
+
+fn pick_instance(mut instances: Vec<InstanceDescriptor>) -> Result<InstanceDescriptor> {
+    let columns: Vec<[&str; 5]> = instances
+        .iter()
+        .map(|i| {
+            [
+                name_of(i).unwrap_or("-"),
+                i.instance_id.as_ref().map(String::as_str).unwrap_or("-"),
+                i.private_ip_address.as_ref().map(String::as_str).unwrap_or("-"),
+                i.instance_type.as_ref().map(String::as_str).unwrap_or("-"),
+                i.state.as_ref().map(String::as_str).unwrap_or("-"),
+            ]
+        })
+        .collect();
+    let mut widths = [0; 5];
+    for row in &columns {
+        for (width, column) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(column.chars().count());
+        }
+    }
+    let choices: Vec<_> = columns
+        .iter()
+        .map(|row| {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(column, width)| format!("{:width$}", column, width = width))
+                .collect();
+            cells.join("  ").trim_end().to_owned()
+        })
+        .collect();
 
-    let instance_id = args.value_of("instance_id").unwrap(); // safe
+    let prompt = "Instance to connect to";
+    let picked = match pick(prompt, &choices) {
+        // Without a terminal, ask for the number of the instance instead
+        Err(utils::Error(utils::ErrorKind::NoTerminal, _)) => {
+            Some(ask_for_choice(prompt, &choices).chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?)
+        }
+        picked => picked.chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?,
+    };
+    let index = picked.ok_or_else(|| Error::from_kind(ErrorKind::Cancelled))?;
 
-    provider
-        .describe_instance(instance_id)
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+    Ok(instances.swap_remove(index))
+}
+
+fn name_of(instance: &InstanceDescriptor) -> Option<&str> {
+    instance
+        .tags
+        .as_ref()
+        .and_then(|tags| tags.get("Name"))
+        .and_then(|name| name.as_ref())
+        .map(String::as_str)
 }
 
 fn ssh_to_instance(
@@ -102,11 +176,9 @@ fn ssh_to_instance(
     let command = args.values_of("command_args")
         .map(|x| x.collect::<Vec<_>>().join(" "));
 
-    let mut ssh_opts: Vec<_> = args.values_of("ssh-opts")
-        .map(|x| x
-             .map(|s| s.to_owned())
-             .collect::<Vec<_>>())
-        .unwrap_or_else(Vec::new);
+    let ssh_opts: Vec<&str> = args.values_of("ssh-opts").unwrap_or_else(Default::default).collect();
+    let mut ssh_opts = ssh_opts_with_bastion(run_config, config, &ssh_opts)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
 
     if let Some(ref login_name) = profile.ssh_user {
         ssh_opts.insert(0, "-l".to_owned());
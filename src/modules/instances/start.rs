@@ -1,12 +1,11 @@
 use clap::{App, Arg, ArgMatches, SubCommand};
 
-use config::{CeresConfig as Config, Provider};
+use config::CeresConfig as Config;
 use run_config::RunConfig;
 use modules::*;
 use output::OutputType;
 use output::instances::{JsonOutputStateChanges, OutputStateChanges, TableOutputStatusChanges};
 use provider::{StateChange, StartInstances};
-use utils::cli::read_instance_ids;
 
 pub const NAME: &str = "start";
 
@@ -19,8 +18,15 @@ impl Module for SubModule {
             .arg(
                 Arg::with_name("instance_ids")
                     .multiple(true)
-                    .required(true)
-                    .help("Instance Ids to start; or '-' to read json with instance ids from stdin"),
+                    .required_unless("filter")
+                    .help("Instances to start by id, 'name:<glob>', or 'tag:<key>=<value>'; '-' reads json"),
+            )
+            .arg(
+                Arg::with_name("filter")
+                    .long("filter")
+                    .short("f")
+                    .takes_value(true)
+                    .help("Selects instances by filter expression; cf. instances list"),
             )
             .arg(
                 Arg::with_name("dry")
@@ -28,6 +34,19 @@ impl Module for SubModule {
                     .short("d")
                     .help("Makes a dry run without actually starting the instances"),
             )
+            .arg(
+                Arg::with_name("wait")
+                    .long("wait")
+                    .conflicts_with("dry")
+                    .help("Waits until the instances are running"),
+            )
+            .arg(
+                Arg::with_name("wait-timeout")
+                    .long("wait-timeout")
+                    .takes_value(true)
+                    .default_value("300")
+                    .help("Timeout in sec for instances to reach their target state"),
+            )
             .arg(
                 Arg::with_name("output")
                     .long("output")
@@ -52,6 +71,11 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
     info!("Outputting instance state changes.");
     output_changes(args, run_config, config, &changes)?;
 
+    if args.is_present("wait") {
+        info!("Waiting for instances to become running.");
+        super::wait_for_instances(args, run_config, config, &changes, "running")?;
+    }
+
     Ok(())
 }
 
@@ -60,13 +84,7 @@ fn start_instances(
     run_config: &RunConfig,
     config: &Config,
 ) -> Result<Vec<StateChange>> {
-    let profile = match run_config.active_profile.as_ref() {
-        "default" => config.get_default_profile(),
-        s => config.get_profile(s),
-    }.chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
-    let Provider::Aws(provider) = profile.provider
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
+    let provider = get_provider(run_config, config)?;
 
     let dry = args.is_present("dry");
 
@@ -75,7 +93,7 @@ fn start_instances(
     }
 
     let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
-    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
+    let instance_ids = select_instance_ids(provider.as_ref(), &instance_ids, args.value_of("filter"))
         .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
 
     provider
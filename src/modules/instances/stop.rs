@@ -1,13 +1,11 @@
-use clams::console::ask_for_confirmation;
 use clap::{App, Arg, ArgMatches, SubCommand};
 
-use config::{CeresConfig as Config, Provider};
+use config::CeresConfig as Config;
 use run_config::RunConfig;
 use modules::*;
 use output::OutputType;
 use output::instances::{JsonOutputStateChanges, OutputStateChanges, TableOutputStatusChanges};
 use provider::{StateChange, StopInstances};
-use utils::cli::read_instance_ids;
 
 pub const NAME: &str = "stop";
 
@@ -20,8 +18,15 @@ impl Module for SubModule {
             .arg(
                 Arg::with_name("instance_ids")
                     .multiple(true)
-                    .required(true)
-                    .help("Instance Ids to stop; or '-' to read json with instance ids from stdin"),
+                    .required_unless("filter")
+                    .help("Instances to stop by id, 'name:<glob>', or 'tag:<key>=<value>'; '-' reads json"),
+            )
+            .arg(
+                Arg::with_name("filter")
+                    .long("filter")
+                    .short("f")
+                    .takes_value(true)
+                    .help("Selects instances by filter expression; cf. instances list"),
             )
             .arg(
                 Arg::with_name("dry")
@@ -35,6 +40,19 @@ impl Module for SubModule {
                     .long("force")
                     .help("Forces the instances to stop. The instances do not have an opportunity to flush file system caches or file system metadata."),
             )
+            .arg(
+                Arg::with_name("wait")
+                    .long("wait")
+                    .conflicts_with("dry")
+                    .help("Waits until the instances are stopped"),
+            )
+            .arg(
+                Arg::with_name("wait-timeout")
+                    .long("wait-timeout")
+                    .takes_value(true)
+                    .default_value("300")
+                    .help("Timeout in sec for instances to reach their target state"),
+            )
             .arg(
                 Arg::with_name("output")
                     .long("output")
@@ -65,6 +83,11 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
     info!("Outputting instance state changes.");
     output_changes(args, run_config, config, &changes)?;
 
+    if args.is_present("wait") {
+        info!("Waiting for instances to become stopped.");
+        super::wait_for_instances(args, run_config, config, &changes, "stopped")?;
+    }
+
     Ok(())
 }
 
@@ -73,13 +96,7 @@ fn stop_instances(
     run_config: &RunConfig,
     config: &Config,
 ) -> Result<Vec<StateChange>> {
-    let profile = match run_config.active_profile.as_ref() {
-        "default" => config.get_default_profile(),
-        s => config.get_profile(s),
-    }.chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
-    let Provider::Aws(provider) = profile.provider
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
+    let provider = get_provider(run_config, config)?;
 
     let dry = args.is_present("dry");
     let force = args.is_present("force");
@@ -89,13 +106,16 @@ fn stop_instances(
         warn!("Going to force stopping instances -- file system caches will not be flushed.");
     }
 
+    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
+    let instance_ids = select_instance_ids(provider.as_ref(), &instance_ids, args.value_of("filter"))
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
     match (dry, yes) {
         (true, _) => {
             warn!("Running in dry mode -- no changes will be executed.");
         }
         (false, false) => {
-            if !ask_for_confirmation("Going to stop instances. Please type 'yes' to continue: ", "yes").unwrap()
-            {
+            if !super::confirm_instances(provider.as_ref(), "stop", &instance_ids)? {
                 return Err(Error::from_kind(ErrorKind::ModuleFailed(String::from(
                     NAME,
                 ))));
@@ -104,10 +124,6 @@ fn stop_instances(
         (false, true) => {}
     }
 
-    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
-    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
-
     provider
         .stop_instances(dry, force, &instance_ids)
         .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
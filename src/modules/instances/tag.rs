@@ -0,0 +1,228 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::collections::{BTreeMap, HashMap};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use output::OutputType;
+use output::instances::{JsonOutputTagChanges, OutputTagChanges, TableOutputTagChanges};
+use provider::{describe_instances_by_ids, CreateTags, DeleteTags, InstanceId, TagChange};
+use utils::cli::read_instance_ids;
+
+pub const NAME: &str = "tag";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("add, change, or remove tags of instances")
+            .arg(
+                Arg::with_name("instance_ids")
+                    .multiple(true)
+                    .required(true)
+                    .help("Instance Ids to tag; or '-' to read json with instance ids from stdin"),
+            )
+            .arg(
+                Arg::with_name("set")
+                    .long("set")
+                    .short("s")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .validator(is_valid_tag)
+                    .required_unless("unset")
+                    .help("Sets tag in the form <key>=<value>; may be used multiple times"),
+            )
+            .arg(
+                Arg::with_name("unset")
+                    .long("unset")
+                    .short("u")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Removes tag by key; may be used multiple times"),
+            )
+            .arg(
+                Arg::with_name("no-overwrite")
+                    .long("no-overwrite")
+                    .help("Only adds tags that are not set yet"),
+            )
+            .arg(
+                Arg::with_name("dry")
+                    .long("dry")
+                    .short("d")
+                    .help("Makes a dry run showing the changes without actually changing the tags"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Tagging instances.");
+    let changes = tag_instances(args, run_config, config)?;
+
+    info!("Outputting tag changes.");
+    output_changes(args, run_config, config, &changes)?;
+
+    Ok(())
+}
+
+fn tag_instances(
+    args: &ArgMatches,
+    run_config: &RunConfig,
+    config: &Config,
+) -> Result<Vec<TagChange>> {
+    let provider = get_provider(run_config, config)?;
+
+    let dry = args.is_present("dry");
+    let no_overwrite = args.is_present("no-overwrite");
+
+    if dry {
+        warn!("Running in dry mode -- no changes will be executed.");
+    }
+
+    let set: Vec<(String, String)> = args
+        .values_of("set")
+        .unwrap_or_else(Default::default)
+        .map(parse_tag)
+        .collect();
+    let unset: Vec<String> = args
+        .values_of("unset")
+        .unwrap_or_else(Default::default)
+        .map(String::from)
+        .collect();
+    let conflicting: Vec<_> = set
+        .iter()
+        .map(|&(ref key, _)| key.as_str())
+        .filter(|key| unset.iter().any(|x| x == key))
+        .collect();
+    if !conflicting.is_empty() {
+        return Err(Error::from_kind(ErrorKind::ConflictingTagChanges(conflicting.join(", "))));
+    }
+
+    let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
+    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    let instances = describe_instances_by_ids(provider.as_ref(), &instance_ids)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+
+    // Instances with the same changes are tagged together to save provider calls.
+    let mut sets: BTreeMap<Vec<(String, String)>, Vec<InstanceId>> = BTreeMap::new();
+    let mut unsets: BTreeMap<Vec<String>, Vec<InstanceId>> = BTreeMap::new();
+    let mut changes = Vec::new();
+    for (instance_id, instance) in instance_ids.into_iter().zip(instances) {
+        let tags = instance.tags.unwrap_or_else(HashMap::new);
+        let current_value = |key: &str| tags.get(key).map(|x| x.clone().unwrap_or_else(String::new));
+
+        let mut to_set = Vec::new();
+        for &(ref key, ref value) in &set {
+            let previous_value = current_value(key);
+            if previous_value.as_ref() == Some(value) || (no_overwrite && previous_value.is_some()) {
+                continue;
+            }
+            to_set.push((key.clone(), value.clone()));
+            changes.push(TagChange {
+                instance_id:    instance_id.clone(),
+                key:            key.clone(),
+                previous_value,
+                value:          Some(value.clone()),
+            });
+        }
+
+        let mut to_unset = Vec::new();
+        for key in &unset {
+            if let Some(previous_value) = current_value(key) {
+                to_unset.push(key.clone());
+                changes.push(TagChange {
+                    instance_id:    instance_id.clone(),
+                    key:            key.clone(),
+                    previous_value: Some(previous_value),
+                    value:          None,
+                });
+            }
+        }
+
+        if !to_set.is_empty() {
+            to_set.sort();
+            sets.entry(to_set).or_insert_with(Vec::new).push(instance_id.clone());
+        }
+        if !to_unset.is_empty() {
+            to_unset.sort();
+            unsets.entry(to_unset).or_insert_with(Vec::new).push(instance_id);
+        }
+    }
+
+    for (to_set, instance_ids) in sets {
+        let to_set: HashMap<_, _> = to_set.into_iter().collect();
+        provider
+            .create_tags(dry, &instance_ids, &to_set)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    }
+    for (to_unset, instance_ids) in unsets {
+        provider
+            .delete_tags(dry, &instance_ids, &to_unset)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+    }
+
+    Ok(changes)
+}
+
+fn is_valid_tag(tag: String) -> ::std::result::Result<(), String> {
+    match tag.find('=') {
+        Some(0) => Err(format!("The tag '{}' has an empty key.", tag)),
+        Some(_) => Ok(()),
+        None => Err(format!("The tag '{}' is not of the form <key>=<value>.", tag)),
+    }
+}
+
+/// Splits a tag at the first '='; the format has already been checked by `is_valid_tag`.
+fn parse_tag(tag: &str) -> (String, String) {
+    let i = tag.find('=').unwrap(); // Safe
+    (tag[..i].to_owned(), tag[i + 1..].to_owned())
+}
+
+fn output_changes(
+    args: &ArgMatches,
+    _: &RunConfig,
+    _: &Config,
+    tag_changes: &[TagChange],
+) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputTagChanges {};
+
+            output
+                .output(&mut stdout, tag_changes)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputTagChanges;
+
+            output
+                .output(&mut stdout, tag_changes)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => {
+            unimplemented!("'Plain' output is not supported for this module");
+        }
+    }
+}
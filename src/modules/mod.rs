@@ -1,19 +1,415 @@
-use clap::{App, ArgMatches};
-use config::CeresConfig as Config;
+use chrono::{DateTime, Utc};
+use clap::{App, Arg, ArgMatches};
+use config::{CeresConfig as Config, Profile, Provider as ProviderConfig};
+use history::RunHistory;
+use provider::{
+    cache::ProviderCache,
+    describe_instances_by_ids,
+    filter::Filter,
+    selector::{self, InstanceSelector},
+    DescribeInstances,
+    InstanceDescriptor,
+    InstanceId,
+    Provider,
+};
 use run_config::RunConfig;
+use serde_json;
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+use utils::{
+    cli::read_instance_ids_from,
+    command::CommandResult,
+    run::{Retries, Rolling},
+    ssh,
+};
 
 pub trait Module {
     fn build_sub_cli() -> App<'static, 'static>;
     fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()>;
 }
 
+/// Looks up the provider backend configured for the active profile.
+///
+/// Instance descriptions are cached on disk if enabled; cf. `provider::cache`.
+pub fn get_provider(run_config: &RunConfig, config: &Config) -> Result<Box<dyn Provider>> {
+    let profile_name = run_config.active_profile_name(config);
+    let profile = config
+        .get_profile(profile_name)
+        .chain_err(|| ErrorKind::ProfileFailed(profile_name.to_owned()))?;
+
+    get_profile_provider(profile_name, profile, &provider_cache(run_config, config))
+}
+
+/// Looks up the provider backend configured for `profile` and wraps it by `cache` if the cache is enabled.
+///
+/// This allows to obtain providers of multiple profiles, e.g., in `for_each_profile`. Inventory files are local
+/// already and thus, never cached.
+pub fn get_profile_provider(profile_name: &str, profile: &Profile, cache: &ProviderCache) -> Result<Box<dyn Provider>> {
+    let provider_config = profile
+        .provider
+        .as_ref()
+        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
+    let provider = provider_config.backend();
+
+    match *provider_config {
+        ProviderConfig::Inventory(_) => Ok(provider),
+        _ if cache.is_enabled() => Ok(Box::new(cache.wrap(&cache_key(cache, profile_name, provider_config), provider))),
+        _ if cache.offline => Err(Error::from_kind(ErrorKind::CacheDisabled)),
+        _ => Ok(provider),
+    }
+}
+
+/// Returns the cache settings for this run.
+pub fn provider_cache(run_config: &RunConfig, config: &Config) -> ProviderCache {
+    let dir = config
+        .cache
+        .dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(default_cache_dir);
+
+    ProviderCache {
+        dir,
+        config:  run_config.active_config.to_path_buf(),
+        ttl:     Duration::from_secs(config.cache.ttl),
+        refresh: run_config.refresh,
+        offline: run_config.offline,
+    }
+}
+
+/// Invalidates the cached instance descriptions of the active profile, e.g., after a command changed instances
+/// without the provider.
+pub fn invalidate_provider_cache(run_config: &RunConfig, config: &Config) {
+    let cache = provider_cache(run_config, config);
+    let profile_name = run_config.active_profile_name(config);
+    let provider_config = config.get_profile(profile_name).ok().and_then(|x| x.provider.as_ref());
+    if let Some(provider_config) = provider_config {
+        cache.invalidate(&cache_key(&cache, profile_name, provider_config));
+    }
+}
+
+/// Derives the cache key from the configuration file, the profile name, and the provider configuration, so profiles
+/// of different configuration files or a changed provider configuration never share cached instance descriptions.
+fn cache_key(cache: &ProviderCache, profile_name: &str, provider_config: &ProviderConfig) -> String {
+    let config_path = cache.config.canonicalize().unwrap_or_else(|_| cache.config.clone());
+    let mut hasher = DefaultHasher::new();
+    config_path.hash(&mut hasher);
+    profile_name.hash(&mut hasher);
+    serde_json::to_string(provider_config).unwrap_or_default().hash(&mut hasher);
+    let sanitized: String = profile_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    format!("{}-{:016x}", sanitized, hasher.finish())
+}
+
+fn default_cache_dir() -> PathBuf {
+    let cache_home = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(env::temp_dir);
+
+    cache_home.join("ceres")
+}
+
+/// Commands supporting `--all-profiles`; cf. `for_each_profile`
+const ALL_PROFILES_COMMANDS: &[&str] = &["consul list", "health check", "instances list"];
+
+/// Fails if `--all-profiles` is set for a command which does not support it.
+pub fn check_all_profiles(cli_args: &ArgMatches, run_config: &RunConfig) -> Result<()> {
+    if !run_config.all_profiles {
+        return Ok(());
+    }
+
+    let mut command = Vec::new();
+    let mut args = cli_args;
+    while let (name, Some(sub_args)) = args.subcommand() {
+        command.push(name);
+        args = sub_args;
+    }
+    let command = command.join(" ");
+
+    if ALL_PROFILES_COMMANDS.contains(&command.as_str()) {
+        Ok(())
+    } else {
+        Err(Error::from_kind(ErrorKind::AllProfilesNotSupported(command)))
+    }
+}
+
+/// Returns the run history; cf. `history`.
+pub fn run_history(config: &Config) -> RunHistory {
+    let dir = config
+        .history
+        .dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(default_history_dir);
+
+    RunHistory::new(dir)
+}
+
+fn default_history_dir() -> PathBuf {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .unwrap_or_else(env::temp_dir);
+
+    data_home.join("ceres").join("runs")
+}
+
+/// Records the `results` of a run started at `started` in the run history and removes runs exceeding the configured
+/// maximum number of runs.
+///
+/// Failing to record the run only warns because the commands have already been run.
+pub fn record_run(run_config: &RunConfig, config: &Config, started: DateTime<Utc>, results: &[CommandResult]) {
+    let command = env::args().collect();
+    let profile_name = run_config.active_profile_name(config);
+    let history = run_history(config);
+    match history.record(command, profile_name, started, results) {
+        Ok(run) => info!("Recorded run '{}'.", run.id),
+        Err(e) => warn!("Failed to record run because {}.", e),
+    }
+
+    if config.history.max_runs == 0 {
+        return;
+    }
+    match history.prune(config.history.max_runs) {
+        Ok(ref removed) if !removed.is_empty() => debug!("Removed {} old runs from run history.", removed.len()),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to remove old runs from run history because {}.", e),
+    }
+}
+
+/// Resolves instance selectors, e.g., instance ids or `name:<glob>`, and an optional filter expression to the ids of
+/// the selected instances; cf. `provider::selector`.
+///
+/// A single selector `-` reads JSON with instance ids from stdin.
+pub fn select_instance_ids<P: DescribeInstances + ?Sized>(
+    provider: &P,
+    selectors: &[&str],
+    filter: Option<&str>,
+) -> Result<Vec<InstanceId>> {
+    select_instance_ids_from(provider, selectors, filter, io::stdin())
+}
+
+fn select_instance_ids_from<P: DescribeInstances + ?Sized, R: Read>(
+    provider: &P,
+    selectors: &[&str],
+    filter: Option<&str>,
+    stdin: R,
+) -> Result<Vec<InstanceId>> {
+    let selectors: Vec<InstanceSelector> = read_instance_ids_from(selectors, stdin)
+        .chain_err(|| ErrorKind::InstanceSelectionFailed)?
+        .iter()
+        .map(|x| x.parse())
+        .collect::<::std::result::Result<_, _>>()
+        .chain_err(|| ErrorKind::InstanceSelectionFailed)?;
+    let filter = match filter {
+        Some(filter) => Some(filter.parse::<Filter>().chain_err(|| ErrorKind::InstanceSelectionFailed)?),
+        None => None,
+    };
+
+    selector::select_instance_ids(provider, &selectors, filter.as_ref())
+        .chain_err(|| ErrorKind::InstanceSelectionFailed)
+}
+
+/// Describes the instances selected by instance selectors and an optional filter expression in the order of the
+/// selectors; cf. `select_instance_ids`.
+pub fn describe_selected_instances(
+    selectors: &[&str],
+    filter: Option<&str>,
+    run_config: &RunConfig,
+    config: &Config,
+) -> Result<Vec<InstanceDescriptor>> {
+    let provider = get_provider(run_config, config)?;
+    let instance_ids = select_instance_ids(provider.as_ref(), selectors, filter)?;
+
+    describe_instances_by_ids(provider.as_ref(), &instance_ids).chain_err(|| ErrorKind::InstanceSelectionFailed)
+}
+
+/// Prepends the ssh options routing connections through the bastion of the active profile to `ssh_opts`; cf.
+/// `config::Bastion`.
+///
+/// `ssh_opts` are returned unchanged if the profile has no bastion or `ssh_opts` already set a jump host.
+pub fn ssh_opts_with_bastion(run_config: &RunConfig, config: &Config, ssh_opts: &[&str]) -> Result<Vec<String>> {
+    let profile_name = run_config.active_profile_name(config);
+    let profile = config
+        .get_profile(profile_name)
+        .chain_err(|| ErrorKind::ProfileFailed(profile_name.to_owned()))?;
+
+    let mut opts: Vec<String> = ssh_opts.iter().map(|&s| s.to_owned()).collect();
+    let bastion = match profile.bastion {
+        Some(ref bastion) if !ssh::sets_jump_host(ssh_opts) => bastion,
+        _ => return Ok(opts),
+    };
+
+    let host = match (bastion.host.as_ref(), bastion.instance.as_ref()) {
+        (Some(host), None) => host.to_owned(),
+        (None, Some(selector)) => {
+            let provider = get_provider(run_config, config)?;
+            bastion_instance_ip(provider.as_ref(), selector)?
+        }
+        _ => return Err(Error::from_kind(ErrorKind::ConfigMissingInProfile("bastion host or instance".to_string()))),
+    };
+    let user = bastion.user.as_ref().or_else(|| profile.ssh_user.as_ref());
+    let jump_host = ssh::jump_host(&host, user.map(String::as_str), bastion.port);
+    debug!("Routing ssh connections through bastion '{}'.", jump_host);
+
+    opts.insert(0, "-J".to_owned());
+    opts.insert(1, jump_host);
+
+    Ok(opts)
+}
+
+/// Returns the public IP address of a running instance selected by `selector`.
+fn bastion_instance_ip<P: DescribeInstances + ?Sized>(provider: &P, selector: &str) -> Result<String> {
+    let selector: InstanceSelector = selector.parse().chain_err(|| ErrorKind::BastionFailed)?;
+    let instances = provider.describe_instances().chain_err(|| ErrorKind::BastionFailed)?;
+
+    selector::select_instances(&instances, &[selector], None)
+        .chain_err(|| ErrorKind::BastionFailed)?
+        .into_iter()
+        .filter(|i| i.state.as_ref().map(String::as_str) == Some("running"))
+        .filter_map(|i| i.public_ip_address.clone())
+        .next()
+        .ok_or_else(|| Error::from_kind(ErrorKind::BastionFailed))
+}
+
+/// Returns the arguments controlling rolling runs of remote commands; cf. `rolling_from_args`.
+pub fn rolling_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("batch-size")
+            .long("batch-size")
+            .takes_value(true)
+            .help("Runs on this number or percentage of instances at a time, e.g., 5 or 20%; default is all at once"),
+        Arg::with_name("max-failures")
+            .long("max-failures")
+            .takes_value(true)
+            .help("Skips remaining batches once commands failed on more instances"),
+        Arg::with_name("pause")
+            .long("pause")
+            .takes_value(true)
+            .help("Pauses for this number of sec between batches"),
+        Arg::with_name("gate-command")
+            .long("gate-command")
+            .takes_value(true)
+            .help("Runs this local command between batches; skips remaining batches if it fails"),
+    ]
+}
+
+/// Reads the settings of a rolling run from the arguments of `rolling_args`.
+pub fn rolling_from_args(args: &ArgMatches) -> Result<Rolling> {
+    let mut rolling = Rolling::default();
+    if let Some(batch_size) = args.value_of("batch-size") {
+        rolling.batch_size = batch_size.parse().chain_err(|| ErrorKind::InvalidRollingArguments)?;
+    }
+    if let Some(max_failures) = args.value_of("max-failures") {
+        rolling.max_failures = Some(max_failures.parse().chain_err(|| ErrorKind::InvalidRollingArguments)?);
+    }
+    if let Some(pause) = args.value_of("pause") {
+        let secs = pause.parse().chain_err(|| ErrorKind::InvalidRollingArguments)?;
+        rolling.pause = Some(Duration::from_secs(secs));
+    }
+
+    Ok(rolling)
+}
+
+/// Returns the arguments controlling retries of failed remote commands; cf. `retries_from_args`.
+pub fn retries_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("retries")
+            .long("retries")
+            .takes_value(true)
+            .default_value("0")
+            .help("Retries failed commands this many times"),
+        Arg::with_name("retry-backoff")
+            .long("retry-backoff")
+            .takes_value(true)
+            .default_value("5")
+            .help("Waits this number of sec before the first retry; doubles with every further retry"),
+    ]
+}
+
+/// Reads the settings of retries from the arguments of `retries_args`.
+pub fn retries_from_args(args: &ArgMatches) -> Result<Retries> {
+    let mut retries = Retries::default();
+    if let Some(count) = args.value_of("retries") {
+        retries.count = count.parse().chain_err(|| ErrorKind::InvalidRetryArguments)?;
+    }
+    if let Some(backoff) = args.value_of("retry-backoff") {
+        let secs = backoff.parse().chain_err(|| ErrorKind::InvalidRetryArguments)?;
+        retries.backoff = Duration::from_secs(secs);
+    }
+
+    Ok(retries)
+}
+
+/// Runs `f` concurrently for each profile selected by `run_config` and returns the results by profile name.
+///
+/// This allows read-only modules to support `--all-profiles`; the results keep the order of `RunConfig::profile_names`.
+/// With `--all-profiles`, profiles `f` fails for, e.g., because they lack the necessary configuration, are skipped
+/// with a warning; it only fails if `f` fails for all profiles.
+pub fn for_each_profile<T, F>(run_config: &RunConfig, config: &Config, f: F) -> Result<Vec<(String, T)>>
+where
+    T: Send + 'static,
+    F: Fn(&str, &Profile) -> Result<T> + Send + Sync + 'static,
+{
+    let f = Arc::new(f);
+    let mut queries = Vec::new();
+    for name in run_config.profile_names(config) {
+        let profile = config
+            .get_profile(&name)
+            .chain_err(|| ErrorKind::ProfileFailed(name.clone()))?
+            .clone();
+        let f = Arc::clone(&f);
+        let thread_name = name.clone();
+        let query = thread::spawn(move || f(&thread_name, &profile));
+        queries.push((name, query));
+    }
+
+    let mut results = Vec::new();
+    let mut last_error = None;
+    for (name, query) in queries {
+        let result = query
+            .join()
+            .map_err(|_| Error::from_kind(ErrorKind::ProfileFailed(name.clone())))
+            .and_then(|result| result.chain_err(|| ErrorKind::ProfileFailed(name.clone())));
+        match result {
+            Ok(result) => results.push((name, result)),
+            Err(e) if run_config.all_profiles => {
+                let cause = e.iter().last().map(|x| x.to_string()).unwrap_or_default();
+                warn!("Skipping profile '{}' because {}.", name, cause);
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    match last_error {
+        Some(e) if results.is_empty() => Err(e),
+        _ => Ok(results),
+    }
+}
+
 main_module!(
     consul,
     centerdevice,
+    complete,
     health,
     infrastructure,
     instances,
     ops,
+    runs,
+    security_groups,
     statuspages,
     stories
 );
@@ -44,5 +440,98 @@ error_chain! {
             description("There is a configuration missing in this profile")
             display("there is no {} configuration in this profile", config)
         }
+
+        AllProfilesNotSupported(command: String) {
+            description("--all-profiles is not supported by this command")
+            display("--all-profiles is not supported by '{}'; only by {}", command, ALL_PROFILES_COMMANDS.join(", "))
+        }
+
+        InstanceSelectionFailed {
+            description("failed to select instances")
+            display("failed to select instances")
+        }
+
+        InvalidRollingArguments {
+            description("invalid batch size, max failures, or pause")
+            display("invalid batch size, max failures, or pause")
+        }
+
+        InvalidRetryArguments {
+            description("invalid retries or retry backoff")
+            display("invalid retries or retry backoff")
+        }
+
+        NoSuchResultsFile(path: String) {
+            description("no such results file")
+            display("no such results file '{}'", path)
+        }
+
+        BastionFailed {
+            description("failed to find running bastion instance with public IP address")
+            display("failed to find running bastion instance with public IP address")
+        }
+
+        ProfileFailed(profile: String) {
+            description("running for profile failed")
+            display("running for profile {} failed", profile)
+        }
+
+        ConflictingTagChanges(keys: String) {
+            description("tags are to be set and removed at the same time")
+            display("tags {} are to be set and removed at the same time", keys)
+        }
+
+        Cancelled {
+            description("cancelled by user")
+            display("cancelled by user")
+        }
+
+        CacheDisabled {
+            description("--offline requires the cache to be enabled")
+            display("--offline requires the cache to be enabled by a ttl greater than 0 in the configuration")
+        }
+
+        ResizeFailed(instance_ids: String) {
+            description("failed to change instance type")
+            display("failed to change instance type of {}", instance_ids)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    use provider::inventory::Inventory;
+
+    fn inventory(dir: &TempDir) -> Inventory {
+        let path = dir.path().join("inventory.toml");
+        fs::write(&path, "[[instances]]\ninstance_id = \"i-1\"\nstate = \"running\"\n").unwrap();
+
+        Inventory { path }
+    }
+
+    #[test]
+    fn select_instance_ids_from_stdin() {
+        let dir = TempDir::new().unwrap();
+        let provider = inventory(&dir);
+
+        let res = select_instance_ids_from(&provider, &["-"], None, r#"[{"instance_id": "i-1"}]"#.as_bytes());
+
+        assert_that(&res).is_ok().is_equal_to(vec!["i-1".to_owned()]);
+    }
+
+    #[test]
+    fn select_instance_ids_from_empty_stdin_fails() {
+        let dir = TempDir::new().unwrap();
+        let provider = inventory(&dir);
+
+        let res = select_instance_ids_from(&provider, &["-"], None, "[]".as_bytes());
+
+        assert_that(&res).is_err();
     }
 }
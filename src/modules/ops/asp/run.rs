@@ -1,20 +1,25 @@
+use chrono::Utc;
 use clap::{App, Arg, ArgMatches, SubCommand};
-use itertools::Itertools;
-use std::time::Duration;
+use service_world::consul::{Catalog, Consul};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
 
-use config::{CeresConfig as Config, Profile, Provider};
+use config::CeresConfig as Config;
 use modules::*;
 use output::OutputType;
-use provider::{DescribeInstance, InstanceDescriptor};
+use provider::InstanceDescriptor;
 use run_config::RunConfig;
-use utils::cli::read_instance_ids;
-use utils::run;
+use utils::run::{self, RollingResults};
 use utils::ssh;
 
 pub const NAME: &str = "run";
 const COMMANDS: &[&str] = &[
     "sudo /usr/local/sbin/run-my-asp.sh"
 ];
+/// Seconds between Consul queries while waiting for services to become healthy
+const HEALTH_POLL_INTERVAL_SECS: u64 = 5;
 
 pub struct SubModule;
 
@@ -24,9 +29,16 @@ impl Module for SubModule {
             .about("run asp on instances")
             .arg(
                 Arg::with_name("instance_ids")
-                    .required(true)
+                    .required_unless("filter")
                     .multiple(true)
-                    .help("Runs command on instances with these instance id; or '-' to read json with instance ids from stdin"),
+                    .help("Runs command on instances with these ids, 'name:<glob>', or 'tag:<key>=<value>'; or '-' to read json from stdin"),
+            )
+            .arg(
+                Arg::with_name("filter")
+                    .long("filter")
+                    .short("f")
+                    .takes_value(true)
+                    .help("Selects instances by filter expression; cf. instances list"),
             )
             .arg(
                 Arg::with_name("login-name")
@@ -40,6 +52,11 @@ impl Module for SubModule {
                     .long("no-progress-bar")
                     .help("Do not show progressbar during command execution"),
             )
+            .arg(
+                Arg::with_name("stream")
+                    .long("stream")
+                    .help("Prints output lines live to stderr prefixed by instance id instead of a progress bar"),
+            )
             .arg(
                 Arg::with_name("public-ip")
                     .short("p")
@@ -74,6 +91,20 @@ impl Module for SubModule {
                     .default_value("300")
                     .help("Timeout in sec for command to finish"),
             )
+            .args(&retries_args())
+            .args(&rolling_args())
+            .arg(
+                Arg::with_name("consul-health")
+                    .long("consul-health")
+                    .help("Waits after each batch until Consul reports all services of its instances healthy"),
+            )
+            .arg(
+                Arg::with_name("health-timeout")
+                    .long("health-timeout")
+                    .takes_value(true)
+                    .default_value("300")
+                    .help("Timeout in sec for services to become healthy"),
+            )
     }
 
     fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
@@ -82,7 +113,6 @@ impl Module for SubModule {
     }
 }
 
-#[allow(unstable_name_collisions)] // flatten from itertools
 fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
     let profile = match run_config.active_profile.as_ref() {
         "default" => config.get_default_profile(),
@@ -91,8 +121,6 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
 
     // Parse my args
     let instance_ids: Vec<&str> = args.values_of("instance_ids").unwrap_or_else(Default::default).collect();
-    let instance_ids: Vec<_> = read_instance_ids(&instance_ids)
-        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
     let public_ip = args.is_present("public-ip");
 
     let ssh_opts: Vec<&str> = args.values_of("ssh-opts").unwrap_or_else(Default::default).collect();
@@ -103,7 +131,30 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
         .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
     );
 
+    let retries = retries_from_args(args)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
     let progress_bar = !args.is_present("no-progress-bar");
+    let stream = args.is_present("stream");
+    let rolling = rolling_from_args(args)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let gate_command = args.value_of("gate-command");
+    let consul_url = if args.is_present("consul-health") {
+        let url = profile.consul
+            .as_ref()
+            .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("consul".to_string())))?
+            .urls
+            .first()
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        Some(url.as_str())
+    } else {
+        None
+    };
+    let health_timeout = Duration::from_secs(
+        args.value_of("health-timeout").unwrap() // safe unwrap
+        .parse()
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?
+    );
 
     let show_all = args.is_present("show-all");
     let output_type = args.value_of("output").unwrap() // Safe
@@ -112,42 +163,129 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
 
     // Run me
     info!("Querying description for instances.");
-    let instances = describe_instances(&instance_ids, &profile)?;
+    let instances = describe_selected_instances(&instance_ids, args.value_of("filter"), run_config, config)
+        .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
 
-    debug!("Building ssh commands.");
-    let commands: Result<Vec<_>> = COMMANDS.iter()
-        .map(|c| {
-            let command_args: Vec<_> = c.split(' ').collect();
-            ssh::build_ssh_command_to_instances(&instances, public_ip, profile.ssh_user.as_ref(), &ssh_opts, &command_args.as_slice(), timeout)
-                .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))
-        }).collect();
-    let commands = commands?;
+    let ssh_opts = ssh_opts_with_bastion(run_config, config, &ssh_opts)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let ssh_opts: Vec<&str> = ssh_opts.iter().map(String::as_str).collect();
 
     info!("Running commands.");
-    let results: Result<Vec<_>>  = commands.into_iter()
-        .map(|cs|
-            run::run(cs, progress_bar)
-                .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))
-        ).collect();
-    let results: Vec<_> = Itertools::flatten(results?.into_iter()).collect();
+    let started = Utc::now();
+    let RollingResults { results, skipped, error } = run::run_in_batches(
+        &instances,
+        &rolling,
+        |batch| {
+            let mut results = Vec::new();
+            for c in COMMANDS {
+                debug!("Building ssh commands.");
+                let command_args: Vec<_> = c.split(' ').collect();
+                let commands = ssh::build_ssh_command_to_instances(
+                    batch,
+                    public_ip,
+                    profile.ssh_user.as_ref(),
+                    &ssh_opts,
+                    &command_args,
+                    timeout,
+                )?;
+                let batch_results = run::run_with_retries(commands, &retries, |commands| {
+                    if stream {
+                        run::run_with_stream(commands)
+                    } else {
+                        run::run(commands, progress_bar)
+                    }
+                })?;
+                results.extend(batch_results);
+            }
+            Ok(results)
+        },
+        |batch, _| -> Result<()> {
+            if let Some(command) = gate_command {
+                run::run_gate_command(command, &instance_ids_of(batch))
+                    .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+            }
+            if let Some(url) = consul_url {
+                wait_for_consul_health(url, batch, health_timeout)?;
+            }
+            Ok(())
+        },
+    );
+    record_run(run_config, config, started, &results);
 
     run::output_results(output_type, show_all, results.as_slice())
         .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    run::output_skipped(&instance_ids_of(skipped));
+
+    if let Some(e) = error {
+        return Err(Error::with_chain(e, ErrorKind::ModuleFailed(NAME.to_owned())));
+    }
+    if !skipped.is_empty() {
+        return Err(Error::from_kind(ErrorKind::ModuleFailed(NAME.to_owned())));
+    }
+
+    // The gate only runs between batches, but the last batch has to become healthy, too
+    if let (Some(url), Some(batch)) = (consul_url, instances.chunks(rolling.batch_size.of(instances.len())).last()) {
+        wait_for_consul_health(url, batch, health_timeout)?;
+    }
 
     Ok(())
 }
 
-fn describe_instances(instance_ids: &[String], profile: &Profile) -> Result<Vec<InstanceDescriptor>> {
-    let Provider::Aws(provider) = profile.provider
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
+/// Waits until Consul reports all services on `instances` healthy; cf. `consul list`.
+///
+/// Instances are matched to Consul nodes by the node meta data `ec2_instance_id` or by their private IP address. An
+/// instance without any service is not healthy yet because its services may still be starting.
+fn wait_for_consul_health(url: &str, instances: &[InstanceDescriptor], timeout: Duration) -> Result<()> {
+    let consul = Consul::new(url.to_owned());
+    let start = Instant::now();
+    loop {
+        let catalog = consul.catalog_by(None, None)
+            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))?;
+        let unhealthy: Vec<_> = instances
+            .iter()
+            .filter(|i| !is_healthy(&catalog, i))
+            .flat_map(|i| i.instance_id.as_ref())
+            .map(String::as_str)
+            .collect();
+        if unhealthy.is_empty() {
+            info!("Services of {} instances are healthy.", instances.len());
+            return Ok(());
+        }
 
-    let res: Result<Vec<InstanceDescriptor>> = instance_ids.iter().
-        map(|id| provider
-            .describe_instance(id)
-            .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
-        ).collect();
+        let ids = unhealthy.join(", ");
+        if start.elapsed() >= timeout {
+            error!("Services of instances {} did not become healthy in time.", ids);
+            return Err(Error::from_kind(ErrorKind::ModuleFailed(String::from(NAME))));
+        }
+        info!("Waiting for services of instances {} to become healthy.", ids);
+        thread::sleep(Duration::from_secs(HEALTH_POLL_INTERVAL_SECS));
+    }
+}
 
-    res
+fn is_healthy(catalog: &Catalog, instance: &InstanceDescriptor) -> bool {
+    let mut services = 0;
+    for service in catalog.services() {
+        if let Some(nodes) = catalog.nodes_by_service(service) {
+            for node in nodes {
+                let by_id = instance
+                    .instance_id
+                    .as_ref()
+                    .map_or(false, |id| node.meta_data.get("ec2_instance_id") == Some(id));
+                let by_ip = instance.private_ip_address.as_ref() == Some(&node.address);
+                if !by_id && !by_ip {
+                    continue;
+                }
+                if !catalog.is_node_healthy_for_service(node, &node.service_name) {
+                    return false;
+                }
+                services += 1;
+            }
+        }
+    }
+
+    services > 0
 }
 
+fn instance_ids_of(instances: &[InstanceDescriptor]) -> Vec<&str> {
+    instances.iter().flat_map(|i| i.instance_id.as_ref()).map(String::as_str).collect()
+}
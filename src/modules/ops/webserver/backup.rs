@@ -3,7 +3,7 @@ use itertools::Itertools;
 use std::collections::HashMap;
 use std::time::Duration;
 
-use config::{CeresConfig as Config, Profile, Provider};
+use config::CeresConfig as Config;
 use modules::*;
 use output::OutputType;
 use provider::{DescribeInstances, InstanceDescriptor};
@@ -111,7 +111,7 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
 
     // Run me
     info!("Querying instances.");
-    let instances = find_instances(&profile)?;
+    let instances = find_instances(run_config, config)?;
     match instances.len()  {
         0 => {
             error!("Did not find any instances with Intent=webserver");
@@ -128,6 +128,10 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
         }
     }
 
+    let ssh_opts = ssh_opts_with_bastion(run_config, config, &ssh_opts)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let ssh_opts: Vec<&str> = ssh_opts.iter().map(String::as_str).collect();
+
     debug!("Building ssh commands.");
     let commands: Result<Vec<_>> = COMMANDS.iter()
         .map(|c| {
@@ -151,10 +155,8 @@ fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result
     Ok(())
 }
 
-fn find_instances(profile: &Profile) -> Result<Vec<InstanceDescriptor>> {
-    let Provider::Aws(provider) = profile.provider
-        .as_ref()
-        .ok_or_else(|| Error::from_kind(ErrorKind::ConfigMissingInProfile("provider".to_string())))?;
+fn find_instances(run_config: &RunConfig, config: &Config) -> Result<Vec<InstanceDescriptor>> {
+    let provider = get_provider(run_config, config)?;
 
     let all = provider.describe_instances()
         .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
@@ -0,0 +1,88 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use config::CeresConfig as Config;
+use history::RunRecord;
+use modules::*;
+use output::OutputType;
+use output::runs::{JsonOutputRuns, OutputRuns, TableOutputRuns};
+use run_config::RunConfig;
+
+pub const NAME: &str = "list";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("list recorded runs")
+            .arg(
+                Arg::with_name("profile-only")
+                    .long("profile-only")
+                    .help("Lists only runs of the active profile"),
+            )
+            .arg(
+                Arg::with_name("failed")
+                    .long("failed")
+                    .help("Lists only runs with failed commands"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    let profile_name = run_config.active_profile_name(config);
+    let profile_only = args.is_present("profile-only");
+    let failed = args.is_present("failed");
+
+    info!("Reading run history.");
+    let runs: Vec<_> = run_history(config)
+        .list()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?
+        .into_iter()
+        .filter(|x| !profile_only || x.profile == profile_name)
+        .filter(|x| !failed || !x.succeeded())
+        .collect();
+
+    info!("Outputting runs.");
+    output_runs(args, &runs)?;
+
+    Ok(())
+}
+
+fn output_runs(args: &ArgMatches, runs: &[RunRecord]) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputRuns;
+
+            output
+                .output(&mut stdout, runs)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputRuns;
+
+            output
+                .output(&mut stdout, runs)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => unimplemented!("'Plain' output is not supported for this module"),
+    }
+}
@@ -0,0 +1,81 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::{
+    fs::File,
+    io::{self, Write},
+};
+
+use config::CeresConfig as Config;
+use history::TargetRecord;
+use modules::*;
+use modules::runs::{get_run, run_id_arg};
+use run_config::RunConfig;
+
+pub const NAME: &str = "logs";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("print the logs of a recorded run")
+            .arg(run_id_arg())
+            .arg(
+                Arg::with_name("target")
+                    .long("target")
+                    .short("t")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Prints only the log of this target, e.g., an instance id"),
+            )
+            .arg(
+                Arg::with_name("failed")
+                    .long("failed")
+                    .help("Prints only the logs of failed commands"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, _: &RunConfig, config: &Config) -> Result<()> {
+    let target_ids: Vec<&str> = args.values_of("target").unwrap_or_else(Default::default).collect();
+    let failed = args.is_present("failed");
+
+    info!("Reading run history.");
+    let run = get_run(args, config)?;
+    let targets: Vec<_> = run
+        .targets
+        .iter()
+        .filter(|x| target_ids.is_empty() || target_ids.contains(&x.id.as_str()))
+        .filter(|x| !failed || !x.exit_status.success())
+        .collect();
+
+    info!("Outputting logs.");
+    output_logs(&targets).chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))
+}
+
+/// Prints the logs of `targets`; each log is preceded by a header if there is more than one.
+fn output_logs(targets: &[&TargetRecord]) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for (i, target) in targets.iter().enumerate() {
+        if targets.len() > 1 {
+            if i > 0 {
+                writeln!(stdout)?;
+            }
+            writeln!(stdout, "==> {} ({:?}) <==", target.id, target.exit_status)?;
+        }
+        match File::open(&target.log) {
+            Ok(mut log) => {
+                io::copy(&mut log, &mut stdout)?;
+            }
+            Err(e) => warn!("Failed to read log '{}' because {}.", target.log.to_string_lossy(), e),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+sub_module!("runs", "Inspect and retry recorded runs", list, logs, rerun_failed, show);
+
+use clap::Arg;
+
+use history::{RunRecord, LAST_RUN_ID};
+
+/// Returns the argument selecting a recorded run; defaults to the latest run.
+pub fn run_id_arg() -> Arg<'static, 'static> {
+    Arg::with_name("run_id")
+        .default_value(LAST_RUN_ID)
+        .help("Selects run by its id; 'last' selects the latest run")
+}
+
+/// Gets the recorded run selected by `run_id_arg`.
+pub fn get_run(args: &ArgMatches, config: &Config) -> Result<RunRecord> {
+    let run_id = args.value_of("run_id").unwrap(); // Safe unwrap
+
+    run_history(config)
+        .get(run_id)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))
+}
@@ -0,0 +1,101 @@
+use chrono::Utc;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::env;
+use tempfile;
+
+use config::CeresConfig as Config;
+use modules::*;
+use modules::runs::{get_run, run_id_arg};
+use output::OutputType;
+use run_config::RunConfig;
+use utils::run;
+
+pub const NAME: &str = "rerun-failed";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("rerun the failed commands of a recorded run")
+            .arg(run_id_arg())
+            .arg(
+                Arg::with_name("no-progress-bar")
+                    .long("no-progress-bar")
+                    .help("Do not show progressbar during command execution"),
+            )
+            .arg(
+                Arg::with_name("fail-on-fail")
+                    .long("fail-on-fail")
+                    .help("Exits with code != 1 if any command fails again"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json"])
+                    .help("Selects output format"),
+            )
+            .arg(
+                Arg::with_name("show-all")
+                    .long("show-all")
+                    .help("Show all command results; by default show only results of failed commands"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, _: &RunConfig, config: &Config) -> Result<()> {
+    let progress_bar = !args.is_present("no-progress-bar");
+    let fail_on_fail = args.is_present("fail-on-fail");
+
+    let show_all = args.is_present("show-all");
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    info!("Reading run history.");
+    let run = get_run(args, config)?;
+
+    debug!("Building commands.");
+    let mut commands = Vec::new();
+    for target in run.failed_targets() {
+        let log = tempfile::NamedTempFile::new()
+            .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?
+            .path()
+            .to_path_buf();
+        match target.to_command(log) {
+            Some(command) => commands.push(command),
+            None => warn!("Skipping '{}' because its command has not been recorded.", target.id),
+        }
+    }
+    if commands.is_empty() {
+        info!("Nothing to rerun for run '{}'.", run.id);
+        return Ok(());
+    }
+
+    info!("Rerunning {} failed commands of run '{}'.", commands.len(), run.id);
+    let started = Utc::now();
+    let results = run::run(commands, progress_bar)
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    // Record the rerun for the profile of the original run since the commands target its instances
+    match run_history(config).record(env::args().collect(), &run.profile, started, &results) {
+        Ok(rerun) => info!("Recorded run '{}'.", rerun.id),
+        Err(e) => warn!("Failed to record run because {}.", e),
+    }
+
+    run::output_results(output_type, show_all, results.as_slice())
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    if fail_on_fail && results.iter().any(|r| !r.exit_status.success()) {
+        return Err(Error::from_kind(ErrorKind::ModuleFailed(NAME.to_owned())));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use config::CeresConfig as Config;
+use history::RunRecord;
+use modules::*;
+use modules::runs::{get_run, run_id_arg};
+use output::OutputType;
+use output::runs::{JsonOutputRun, OutputRun, TableOutputRun};
+use run_config::RunConfig;
+
+pub const NAME: &str = "show";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("show a recorded run with the results of its commands")
+            .arg(run_id_arg())
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, _: &RunConfig, config: &Config) -> Result<()> {
+    info!("Reading run history.");
+    let run = get_run(args, config)?;
+
+    info!("Outputting run.");
+    output_run(args, &run)?;
+
+    Ok(())
+}
+
+fn output_run(args: &ArgMatches, run: &RunRecord) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputRun;
+
+            output
+                .output(&mut stdout, run)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputRun;
+
+            output
+                .output(&mut stdout, run)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => unimplemented!("'Plain' output is not supported for this module"),
+    }
+}
@@ -0,0 +1,82 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use modules::security_groups::{audit_security_groups, list::query_security_groups, Finding};
+use output::OutputType;
+use output::security_groups::{JsonOutputFindings, OutputFindings, PlainOutputFindings, TableOutputFindings};
+
+pub const NAME: &str = "audit";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("audit security groups for rules open to the world and unused groups")
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json", "plain"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Querying security groups.");
+    let usages = query_security_groups(run_config, config)?;
+
+    info!("Auditing security groups.");
+    let findings = audit_security_groups(&usages);
+
+    info!("Outputting findings.");
+    output_findings(args, run_config, config, &findings)?;
+
+    Ok(())
+}
+
+fn output_findings(
+    args: &ArgMatches,
+    _: &RunConfig,
+    _: &Config,
+    findings: &[Finding],
+) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputFindings;
+
+            output
+                .output(&mut stdout, findings)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputFindings;
+
+            output
+                .output(&mut stdout, findings)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => {
+            let output = PlainOutputFindings;
+
+            output
+                .output(&mut stdout, findings)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+    }
+}
@@ -0,0 +1,98 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use config::CeresConfig as Config;
+use run_config::RunConfig;
+use modules::*;
+use modules::security_groups::{security_group_usages, SecurityGroupUsage};
+use output::OutputType;
+use output::security_groups::{
+    JsonOutputSecurityGroups,
+    OutputSecurityGroups,
+    PlainOutputSecurityGroups,
+    TableOutputSecurityGroups,
+};
+
+pub const NAME: &str = "list";
+
+pub struct SubModule;
+
+impl Module for SubModule {
+    fn build_sub_cli() -> App<'static, 'static> {
+        SubCommand::with_name(NAME)
+            .about("list security groups with their rules and the instances using them")
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true)
+                    .default_value("human")
+                    .possible_values(&["human", "json", "plain"])
+                    .help("Selects output format"),
+            )
+    }
+
+    fn call(cli_args: Option<&ArgMatches>, run_config: &RunConfig, config: &Config) -> Result<()> {
+        let args = cli_args.unwrap(); // Safe unwrap
+        do_call(args, run_config, config)
+    }
+}
+
+fn do_call(args: &ArgMatches, run_config: &RunConfig, config: &Config) -> Result<()> {
+    info!("Querying security groups.");
+    let usages = query_security_groups(run_config, config)?;
+
+    info!("Outputting security groups.");
+    output_security_groups(args, run_config, config, &usages)?;
+
+    Ok(())
+}
+
+/// Queries the security groups and instances of the active profile and relates them.
+pub fn query_security_groups(run_config: &RunConfig, config: &Config) -> Result<Vec<SecurityGroupUsage>> {
+    let provider = get_provider(run_config, config)?;
+
+    let security_groups = provider
+        .describe_security_groups()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let instances = provider
+        .describe_instances()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+
+    Ok(security_group_usages(security_groups, &instances))
+}
+
+fn output_security_groups(
+    args: &ArgMatches,
+    _: &RunConfig,
+    _: &Config,
+    usages: &[SecurityGroupUsage],
+) -> Result<()> {
+    let output_type = args.value_of("output").unwrap() // Safe
+        .parse::<OutputType>()
+        .chain_err(|| ErrorKind::ModuleFailed(NAME.to_owned()))?;
+    let mut stdout = ::std::io::stdout();
+
+    match output_type {
+        OutputType::Human => {
+            let output = TableOutputSecurityGroups;
+
+            output
+                .output(&mut stdout, usages)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Json => {
+            let output = JsonOutputSecurityGroups;
+
+            output
+                .output(&mut stdout, usages)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+        OutputType::Plain => {
+            let output = PlainOutputSecurityGroups;
+
+            output
+                .output(&mut stdout, usages)
+                .chain_err(|| ErrorKind::ModuleFailed(String::from(NAME)))
+        },
+    }
+}
@@ -0,0 +1,189 @@
+sub_module!("security-groups", "Inspect and audit security groups", audit, list);
+
+use provider::{InstanceDescriptor, InstanceId, SecurityGroup, SecurityGroupRule};
+
+/// Ports that may be open to the world, i.e., HTTP and HTTPS.
+const PUBLIC_PORTS: &[i64] = &[80, 443];
+
+/// Security group and the instances using it
+#[derive(Debug, Serialize)]
+pub struct SecurityGroupUsage {
+    pub security_group: SecurityGroup,
+    pub instance_ids:   Vec<InstanceId>,
+}
+
+/// Relates security groups to the instances using them; terminated instances are ignored.
+pub fn security_group_usages(
+    security_groups: Vec<SecurityGroup>,
+    instances: &[InstanceDescriptor],
+) -> Vec<SecurityGroupUsage> {
+    let instances: Vec<_> = instances
+        .iter()
+        .filter(|x| x.state.as_ref().map(|s| s != "terminated").unwrap_or(true))
+        .map(|x| (x.instance_id.clone().unwrap_or_else(|| "-".to_owned()), x.security_group_ids()))
+        .collect();
+
+    security_groups
+        .into_iter()
+        .map(|security_group| {
+            let instance_ids = instances
+                .iter()
+                .filter(|&&(_, ref group_ids)| group_ids.contains(&security_group.group_id))
+                .map(|&(ref instance_id, _)| instance_id.clone())
+                .collect();
+            SecurityGroupUsage {
+                security_group,
+                instance_ids,
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    /// An ingress rule allows traffic from anywhere to other ports than HTTP and HTTPS.
+    OpenToWorld,
+    /// No instance uses the security group.
+    Unused,
+}
+
+impl FindingKind {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            FindingKind::OpenToWorld => "open-to-world",
+            FindingKind::Unused => "unused",
+        }
+    }
+}
+
+/// Risky configuration of a security group found by `audit_security_groups`
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub group_id:     String,
+    pub group_name:   Option<String>,
+    pub kind:         FindingKind,
+    pub rule:         Option<SecurityGroupRule>,
+    pub instance_ids: Vec<InstanceId>,
+}
+
+/// Audits security groups for ingress rules open to the world on other ports than HTTP and HTTPS and for groups
+/// not used by any instance.
+///
+/// Default security groups cannot be deleted and are therefore never reported as unused. Since only instances are
+/// taken into account, groups used by other resources like load balancers are reported as unused.
+pub fn audit_security_groups(usages: &[SecurityGroupUsage]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for usage in usages {
+        let sg = &usage.security_group;
+        let finding = |kind, rule: Option<&SecurityGroupRule>| Finding {
+            group_id:     sg.group_id.clone(),
+            group_name:   sg.group_name.clone(),
+            kind,
+            rule:         rule.cloned(),
+            instance_ids: usage.instance_ids.clone(),
+        };
+
+        for rule in sg.ingress.iter().filter(|x| is_risky(x)) {
+            findings.push(finding(FindingKind::OpenToWorld, Some(rule)));
+        }
+        if usage.instance_ids.is_empty() && sg.group_name.as_ref().map(|x| x != "default").unwrap_or(true) {
+            findings.push(finding(FindingKind::Unused, None));
+        }
+    }
+
+    findings
+}
+
+fn is_risky(rule: &SecurityGroupRule) -> bool {
+    if !rule.is_open_to_world() {
+        return false;
+    }
+    if rule.protocol != "tcp" {
+        return true;
+    }
+    match (rule.from_port, rule.to_port) {
+        (Some(from), Some(to)) => (from..=to).any(|port| !PUBLIC_PORTS.contains(&port)),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    fn rule(protocol: &str, from_port: Option<i64>, to_port: Option<i64>, source: &str) -> SecurityGroupRule {
+        SecurityGroupRule {
+            protocol: protocol.to_owned(),
+            from_port,
+            to_port,
+            sources:  vec![source.to_owned()],
+        }
+    }
+
+    fn security_group(group_id: &str, group_name: &str, ingress: Vec<SecurityGroupRule>) -> SecurityGroup {
+        SecurityGroup {
+            account:     None,
+            region:      None,
+            group_id:    group_id.to_owned(),
+            group_name:  Some(group_name.to_owned()),
+            description: None,
+            vpc_id:      None,
+            ingress,
+            egress:      vec![],
+        }
+    }
+
+    fn instance(instance_id: &str, state: &str, group_id: &str) -> InstanceDescriptor {
+        InstanceDescriptor {
+            instance_id: Some(instance_id.to_owned()),
+            security_groups: Some(vec![format!("id={}, name=some-group", group_id)]),
+            state: Some(state.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn security_group_usages_ignore_terminated_instances() {
+        let security_groups = vec![security_group("sg-1", "web", vec![]), security_group("sg-2", "db", vec![])];
+        let instances = vec![
+            instance("i-1", "running", "sg-1"),
+            instance("i-2", "stopped", "sg-1"),
+            instance("i-3", "terminated", "sg-2"),
+        ];
+
+        let usages = security_group_usages(security_groups, &instances);
+
+        assert_that(&usages[0].instance_ids).is_equal_to(vec!["i-1".to_owned(), "i-2".to_owned()]);
+        assert_that(&usages[1].instance_ids).is_empty();
+    }
+
+    #[test]
+    fn audit_finds_rules_open_to_world_and_unused_groups() {
+        let security_groups = vec![
+            security_group("sg-1", "web", vec![
+                rule("tcp", Some(443), Some(443), "0.0.0.0/0"),
+                rule("tcp", Some(22), Some(22), "::/0"),
+                rule("tcp", Some(5432), Some(5432), "10.0.0.0/8"),
+            ]),
+            security_group("sg-2", "all", vec![rule("-1", None, None, "0.0.0.0/0")]),
+            security_group("sg-3", "default", vec![]),
+        ];
+        let instances = vec![instance("i-1", "running", "sg-1")];
+        let usages = security_group_usages(security_groups, &instances);
+
+        let findings: Vec<_> = audit_security_groups(&usages)
+            .into_iter()
+            .map(|x| (x.group_id, x.kind, x.rule.map(|r| r.to_string())))
+            .collect();
+
+        assert_that(&findings).is_equal_to(vec![
+            ("sg-1".to_owned(), FindingKind::OpenToWorld, Some("tcp 22 ::/0".to_owned())),
+            ("sg-2".to_owned(), FindingKind::OpenToWorld, Some("all all 0.0.0.0/0".to_owned())),
+            ("sg-2".to_owned(), FindingKind::Unused, None),
+        ]);
+    }
+}
@@ -1,12 +1,21 @@
 use serde_json;
 
 use output::consul::*;
-use service_world::consul::Catalog;
 
-pub struct JsonOutputCatalogResult;
+/// Outputs the catalogs as JSON.
+///
+/// A single catalog is output as is unless `by_profile` is set; otherwise the catalogs are output
+/// as list of profile and catalog.
+pub struct JsonOutputCatalogResult {
+    pub by_profile: bool,
+}
 
 impl OutputCatalogResult for JsonOutputCatalogResult {
-    fn output<T: Write>(&self, writer: &mut T, result: &Catalog) -> Result<()> {
-        serde_json::to_writer_pretty(writer, result).chain_err(|| ErrorKind::OutputFailed)
+    fn output<T: Write>(&self, writer: &mut T, results: &[ProfileCatalog]) -> Result<()> {
+        match results {
+            [ref result] if !self.by_profile => serde_json::to_writer_pretty(writer, &result.catalog),
+            _ => serde_json::to_writer_pretty(writer, results),
+        }
+        .chain_err(|| ErrorKind::OutputFailed)
     }
 }
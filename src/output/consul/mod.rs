@@ -1,7 +1,7 @@
 use service_world::consul::{Catalog, Node};
 use std::{collections::HashMap, io::Write};
 
-use modules::consul::NodeField;
+use modules::consul::{NodeField, ProfileCatalog};
 use output::*;
 
 pub mod json_output;
@@ -15,11 +15,13 @@ pub use self::{
 };
 
 pub trait OutputCatalogResult {
-    fn output<T: Write>(&self, writer: &mut T, results: &Catalog) -> Result<()>;
+    fn output<T: Write>(&self, writer: &mut T, results: &[ProfileCatalog]) -> Result<()>;
 }
 
-fn value_for_field(field: &NodeField, catalog: &Catalog, node: &Node) -> String {
+fn value_for_field(field: &NodeField, profile_catalog: &ProfileCatalog, node: &Node) -> String {
+    let catalog = &profile_catalog.catalog;
     match *field {
+        NodeField::Profile => profile_catalog.profile.clone(),
         NodeField::Id => node.id.clone(),
         NodeField::Name => node.name.clone(),
         NodeField::MetaData(ref filter) => format_meta_data(&node.meta_data, filter.as_ref().map(|x| x.as_slice())),
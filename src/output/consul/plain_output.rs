@@ -1,4 +1,3 @@
-use service_world::consul::Catalog;
 use std::io::Write;
 
 use output::consul::*;
@@ -26,24 +25,27 @@ impl Default for PlainOutputCatalogResult {
 }
 
 impl OutputCatalogResult for PlainOutputCatalogResult {
-    fn output<T: Write>(&self, writer: &mut T, catalog: &Catalog) -> Result<()> {
+    fn output<T: Write>(&self, writer: &mut T, catalogs: &[ProfileCatalog]) -> Result<()> {
         let mut rows = Vec::new();
-        for service in catalog.services() {
-            if let Some(nodes) = catalog.nodes_by_service(service) {
-                for node in nodes {
-                    #[allow(clippy::match_single_binding)]
-                    match self.fields.as_slice() {
-                        // cf. https://github.com/rust-lang/rust/issues/23121
-                        // [NodeField::MetaData(Some(ref mdf))] if mdf.len() == 1 && mdf.first().unwrap() ==
-                        // "ec2_instance_id" => { rows.push(
-                        // node.metadata.get("ec2_instance_id").unwrap_or("n/a")); },
-                        _ => {
-                            let row = self
-                                .fields
-                                .iter()
-                                .map(|f| value_for_field(f, catalog, node))
-                                .collect::<Vec<_>>();
-                            rows.push(row);
+        for profile_catalog in catalogs {
+            let catalog = &profile_catalog.catalog;
+            for service in catalog.services() {
+                if let Some(nodes) = catalog.nodes_by_service(service) {
+                    for node in nodes {
+                        #[allow(clippy::match_single_binding)]
+                        match self.fields.as_slice() {
+                            // cf. https://github.com/rust-lang/rust/issues/23121
+                            // [NodeField::MetaData(Some(ref mdf))] if mdf.len() == 1 && mdf.first().unwrap() ==
+                            // "ec2_instance_id" => { rows.push(
+                            // node.metadata.get("ec2_instance_id").unwrap_or("n/a")); },
+                            _ => {
+                                let row = self
+                                    .fields
+                                    .iter()
+                                    .map(|f| value_for_field(f, profile_catalog, node))
+                                    .collect::<Vec<_>>();
+                                rows.push(row);
+                            }
                         }
                     }
                 }
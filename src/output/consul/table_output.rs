@@ -1,5 +1,4 @@
 use prettytable::{cell::Cell, format, row::Row, Table};
-use service_world::consul::Catalog;
 use std::io::Write;
 
 use modules::consul::NodeField;
@@ -28,7 +27,7 @@ impl Default for TableOutputCatalogResult {
 }
 
 impl OutputCatalogResult for TableOutputCatalogResult {
-    fn output<T: Write>(&self, writer: &mut T, catalog: &Catalog) -> Result<()> {
+    fn output<T: Write>(&self, writer: &mut T, catalogs: &[ProfileCatalog]) -> Result<()> {
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
 
@@ -44,15 +43,18 @@ impl OutputCatalogResult for TableOutputCatalogResult {
         // `InstanceDescriptorFields` need to allocate representations first, e.g.,
         // `InstanceDescriptorFields::Tags`
         let mut rows = Vec::new();
-        for service in catalog.services() {
-            if let Some(nodes) = catalog.nodes_by_service(service) {
-                for node in nodes {
-                    let row = self
-                        .fields
-                        .iter()
-                        .map(|f| value_for_field(f, catalog, node))
-                        .collect::<Vec<_>>();
-                    rows.push(row);
+        for profile_catalog in catalogs {
+            let catalog = &profile_catalog.catalog;
+            for service in catalog.services() {
+                if let Some(nodes) = catalog.nodes_by_service(service) {
+                    for node in nodes {
+                        let row = self
+                            .fields
+                            .iter()
+                            .map(|f| value_for_field(f, profile_catalog, node))
+                            .collect::<Vec<_>>();
+                        rows.push(row);
+                    }
                 }
             }
         }
@@ -66,6 +68,7 @@ impl OutputCatalogResult for TableOutputCatalogResult {
 
 fn header_for_field(field: &NodeField) -> &str {
     match *field {
+        NodeField::Profile => "Profile",
         NodeField::Id => "Node Id",
         NodeField::Name => "Node Name",
         NodeField::MetaData(_) => "Meta Data",
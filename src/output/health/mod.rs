@@ -13,25 +13,55 @@ pub trait OutputHealthCheck {
     fn output<T: Write>(&self, writer: &mut T, health_checks: &[HealthCheck]) -> Result<()>;
 }
 
-pub struct JsonOutputHealthCheck;
+/// Outputs the health checks as JSON; the profile of each health check is only output if `show_profile` is set.
+pub struct JsonOutputHealthCheck {
+    pub show_profile: bool,
+}
+
+/// `HealthCheck` without its profile
+#[derive(Serialize)]
+struct HealthCheckRef<'a> {
+    name:   &'a str,
+    result: &'a HealthCheckResult,
+}
 
 impl OutputHealthCheck for JsonOutputHealthCheck {
     fn output<T: Write>(&self, writer: &mut T, health_checks: &[HealthCheck]) -> Result<()> {
-        serde_json::to_writer_pretty(writer, health_checks).chain_err(|| ErrorKind::OutputFailed)
+        if self.show_profile {
+            serde_json::to_writer_pretty(writer, health_checks)
+        } else {
+            let health_checks: Vec<_> = health_checks
+                .iter()
+                .map(|hc| HealthCheckRef {
+                    name:   &hc.name,
+                    result: &hc.result,
+                })
+                .collect();
+            serde_json::to_writer_pretty(writer, &health_checks)
+        }
+        .chain_err(|| ErrorKind::OutputFailed)
     }
 }
 
-pub struct PlainOutputHealthCheck;
+pub struct PlainOutputHealthCheck {
+    pub show_profile: bool,
+}
 
 impl OutputHealthCheck for PlainOutputHealthCheck {
     fn output<T: Write>(&self, writer: &mut T, health_checks: &[HealthCheck]) -> Result<()> {
         for hc in health_checks {
+            let prefix = if self.show_profile {
+                format!("{} ", hc.profile)
+            } else {
+                String::new()
+            };
             match hc.result {
                 HealthCheckResult::Ok(ref checks) => {
                     for resource_name in checks.keys() {
                         let resource = &checks[resource_name]; // Safe, because iter over keys
                         let line = format!(
-                            "{} {} {} {} {}\n",
+                            "{}{} {} {} {} {}\n",
+                            prefix,
                             hc.name,
                             resource_name,
                             resource
@@ -48,7 +78,7 @@ impl OutputHealthCheck for PlainOutputHealthCheck {
                     }
                 }
                 HealthCheckResult::Failed(ref reason) => {
-                    let line = format!("{} Failed {}\n", hc.name, reason);
+                    let line = format!("{}{} Failed {}\n", prefix, hc.name, reason);
                     let _ = writer.write(line.as_bytes());
                 }
             }
@@ -57,20 +87,26 @@ impl OutputHealthCheck for PlainOutputHealthCheck {
     }
 }
 
-pub struct TableOutputHealthCheck;
+pub struct TableOutputHealthCheck {
+    pub show_profile: bool,
+}
 
 impl OutputHealthCheck for TableOutputHealthCheck {
     fn output<T: Write>(&self, writer: &mut T, health_checks: &[HealthCheck]) -> Result<()> {
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
 
-        table.set_titles(Row::new(vec![
+        let mut titles = Row::new(vec![
             Cell::new("Service"),
             Cell::new("Resource"),
             Cell::new("Health"),
             Cell::new("Since *"),
             Cell::new("Last update at *"),
-        ]));
+        ]);
+        if self.show_profile {
+            titles.insert_cell(0, Cell::new("Profile"));
+        }
+        table.set_titles(titles);
 
         for hc in health_checks {
             let mut previous_hc_name: Option<&str> = None;
@@ -78,20 +114,20 @@ impl OutputHealthCheck for TableOutputHealthCheck {
                 HealthCheckResult::Ok(ref checks) => {
                     if let Some(resource) = checks.get(GLOBAL_NAME) {
                         let row = make_row(&hc.name, &previous_hc_name, GLOBAL_NAME, resource);
-                        table.add_row(row);
+                        self.add_row(&mut table, &hc.profile, row);
                         previous_hc_name = Some(&hc.name);
                     }
                     for resource_name in checks.keys().filter(|x| &x[..] != GLOBAL_NAME) {
                         let resource = &checks[resource_name]; // Safe, because iter over keys
                         let row = make_row(&hc.name, &previous_hc_name, resource_name, resource);
-                        table.add_row(row);
+                        self.add_row(&mut table, &hc.profile, row);
                         previous_hc_name = Some(&hc.name);
                     }
                 }
                 HealthCheckResult::Failed(ref reason) => {
                     let failed = format!("Failed because {}", reason);
                     let row = Row::new(vec![Cell::new(&hc.name), Cell::new(&failed)]);
-                    table.add_row(row);
+                    self.add_row(&mut table, &hc.profile, row);
                 }
             }
         }
@@ -105,6 +141,15 @@ impl OutputHealthCheck for TableOutputHealthCheck {
     }
 }
 
+impl TableOutputHealthCheck {
+    fn add_row(&self, table: &mut Table, profile: &str, mut row: Row) {
+        if self.show_profile {
+            row.insert_cell(0, Cell::new(profile));
+        }
+        table.add_row(row);
+    }
+}
+
 fn make_row(hc_name: &str, previous_hc_name: &Option<&str>, resource_name: &str, resource: &HealthSample) -> Row {
     let service_cell = match previous_hc_name {
         Some(name) if name == &hc_name => Cell::new(""),
@@ -2,8 +2,9 @@ use prettytable::{cell::Cell, format, row::Row, Table};
 use serde_json;
 use std::{collections::HashMap, io::Write};
 
-use modules::infrastructure::Resource;
+use modules::infrastructure::{ImageListEntry, Resource};
 use output::*;
+use provider::format_launch_time;
 
 pub trait OutputResourceListResult {
     fn output<T: Write>(&self, writer: &mut T, results: &[Resource]) -> Result<()>;
@@ -51,6 +52,91 @@ impl OutputResourceListResult for TableOutputResourceListResult {
     }
 }
 
+pub trait OutputImageList {
+    fn output<T: Write>(&self, writer: &mut T, entries: &[ImageListEntry]) -> Result<()>;
+}
+
+pub struct JsonOutputImageList;
+
+impl OutputImageList for JsonOutputImageList {
+    fn output<T: Write>(&self, writer: &mut T, entries: &[ImageListEntry]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, entries).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct PlainOutputImageList;
+
+impl OutputImageList for PlainOutputImageList {
+    fn output<T: Write>(&self, writer: &mut T, entries: &[ImageListEntry]) -> Result<()> {
+        for entry in entries {
+            let fields = image_list_entry_fields(entry);
+            // Tags may contain spaces, so they are omitted
+            let line = format!("{}\n", fields[..fields.len() - 1].join(" "));
+            let _ = writer.write(line.as_bytes());
+        }
+        Ok(())
+    }
+}
+
+pub struct TableOutputImageList;
+
+impl OutputImageList for TableOutputImageList {
+    fn output<T: Write>(&self, writer: &mut T, entries: &[ImageListEntry]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(vec![
+            Cell::new("Project"),
+            Cell::new("Resource"),
+            Cell::new("Image Id"),
+            Cell::new("Name"),
+            Cell::new("Creation Date"),
+            Cell::new("Running Instances"),
+            Cell::new("Flags"),
+            Cell::new("Tags"),
+        ]));
+
+        for entry in entries {
+            let row = Row::new(image_list_entry_fields(entry).iter().map(|x| Cell::new(x)).collect());
+            table.add_row(row);
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+/// Returns project, resource, image id, name, creation date, running instances, flags, and tags of an entry.
+fn image_list_entry_fields(entry: &ImageListEntry) -> Vec<String> {
+    let empty = || String::from("-");
+    let resource = entry.resource.as_ref();
+    let image = entry.image.as_ref();
+    let flags: Vec<_> = entry.flags.iter().map(|x| x.as_str()).collect();
+
+    vec![
+        resource.map(|x| x.project.clone()).unwrap_or_else(empty),
+        resource.map(|x| x.name.clone()).unwrap_or_else(empty),
+        image.map(|x| x.image_id.clone()).unwrap_or_else(empty),
+        image.and_then(|x| x.name.clone()).unwrap_or_else(empty),
+        image
+            .and_then(|x| x.creation_date.as_ref().map(format_launch_time))
+            .unwrap_or_else(empty),
+        entry.running_instances.to_string(),
+        if flags.is_empty() { empty() } else { flags.join(",") },
+        image
+            .and_then(|x| x.tags.as_ref().map(format_tags))
+            .unwrap_or_else(empty),
+    ]
+}
+
+fn format_tags(tags: &HashMap<String, Option<String>>) -> String {
+    let mut tags: Vec<_> = tags
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v.as_ref().map(|x| x.as_str()).unwrap_or("")))
+        .collect();
+    tags.sort();
+    tags.join(", ")
+}
+
 fn resources_by_project(resources: &[Resource]) -> HashMap<&str, Vec<&str>> {
     let mut map = HashMap::new();
 
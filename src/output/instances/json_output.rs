@@ -1,7 +1,7 @@
 use serde_json;
 
 use output::instances::*;
-use provider::{InstanceDescriptor, StateChange};
+use provider::{pricing::CostSummary, Image, InstanceDescriptor, Snapshot, StateChange, TagChange};
 use utils::command::CommandResult;
 
 pub struct JsonOutputInstances;
@@ -20,6 +20,38 @@ impl OutputStateChanges for JsonOutputStateChanges {
     }
 }
 
+pub struct JsonOutputTagChanges;
+
+impl OutputTagChanges for JsonOutputTagChanges {
+    fn output<T: Write>(&self, writer: &mut T, tag_changes: &[TagChange]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, tag_changes).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct JsonOutputSnapshots;
+
+impl OutputSnapshots for JsonOutputSnapshots {
+    fn output<T: Write>(&self, writer: &mut T, snapshots: &[Snapshot]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, snapshots).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct JsonOutputImages;
+
+impl OutputImages for JsonOutputImages {
+    fn output<T: Write>(&self, writer: &mut T, images: &[Image]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, images).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct JsonOutputCostSummaries;
+
+impl OutputCostSummaries for JsonOutputCostSummaries {
+    fn output<T: Write>(&self, writer: &mut T, summaries: &[CostSummary]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, summaries).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
 pub struct JsonOutputCommandResults;
 
 impl OutputCommandResults for JsonOutputCommandResults {
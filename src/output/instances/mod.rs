@@ -1,7 +1,7 @@
 use std::io::Write;
 
 use output::*;
-use provider::{InstanceDescriptor, StateChange};
+use provider::{pricing::CostSummary, Image, InstanceDescriptor, Snapshot, StateChange, TagChange};
 use utils::command::CommandResult;
 
 pub mod json_output;
@@ -9,9 +9,25 @@ pub mod plain_output;
 pub mod table_output;
 
 pub use self::{
-    json_output::{JsonOutputCommandResults, JsonOutputInstances, JsonOutputStateChanges},
-    plain_output::PlainOutputInstances,
-    table_output::{TableOutputCommandResults, TableOutputInstances, TableOutputStatusChanges},
+    json_output::{
+        JsonOutputCommandResults,
+        JsonOutputCostSummaries,
+        JsonOutputImages,
+        JsonOutputInstances,
+        JsonOutputSnapshots,
+        JsonOutputStateChanges,
+        JsonOutputTagChanges,
+    },
+    plain_output::{PlainOutputCostSummaries, PlainOutputImages, PlainOutputInstances, PlainOutputSnapshots},
+    table_output::{
+        TableOutputCommandResults,
+        TableOutputCostSummaries,
+        TableOutputImages,
+        TableOutputInstances,
+        TableOutputSnapshots,
+        TableOutputStatusChanges,
+        TableOutputTagChanges,
+    },
 };
 
 pub trait OutputInstances {
@@ -22,6 +38,22 @@ pub trait OutputStateChanges {
     fn output<T: Write>(&self, writer: &mut T, state_changes: &[StateChange]) -> Result<()>;
 }
 
+pub trait OutputTagChanges {
+    fn output<T: Write>(&self, writer: &mut T, tag_changes: &[TagChange]) -> Result<()>;
+}
+
+pub trait OutputSnapshots {
+    fn output<T: Write>(&self, writer: &mut T, snapshots: &[Snapshot]) -> Result<()>;
+}
+
+pub trait OutputImages {
+    fn output<T: Write>(&self, writer: &mut T, images: &[Image]) -> Result<()>;
+}
+
+pub trait OutputCostSummaries {
+    fn output<T: Write>(&self, writer: &mut T, summaries: &[CostSummary]) -> Result<()>;
+}
+
 pub trait OutputCommandResults {
     fn output<T: Write>(&self, writer: &mut T, results: &[CommandResult]) -> Result<()>;
 }
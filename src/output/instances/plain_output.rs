@@ -1,7 +1,15 @@
 use std::{collections::HashMap, io::Write};
 
 use output::instances::*;
-use provider::{InstanceDescriptor, InstanceDescriptorFields};
+use provider::{
+    format_cost,
+    format_launch_time,
+    pricing::CostSummary,
+    Image,
+    InstanceDescriptor,
+    InstanceDescriptorFields,
+    Snapshot,
+};
 
 pub struct PlainOutputInstances {
     pub fields: Vec<InstanceDescriptorFields>,
@@ -47,23 +55,91 @@ impl OutputInstances for PlainOutputInstances {
     }
 }
 
+/// Outputs one snapshot per line as `<snapshot id>;<instance id>;<volume id>;<device name>;<state>`.
+pub struct PlainOutputSnapshots;
+
+impl OutputSnapshots for PlainOutputSnapshots {
+    fn output<T: Write>(&self, writer: &mut T, snapshots: &[Snapshot]) -> Result<()> {
+        for s in snapshots {
+            let line = format!(
+                "{};{};{};{};{}\n",
+                s.snapshot_id,
+                s.instance_id,
+                s.volume_id,
+                s.device_name.as_ref().map(String::as_str).unwrap_or("-"),
+                s.state.as_ref().map(String::as_str).unwrap_or("-")
+            );
+            let _ = writer.write(line.as_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+/// Outputs one image per line as `<image id>;<instance id>;<name>;<state>`.
+pub struct PlainOutputImages;
+
+impl OutputImages for PlainOutputImages {
+    fn output<T: Write>(&self, writer: &mut T, images: &[Image]) -> Result<()> {
+        for i in images {
+            let line = format!(
+                "{};{};{};{}\n",
+                i.image_id,
+                i.instance_id,
+                i.name,
+                i.state.as_ref().map(String::as_str).unwrap_or("-")
+            );
+            let _ = writer.write(line.as_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+pub struct PlainOutputCostSummaries;
+
+impl OutputCostSummaries for PlainOutputCostSummaries {
+    fn output<T: Write>(&self, writer: &mut T, summaries: &[CostSummary]) -> Result<()> {
+        for s in summaries {
+            let line = format!(
+                "{};{};{};{};{}\n",
+                s.group.as_ref().map(String::as_str).unwrap_or("-"),
+                s.instances,
+                s.unpriced_instances,
+                format_cost(s.hourly_cost),
+                format_cost(s.monthly_cost),
+            );
+            let _ = writer.write(line.as_bytes());
+        }
+
+        Ok(())
+    }
+}
+
 fn value_for_field(field: &InstanceDescriptorFields, instance: &InstanceDescriptor) -> String {
     match *field {
+        InstanceDescriptorFields::Account => instance.account.clone(),
         InstanceDescriptorFields::BlockDeviceMappings => {
             instance.block_device_mappings.as_ref().map(|bdms| bdms.join("\n"))
         }
+        InstanceDescriptorFields::Cost => instance.cost.map(format_cost),
         InstanceDescriptorFields::Hypervisor => instance.hypervisor.clone(),
         InstanceDescriptorFields::IamInstanceProfile => instance.iam_instance_profile.clone(),
         InstanceDescriptorFields::ImageId => instance.image_id.clone(),
         InstanceDescriptorFields::InstanceId => instance.instance_id.clone(),
         InstanceDescriptorFields::InstanceType => instance.instance_type.clone(),
-        InstanceDescriptorFields::LaunchTime => instance.launch_time.clone(),
+        InstanceDescriptorFields::Ipv6Addresses => instance.field_values(field).map(|x| x.join("\n")),
+        InstanceDescriptorFields::LaunchTime => instance.launch_time.as_ref().map(format_launch_time),
         InstanceDescriptorFields::Monitoring => instance.monitoring.clone(),
+        InstanceDescriptorFields::NetworkInterfaces => instance.field_values(field).map(|x| x.join("\n")),
         InstanceDescriptorFields::Placement => instance.placement.clone(),
         InstanceDescriptorFields::PrivateDnsName => instance.private_dns_name.clone(),
         InstanceDescriptorFields::PrivateIpAddress => instance.private_ip_address.clone(),
+        InstanceDescriptorFields::PrivateIpAddresses => instance.field_values(field).map(|x| x.join("\n")),
+        InstanceDescriptorFields::Profile => instance.profile.clone(),
         InstanceDescriptorFields::PublicDnsName => instance.public_dns_name.clone(),
         InstanceDescriptorFields::PublicIpAddress => instance.public_ip_address.clone(),
+        InstanceDescriptorFields::Region => instance.region.clone(),
         InstanceDescriptorFields::RootDeviceName => instance.root_device_name.clone(),
         InstanceDescriptorFields::RootDeviceType => instance.root_device_type.clone(),
         InstanceDescriptorFields::SecurityGroups => instance.security_groups.as_ref().map(|sgs| sgs.join("\n")),
@@ -76,6 +152,7 @@ fn value_for_field(field: &InstanceDescriptorFields, instance: &InstanceDescript
             ))
         }
         InstanceDescriptorFields::VirtualizationType => instance.virtualization_type.clone(),
+        InstanceDescriptorFields::Volumes => instance.field_values(field).map(|x| x.join("\n")),
         InstanceDescriptorFields::VpcId => instance.vpc_id.clone(),
     }
     .unwrap_or_else(|| String::from("-"))
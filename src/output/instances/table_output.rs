@@ -3,7 +3,17 @@ use std::{collections::HashMap, io::Write};
 use utils::command::ExitStatus;
 
 use output::instances::*;
-use provider::{InstanceDescriptor, InstanceDescriptorFields, StateChange};
+use provider::{
+    format_cost,
+    format_launch_time,
+    pricing::{CostSummary, HOURS_PER_MONTH},
+    Image,
+    InstanceDescriptor,
+    InstanceDescriptorFields,
+    Snapshot,
+    StateChange,
+    TagChange,
+};
 use utils::command::CommandResult;
 
 pub struct TableOutputInstances {
@@ -59,19 +69,26 @@ impl OutputInstances for TableOutputInstances {
 
 fn header_for_field(field: &InstanceDescriptorFields) -> &str {
     match *field {
+        InstanceDescriptorFields::Account => "Account",
         InstanceDescriptorFields::BlockDeviceMappings => "Block Device Mappings",
+        InstanceDescriptorFields::Cost => "Cost [USD/h]",
         InstanceDescriptorFields::Hypervisor => "Hypervisor",
         InstanceDescriptorFields::IamInstanceProfile => "Iam Instance Profile",
         InstanceDescriptorFields::ImageId => "Image Id",
         InstanceDescriptorFields::InstanceId => "Instance Id",
         InstanceDescriptorFields::InstanceType => "Instance Type",
+        InstanceDescriptorFields::Ipv6Addresses => "IPv6 Addresses",
         InstanceDescriptorFields::LaunchTime => "Launch Time",
         InstanceDescriptorFields::Monitoring => "Monitoring",
+        InstanceDescriptorFields::NetworkInterfaces => "Network Interfaces",
         InstanceDescriptorFields::Placement => "Placement",
         InstanceDescriptorFields::PrivateDnsName => "Private DNS Name",
         InstanceDescriptorFields::PrivateIpAddress => "Private IP Address",
+        InstanceDescriptorFields::PrivateIpAddresses => "Private IP Addresses",
+        InstanceDescriptorFields::Profile => "Profile",
         InstanceDescriptorFields::PublicDnsName => "Public DNS Name",
         InstanceDescriptorFields::PublicIpAddress => "Public IP Address",
+        InstanceDescriptorFields::Region => "Region",
         InstanceDescriptorFields::RootDeviceName => "Root Device Name",
         InstanceDescriptorFields::RootDeviceType => "Root Device Type",
         InstanceDescriptorFields::SecurityGroups => "Security Groups",
@@ -79,27 +96,35 @@ fn header_for_field(field: &InstanceDescriptorFields) -> &str {
         InstanceDescriptorFields::StateReason => "State Reason",
         InstanceDescriptorFields::Tags(_) => "Tags",
         InstanceDescriptorFields::VirtualizationType => "Virtualization Type",
+        InstanceDescriptorFields::Volumes => "Volumes",
         InstanceDescriptorFields::VpcId => "Vpc Id",
     }
 }
 
 fn value_for_field(field: &InstanceDescriptorFields, instance: &InstanceDescriptor) -> String {
     match *field {
+        InstanceDescriptorFields::Account => instance.account.clone(),
         InstanceDescriptorFields::BlockDeviceMappings => {
             instance.block_device_mappings.as_ref().map(|bdms| bdms.join("\n"))
         }
+        InstanceDescriptorFields::Cost => instance.cost.map(format_cost),
         InstanceDescriptorFields::Hypervisor => instance.hypervisor.clone(),
         InstanceDescriptorFields::IamInstanceProfile => instance.iam_instance_profile.clone(),
         InstanceDescriptorFields::ImageId => instance.image_id.clone(),
         InstanceDescriptorFields::InstanceId => instance.instance_id.clone(),
         InstanceDescriptorFields::InstanceType => instance.instance_type.clone(),
-        InstanceDescriptorFields::LaunchTime => instance.launch_time.clone(),
+        InstanceDescriptorFields::Ipv6Addresses => instance.field_values(field).map(|x| x.join("\n")),
+        InstanceDescriptorFields::LaunchTime => instance.launch_time.as_ref().map(format_launch_time),
         InstanceDescriptorFields::Monitoring => instance.monitoring.clone(),
+        InstanceDescriptorFields::NetworkInterfaces => instance.field_values(field).map(|x| x.join("\n")),
         InstanceDescriptorFields::Placement => instance.placement.clone(),
         InstanceDescriptorFields::PrivateDnsName => instance.private_dns_name.clone(),
         InstanceDescriptorFields::PrivateIpAddress => instance.private_ip_address.clone(),
+        InstanceDescriptorFields::PrivateIpAddresses => instance.field_values(field).map(|x| x.join("\n")),
+        InstanceDescriptorFields::Profile => instance.profile.clone(),
         InstanceDescriptorFields::PublicDnsName => instance.public_dns_name.clone(),
         InstanceDescriptorFields::PublicIpAddress => instance.public_ip_address.clone(),
+        InstanceDescriptorFields::Region => instance.region.clone(),
         InstanceDescriptorFields::RootDeviceName => instance.root_device_name.clone(),
         InstanceDescriptorFields::RootDeviceType => instance.root_device_type.clone(),
         InstanceDescriptorFields::SecurityGroups => instance.security_groups.as_ref().map(|sgs| sgs.join("\n")),
@@ -113,6 +138,7 @@ fn value_for_field(field: &InstanceDescriptorFields, instance: &InstanceDescript
             }
         }
         InstanceDescriptorFields::VirtualizationType => instance.virtualization_type.clone(),
+        InstanceDescriptorFields::Volumes => instance.field_values(field).map(|x| x.join("\n")),
         InstanceDescriptorFields::VpcId => instance.vpc_id.clone(),
     }
     .unwrap_or_else(|| String::from("-"))
@@ -171,6 +197,130 @@ impl OutputStateChanges for TableOutputStatusChanges {
     }
 }
 
+pub struct TableOutputTagChanges;
+
+impl OutputTagChanges for TableOutputTagChanges {
+    fn output<T: Write>(&self, writer: &mut T, tag_changes: &[TagChange]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(
+            ["Instance Id", "Tag", "Previous Value", "Value"]
+                .iter()
+                .map(|x| Cell::new(x))
+                .collect::<Vec<_>>(),
+        ));
+
+        for change in tag_changes {
+            table.add_row(Row::new(vec![
+                Cell::new(&change.instance_id),
+                Cell::new(&change.key),
+                Cell::new(change.previous_value.as_ref().map(String::as_str).unwrap_or("- n/a -")),
+                Cell::new(change.value.as_ref().map(String::as_str).unwrap_or("- n/a -")),
+            ]));
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct TableOutputSnapshots;
+
+impl OutputSnapshots for TableOutputSnapshots {
+    fn output<T: Write>(&self, writer: &mut T, snapshots: &[Snapshot]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(
+            ["Snapshot Id", "Instance Id", "Volume Id", "Device Name", "State"]
+                .iter()
+                .map(|x| Cell::new(x))
+                .collect::<Vec<_>>(),
+        ));
+
+        for s in snapshots {
+            table.add_row(Row::new(vec![
+                Cell::new(&s.snapshot_id),
+                Cell::new(&s.instance_id),
+                Cell::new(&s.volume_id),
+                Cell::new(s.device_name.as_ref().map(String::as_str).unwrap_or("- n/a -")),
+                Cell::new(s.state.as_ref().map(String::as_str).unwrap_or("- n/a -")),
+            ]));
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct TableOutputImages;
+
+impl OutputImages for TableOutputImages {
+    fn output<T: Write>(&self, writer: &mut T, images: &[Image]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(
+            ["Image Id", "Instance Id", "Name", "State"]
+                .iter()
+                .map(|x| Cell::new(x))
+                .collect::<Vec<_>>(),
+        ));
+
+        for i in images {
+            table.add_row(Row::new(vec![
+                Cell::new(&i.image_id),
+                Cell::new(&i.instance_id),
+                Cell::new(&i.name),
+                Cell::new(i.state.as_ref().map(String::as_str).unwrap_or("- n/a -")),
+            ]));
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct TableOutputCostSummaries {
+    /// Title of the group column, e.g., the tag instances are grouped by
+    pub group_title: String,
+}
+
+impl OutputCostSummaries for TableOutputCostSummaries {
+    fn output<T: Write>(&self, writer: &mut T, summaries: &[CostSummary]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(
+            [self.group_title.as_str(), "Instances", "Unpriced", "Cost [USD/h]", "Cost [USD/month]"]
+                .iter()
+                .map(|x| Cell::new(x))
+                .collect::<Vec<_>>(),
+        ));
+
+        for s in summaries {
+            table.add_row(Row::new(vec![
+                Cell::new(s.group.as_ref().map(String::as_str).unwrap_or("-")),
+                Cell::new(&s.instances.to_string()),
+                Cell::new(&s.unpriced_instances.to_string()),
+                Cell::new_align(&format!("{:.2}", s.hourly_cost), format::Alignment::RIGHT),
+                Cell::new_align(&format!("{:.2}", s.monthly_cost), format::Alignment::RIGHT),
+            ]));
+        }
+
+        if summaries.len() > 1 {
+            let hourly_cost: f64 = summaries.iter().map(|x| x.hourly_cost).sum();
+            table.add_row(Row::new(vec![
+                Cell::new("Total"),
+                Cell::new(&summaries.iter().map(|x| x.instances).sum::<usize>().to_string()),
+                Cell::new(&summaries.iter().map(|x| x.unpriced_instances).sum::<usize>().to_string()),
+                Cell::new_align(&format!("{:.2}", hourly_cost), format::Alignment::RIGHT),
+                Cell::new_align(&format!("{:.2}", hourly_cost * HOURS_PER_MONTH), format::Alignment::RIGHT),
+            ]));
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
 pub struct TableOutputCommandResults {
     pub show_all: bool,
 }
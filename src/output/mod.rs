@@ -5,6 +5,8 @@ pub mod consul;
 pub mod health;
 pub mod infrastructure;
 pub mod instances;
+pub mod runs;
+pub mod security_groups;
 pub mod statuspages;
 pub mod stories;
 
@@ -0,0 +1,130 @@
+use prettytable::{cell::Cell, format, row::Row, Table};
+use serde_json;
+use std::io::Write;
+
+use history::{RunRecord, TargetRecord};
+use output::*;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub trait OutputRuns {
+    fn output<T: Write>(&self, writer: &mut T, runs: &[RunRecord]) -> Result<()>;
+}
+
+pub struct JsonOutputRuns;
+
+impl OutputRuns for JsonOutputRuns {
+    fn output<T: Write>(&self, writer: &mut T, runs: &[RunRecord]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, runs).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct TableOutputRuns;
+
+impl OutputRuns for TableOutputRuns {
+    fn output<T: Write>(&self, writer: &mut T, runs: &[RunRecord]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(
+            ["Run Id", "Started", "Duration", "Profile", "User", "Command", "Targets", "Failed"]
+                .iter()
+                .map(|x| Cell::new(x))
+                .collect::<Vec<_>>(),
+        ));
+
+        for run in runs {
+            table.add_row(Row::new(vec![
+                Cell::new(&run.id),
+                Cell::new(&run.started.format(TIME_FORMAT).to_string()),
+                Cell::new(&format_duration(run)),
+                Cell::new(&run.profile),
+                Cell::new(run.user.as_ref().map(String::as_str).unwrap_or("-")),
+                Cell::new(&format_ceres_command(&run.command)),
+                Cell::new(&run.targets.len().to_string()),
+                Cell::new(&run.failed_targets().len().to_string()),
+            ]));
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub trait OutputRun {
+    fn output<T: Write>(&self, writer: &mut T, run: &RunRecord) -> Result<()>;
+}
+
+pub struct JsonOutputRun;
+
+impl OutputRun for JsonOutputRun {
+    fn output<T: Write>(&self, writer: &mut T, run: &RunRecord) -> Result<()> {
+        serde_json::to_writer_pretty(writer, run).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct TableOutputRun;
+
+impl OutputRun for TableOutputRun {
+    fn output<T: Write>(&self, writer: &mut T, run: &RunRecord) -> Result<()> {
+        let mut summary = Table::new();
+        summary.set_format(*format::consts::FORMAT_CLEAN);
+        let finished = run.finished.format(TIME_FORMAT).to_string();
+        let entries = vec![
+            ("Run Id:", run.id.clone()),
+            ("Command:", format_ceres_command(&run.command)),
+            ("Profile:", run.profile.clone()),
+            ("User:", run.user.clone().unwrap_or_else(|| "-".to_owned())),
+            ("Started:", run.started.format(TIME_FORMAT).to_string()),
+            ("Finished:", format!("{} ({})", finished, format_duration(run))),
+        ];
+        for (key, value) in entries {
+            summary.add_row(Row::new(vec![Cell::new(key), Cell::new(&value)]));
+        }
+        summary.print(writer).chain_err(|| ErrorKind::OutputFailed)?;
+        writeln!(writer).chain_err(|| ErrorKind::OutputFailed)?;
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(
+            ["Target", "Command", "Exit Status", "Log File"]
+                .iter()
+                .map(|x| Cell::new(x))
+                .collect::<Vec<_>>(),
+        ));
+
+        for target in &run.targets {
+            table.add_row(Row::new(vec![
+                Cell::new(&target.id),
+                Cell::new(&format_target_command(target)),
+                Cell::new(&format!("{:?}", target.exit_status)),
+                Cell::new(&target.log.to_string_lossy()),
+            ]));
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+/// Formats the ceres command line without the path of the executable.
+fn format_ceres_command(command: &[String]) -> String {
+    let mut args = command.iter().skip(1).map(String::as_str).collect::<Vec<_>>();
+    args.insert(0, "ceres");
+    args.join(" ")
+}
+
+fn format_target_command(target: &TargetRecord) -> String {
+    match target.command {
+        Some(ref command) => {
+            let mut line = vec![command.cmd.as_str()];
+            line.extend(command.args.iter().flat_map(|args| args.iter().map(String::as_str)));
+            line.join(" ")
+        }
+        None => "-".to_owned(),
+    }
+}
+
+fn format_duration(run: &RunRecord) -> String {
+    let secs = run.finished.signed_duration_since(run.started).num_seconds().max(0);
+    format!("{}m {:02}s", secs / 60, secs % 60)
+}
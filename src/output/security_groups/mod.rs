@@ -0,0 +1,155 @@
+use prettytable::{cell::Cell, format, row::Row, Table};
+use serde_json;
+use std::io::Write;
+
+use modules::security_groups::{Finding, SecurityGroupUsage};
+use output::*;
+use provider::SecurityGroupRule;
+
+pub trait OutputSecurityGroups {
+    fn output<T: Write>(&self, writer: &mut T, usages: &[SecurityGroupUsage]) -> Result<()>;
+}
+
+pub struct JsonOutputSecurityGroups;
+
+impl OutputSecurityGroups for JsonOutputSecurityGroups {
+    fn output<T: Write>(&self, writer: &mut T, usages: &[SecurityGroupUsage]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, usages).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+/// Outputs one line per rule; groups without rules get a single line without rule.
+pub struct PlainOutputSecurityGroups;
+
+impl OutputSecurityGroups for PlainOutputSecurityGroups {
+    fn output<T: Write>(&self, writer: &mut T, usages: &[SecurityGroupUsage]) -> Result<()> {
+        for usage in usages {
+            let sg = &usage.security_group;
+            let rules: Vec<_> = sg
+                .ingress
+                .iter()
+                .map(|x| ("ingress", x.to_string()))
+                .chain(sg.egress.iter().map(|x| ("egress", x.to_string())))
+                .collect();
+            let instance_ids = join_or_empty(&usage.instance_ids, ",");
+            let group_name = sg.group_name.as_ref().map(|x| x.as_str()).unwrap_or("-");
+
+            if rules.is_empty() {
+                let line = format!("{};{};-;-;{}\n", sg.group_id, group_name, instance_ids);
+                let _ = writer.write(line.as_bytes());
+            }
+            for (direction, rule) in rules {
+                let line = format!("{};{};{};{};{}\n", sg.group_id, group_name, direction, rule, instance_ids);
+                let _ = writer.write(line.as_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct TableOutputSecurityGroups;
+
+impl OutputSecurityGroups for TableOutputSecurityGroups {
+    fn output<T: Write>(&self, writer: &mut T, usages: &[SecurityGroupUsage]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(vec![
+            Cell::new("Group Id"),
+            Cell::new("Name"),
+            Cell::new("VPC Id"),
+            Cell::new("Ingress"),
+            Cell::new("Egress"),
+            Cell::new("Instances"),
+        ]));
+
+        for usage in usages {
+            let sg = &usage.security_group;
+            let row = Row::new(vec![
+                Cell::new(&sg.group_id),
+                Cell::new(sg.group_name.as_ref().map(|x| x.as_str()).unwrap_or("-")),
+                Cell::new(sg.vpc_id.as_ref().map(|x| x.as_str()).unwrap_or("-")),
+                Cell::new(&format_rules(&sg.ingress)),
+                Cell::new(&format_rules(&sg.egress)),
+                Cell::new(&join_or_empty(&usage.instance_ids, "\n")),
+            ]);
+            table.add_row(row);
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub trait OutputFindings {
+    fn output<T: Write>(&self, writer: &mut T, findings: &[Finding]) -> Result<()>;
+}
+
+pub struct JsonOutputFindings;
+
+impl OutputFindings for JsonOutputFindings {
+    fn output<T: Write>(&self, writer: &mut T, findings: &[Finding]) -> Result<()> {
+        serde_json::to_writer_pretty(writer, findings).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+pub struct PlainOutputFindings;
+
+impl OutputFindings for PlainOutputFindings {
+    fn output<T: Write>(&self, writer: &mut T, findings: &[Finding]) -> Result<()> {
+        for finding in findings {
+            let line = format!(
+                "{};{};{};{};{}\n",
+                finding.group_id,
+                finding.group_name.as_ref().map(|x| x.as_str()).unwrap_or("-"),
+                finding.kind.as_str(),
+                finding.rule.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "-".to_owned()),
+                join_or_empty(&finding.instance_ids, ","),
+            );
+            let _ = writer.write(line.as_bytes());
+        }
+        Ok(())
+    }
+}
+
+pub struct TableOutputFindings;
+
+impl OutputFindings for TableOutputFindings {
+    fn output<T: Write>(&self, writer: &mut T, findings: &[Finding]) -> Result<()> {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+        table.set_titles(Row::new(vec![
+            Cell::new("Group Id"),
+            Cell::new("Name"),
+            Cell::new("Finding"),
+            Cell::new("Rule"),
+            Cell::new("Instances"),
+        ]));
+
+        for finding in findings {
+            let row = Row::new(vec![
+                Cell::new(&finding.group_id),
+                Cell::new(finding.group_name.as_ref().map(|x| x.as_str()).unwrap_or("-")),
+                Cell::new(finding.kind.as_str()),
+                Cell::new(&finding.rule.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "-".to_owned())),
+                Cell::new(&join_or_empty(&finding.instance_ids, "\n")),
+            ]);
+            table.add_row(row);
+        }
+
+        table.print(writer).chain_err(|| ErrorKind::OutputFailed)
+    }
+}
+
+fn format_rules(rules: &[SecurityGroupRule]) -> String {
+    let rules: Vec<_> = rules.iter().map(ToString::to_string).collect();
+    join_or_empty(&rules, "\n")
+}
+
+fn join_or_empty(values: &[String], separator: &str) -> String {
+    if values.is_empty() {
+        "-".to_owned()
+    } else {
+        values.join(separator)
+    }
+}
@@ -1,9 +1,27 @@
+use chrono::{DateTime, Utc};
 use rusoto_core::{request::HttpClient, Region};
 use rusoto_credential::StaticProvider;
 use rusoto_ec2::{
     self as ec2,
+    AttributeValue,
+    CreateImageError,
+    CreateImageRequest,
+    CreateSnapshotError,
+    CreateSnapshotRequest,
+    CreateTagsError,
+    CreateTagsRequest,
+    DeleteTagsError,
+    DeleteTagsRequest,
+    DescribeImagesRequest,
     DescribeInstancesRequest,
+    DescribeSecurityGroupsRequest,
+    DescribeSnapshotsRequest,
+    DescribeVolumesRequest,
     Ec2,
+    ModifyInstanceAttributeError,
+    ModifyInstanceAttributeRequest,
+    RebootInstancesError,
+    RebootInstancesRequest,
     StartInstancesError,
     StartInstancesRequest,
     StopInstancesError,
@@ -13,35 +31,137 @@ use rusoto_ec2::{
 };
 use rusoto_sts::{StsAssumeRoleSessionCredentialsProvider, StsClient};
 use serde::{
-    de::{self, Deserializer, Visitor},
+    de::{self, Deserialize, Deserializer, Visitor},
     ser::Serializer,
 };
-use std::{collections::HashMap, default::Default, fmt, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    default::Default,
+    fmt,
+    iter,
+    str::FromStr,
+    thread,
+};
 
 use provider::{
+    create_dry_run_results,
+    CreateImage,
+    CreateSnapshots,
+    CreateTags,
+    DeleteTags,
+    DescribeImages,
     DescribeInstance,
     DescribeInstances,
+    DescribeSecurityGroups,
     Error as ProviderError,
     ErrorKind as ProviderErrorKind,
+    Image,
+    ImageDescriptor,
     InstanceDescriptor,
     InstanceId,
+    ModifyInstanceType,
+    NetworkInterface,
+    RebootInstances,
     Result as ProviderResult,
+    SecurityGroup,
+    SecurityGroupRule,
+    Snapshot,
     StartInstances,
     StateChange,
     StopInstances,
     TerminateInstances,
+    Volume,
 };
 
 const EMPTY: &str = "-";
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Aws {
-    pub access_key_id:     String,
-    pub secret_access_key: String,
-    pub token:             Option<String>,
+    pub access_key_id:        String,
+    pub secret_access_key:    String,
+    pub token:                Option<String>,
     #[serde(serialize_with = "ser_region", deserialize_with = "de_ser_region")]
-    pub region:            Region,
-    pub role_arn:          String,
+    pub region:               Region,
+    pub role_arn:             String,
+    #[serde(default, serialize_with = "ser_regions", deserialize_with = "de_ser_regions")]
+    pub additional_regions:   Vec<Region>,
+    #[serde(default)]
+    pub additional_role_arns: Vec<String>,
+}
+
+impl Aws {
+    /// Returns all combinations of regions and roles to query; the primary region and role come first.
+    fn targets(&self) -> Vec<Target> {
+        let role_arns: Vec<&String> = iter::once(&self.role_arn).chain(self.additional_role_arns.iter()).collect();
+        iter::once(&self.region)
+            .chain(self.additional_regions.iter())
+            .flat_map(|region| {
+                role_arns.iter().map(move |role_arn| Target {
+                    region:   region.clone(),
+                    role_arn: (*role_arn).clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+    region:   Region,
+    role_arn: String,
+}
+
+impl Target {
+    fn instance_descriptor(&self, instance: ec2::Instance) -> InstanceDescriptor {
+        let mut instance: InstanceDescriptor = instance.into();
+        instance.region = Some(self.region.name().to_owned());
+        instance.account = account_from_role_arn(&self.role_arn);
+
+        instance
+    }
+
+    fn security_group(&self, sg: ec2::SecurityGroup) -> Option<SecurityGroup> {
+        let rules = |permissions: Option<Vec<ec2::IpPermission>>| {
+            permissions
+                .unwrap_or_else(Vec::new)
+                .iter()
+                .map(security_group_rule_from)
+                .collect()
+        };
+        let group_id = sg.group_id?;
+        Some(SecurityGroup {
+            account:     account_from_role_arn(&self.role_arn),
+            region:      Some(self.region.name().to_owned()),
+            group_id,
+            group_name:  sg.group_name,
+            description: sg.description,
+            vpc_id:      sg.vpc_id,
+            ingress:     rules(sg.ip_permissions),
+            egress:      rules(sg.ip_permissions_egress),
+        })
+    }
+
+    fn image_descriptor(&self, image: ec2::Image) -> Option<ImageDescriptor> {
+        let image_id = image.image_id?;
+        Some(ImageDescriptor {
+            account:       account_from_role_arn(&self.role_arn),
+            creation_date: image.creation_date.as_ref().and_then(|x| parse_launch_time(x)),
+            image_id,
+            name:          image.name,
+            region:        Some(self.region.name().to_owned()),
+            state:         image.state,
+            tags:          image.tags.map(vec_tags_to_hashmap),
+        })
+    }
+}
+
+/// Extracts the account id from a role ARN like `arn:aws:iam::123456789012:role/Name`.
+fn account_from_role_arn(role_arn: &str) -> Option<String> {
+    role_arn
+        .split(':')
+        .nth(4)
+        .filter(|account| !account.is_empty())
+        .map(ToOwned::to_owned)
 }
 
 fn ser_region<S>(region: &Region, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
@@ -77,6 +197,24 @@ where
     deserializer.deserialize_string(RegionVisitor)
 }
 
+fn ser_regions<S>(regions: &[Region], serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(regions.iter().map(Region::name))
+}
+
+fn de_ser_regions<'de, D>(deserializer: D) -> ::std::result::Result<Vec<Region>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let names: Vec<String> = Deserialize::deserialize(deserializer)?;
+    names
+        .iter()
+        .map(|s| Region::from_str(s).map_err(|_| de::Error::custom(format!("invalid region string '{}'", s))))
+        .collect()
+}
+
 impl DescribeInstances for Aws {
     fn describe_instances(&self) -> ProviderResult<Vec<InstanceDescriptor>> {
         list(self).map_err(|e| {
@@ -89,9 +227,29 @@ impl DescribeInstances for Aws {
 }
 
 fn list(aws: &Aws) -> Result<Vec<InstanceDescriptor>> {
-    let credentials_provider = assume_role(aws)?;
-    let default_client = HttpClient::new().chain_err(|| ErrorKind::AwsApiError)?;
-    let client = ec2::Ec2Client::new_with(default_client, credentials_provider, aws.region.clone());
+    // Query all regions and accounts concurrently
+    let queries: Vec<_> = aws
+        .targets()
+        .into_iter()
+        .map(|target| {
+            let aws = aws.clone();
+            thread::spawn(move || list_target(&aws, &target))
+        })
+        .collect();
+
+    let mut instances = Vec::new();
+    for query in queries {
+        let target_instances = query
+            .join()
+            .map_err(|_| Error::from_kind(ErrorKind::AwsApiResultError("query thread panicked".to_string())))??;
+        instances.extend(target_instances);
+    }
+
+    Ok(instances)
+}
+
+fn list_target(aws: &Aws, target: &Target) -> Result<Vec<InstanceDescriptor>> {
+    let client = ec2_client(aws, target)?;
 
     let request = Default::default();
     let result = client
@@ -106,14 +264,115 @@ fn list(aws: &Aws) -> Result<Vec<InstanceDescriptor>> {
     for r in reservations {
         if let Some(resv_instances) = r.instances {
             for i in resv_instances {
-                instances.push(i.into());
+                instances.push(target.instance_descriptor(i));
             }
         }
     }
+    add_volume_details(&client, &mut instances);
 
     Ok(instances)
 }
 
+impl DescribeImages for Aws {
+    fn describe_images(&self) -> ProviderResult<Vec<ImageDescriptor>> {
+        list_images(self).map_err(|e| {
+            ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("describe_images")))
+        })
+    }
+}
+
+fn list_images(aws: &Aws) -> Result<Vec<ImageDescriptor>> {
+    let mut images = Vec::new();
+    for target in aws.targets() {
+        images.extend(list_images_target(aws, &target)?);
+    }
+
+    Ok(images)
+}
+
+fn list_images_target(aws: &Aws, target: &Target) -> Result<Vec<ImageDescriptor>> {
+    let client = ec2_client(aws, target)?;
+
+    let request = DescribeImagesRequest {
+        owners: Some(vec!["self".to_owned()]),
+        ..Default::default()
+    };
+    let result = client
+        .describe_images(request)
+        .sync()
+        .chain_err(|| ErrorKind::AwsApiError)?;
+
+    Ok(result
+        .images
+        .unwrap_or_else(Vec::new)
+        .into_iter()
+        .filter_map(|image| target.image_descriptor(image))
+        .collect())
+}
+
+impl DescribeSecurityGroups for Aws {
+    fn describe_security_groups(&self) -> ProviderResult<Vec<SecurityGroup>> {
+        list_security_groups(self).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("describe_security_groups")),
+            )
+        })
+    }
+}
+
+fn list_security_groups(aws: &Aws) -> Result<Vec<SecurityGroup>> {
+    let mut security_groups = Vec::new();
+    for target in aws.targets() {
+        security_groups.extend(list_security_groups_target(aws, &target)?);
+    }
+
+    Ok(security_groups)
+}
+
+fn list_security_groups_target(aws: &Aws, target: &Target) -> Result<Vec<SecurityGroup>> {
+    let client = ec2_client(aws, target)?;
+
+    let request = DescribeSecurityGroupsRequest::default();
+    let result = client
+        .describe_security_groups(request)
+        .sync()
+        .chain_err(|| ErrorKind::AwsApiError)?;
+
+    Ok(result
+        .security_groups
+        .unwrap_or_else(Vec::new)
+        .into_iter()
+        .filter_map(|sg| target.security_group(sg))
+        .collect())
+}
+
+fn security_group_rule_from(permission: &ec2::IpPermission) -> SecurityGroupRule {
+    let ipv4 = permission
+        .ip_ranges
+        .iter()
+        .flat_map(|xs| xs.iter().filter_map(|x| x.cidr_ip.clone()));
+    let ipv6 = permission
+        .ipv_6_ranges
+        .iter()
+        .flat_map(|xs| xs.iter().filter_map(|x| x.cidr_ipv_6.clone()));
+    let groups = permission
+        .user_id_group_pairs
+        .iter()
+        .flat_map(|xs| xs.iter().filter_map(|x| x.group_id.clone()));
+    let prefix_lists = permission
+        .prefix_list_ids
+        .iter()
+        .flat_map(|xs| xs.iter().filter_map(|x| x.prefix_list_id.clone()));
+
+    SecurityGroupRule {
+        protocol:  permission.ip_protocol.clone().unwrap_or_else(|| "-1".to_owned()),
+        from_port: permission.from_port,
+        to_port:   permission.to_port,
+        sources:   ipv4.chain(ipv6).chain(groups).chain(prefix_lists).collect(),
+    }
+}
+
 impl DescribeInstance for Aws {
     fn describe_instance(&self, instance_id: &str) -> ProviderResult<InstanceDescriptor> {
         describe(self, instance_id).map_err(|e| {
@@ -126,9 +385,11 @@ impl DescribeInstance for Aws {
 }
 
 fn describe(aws: &Aws, instance_id: &str) -> Result<InstanceDescriptor> {
-    let credentials_provider = assume_role(aws)?;
-    let default_client = HttpClient::new().chain_err(|| ErrorKind::AwsApiError)?;
-    let client = ec2::Ec2Client::new_with(default_client, credentials_provider, aws.region.clone());
+    first_target(aws, |target| describe_target(aws, target, instance_id))
+}
+
+fn describe_target(aws: &Aws, target: &Target, instance_id: &str) -> Result<InstanceDescriptor> {
+    let client = ec2_client(aws, target)?;
 
     let request = DescribeInstancesRequest {
         dry_run:      Some(false),
@@ -156,18 +417,24 @@ fn describe(aws: &Aws, instance_id: &str) -> Result<InstanceDescriptor> {
         .pop()
         .ok_or_else(|| Error::from_kind(ErrorKind::AwsApiResultError("no instances received".to_string())))?;
 
-    Ok(instance.into())
+    let mut instances = vec![target.instance_descriptor(instance)];
+    add_volume_details(&client, &mut instances);
+
+    Ok(instances.remove(0))
 }
 
 impl From<ec2::Instance> for InstanceDescriptor {
     fn from(r: ec2::Instance) -> Self {
         InstanceDescriptor {
+            account:                  None,
             ami_launch_index:         r.ami_launch_index,
             architecture:             r.architecture,
             block_device_mappings:    r
                 .block_device_mappings
+                .as_ref()
                 .map(|bdms| bdms.iter().map(block_device_mapping_to_string).collect()),
             client_token:             r.client_token,
+            cost:                     None,
             ebs_optimized:            r.ebs_optimized,
             // elastic_gpu_associations: r.elastic_gpu_associations,
             ena_support:              r.ena_support,
@@ -179,17 +446,21 @@ impl From<ec2::Instance> for InstanceDescriptor {
             instance_type:            r.instance_type,
             kernel_id:                r.kernel_id,
             key_name:                 r.key_name,
-            launch_time:              r.launch_time,
+            launch_time:              r.launch_time.as_ref().and_then(|x| parse_launch_time(x)),
             monitoring:               r.monitoring.as_ref().map(monitoring_to_string),
-            // network_interfaces: r.network_interfaces,
+            network_interfaces:       r
+                .network_interfaces
+                .map(|nis| nis.iter().map(network_interface_from).collect()),
             placement:                r.placement.as_ref().map(placement_to_string),
             platform:                 r.platform,
             private_dns_name:         r.private_dns_name,
             private_ip_address:       r.private_ip_address,
+            profile:                  None,
             // product_codes: r.product_codes,
             public_dns_name:          r.public_dns_name,
             public_ip_address:        r.public_ip_address,
             ramdisk_id:               r.ramdisk_id,
+            region:                   None,
             root_device_name:         r.root_device_name,
             root_device_type:         r.root_device_type,
             security_groups:          r
@@ -208,11 +479,20 @@ impl From<ec2::Instance> for InstanceDescriptor {
                 None
             },
             virtualization_type:      r.virtualization_type,
+            volumes:                  r
+                .block_device_mappings
+                .map(|bdms| bdms.iter().filter_map(volume_from_block_device_mapping).collect()),
             vpc_id:                   r.vpc_id,
         }
     }
 }
 
+fn parse_launch_time(launch_time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(launch_time)
+        .ok()
+        .map(|x| x.with_timezone(&Utc))
+}
+
 fn block_device_mapping_to_string(bdm: &ec2::InstanceBlockDeviceMapping) -> String {
     format!(
         "dev={}, id={}",
@@ -226,6 +506,94 @@ fn block_device_mapping_to_string(bdm: &ec2::InstanceBlockDeviceMapping) -> Stri
     )
 }
 
+fn volume_from_block_device_mapping(bdm: &ec2::InstanceBlockDeviceMapping) -> Option<Volume> {
+    bdm.ebs.as_ref().map(|ebs| Volume {
+        volume_id:   ebs.volume_id.clone(),
+        device_name: bdm.device_name.clone(),
+        ..Default::default()
+    })
+}
+
+fn network_interface_from(ni: &ec2::InstanceNetworkInterface) -> NetworkInterface {
+    NetworkInterface {
+        network_interface_id: ni.network_interface_id.clone(),
+        subnet_id:            ni.subnet_id.clone(),
+        private_ip_addresses: ni
+            .private_ip_addresses
+            .as_ref()
+            .map(|ips| ips.iter().filter_map(|x| x.private_ip_address.clone()).collect())
+            .unwrap_or_default(),
+        ipv6_addresses:       ni
+            .ipv_6_addresses
+            .as_ref()
+            .map(|ips| ips.iter().filter_map(|x| x.ipv_6_address.clone()).collect())
+            .unwrap_or_default(),
+        security_groups:      ni
+            .groups
+            .as_ref()
+            .map(|sgs| sgs.iter().filter_map(|x| x.group_id.clone()).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Adds size, type, iops, and encryption of the attached EBS volumes.
+///
+/// Volume details are nice to have, so failing to retrieve them only yields a warning.
+fn add_volume_details(client: &ec2::Ec2Client, instances: &mut [InstanceDescriptor]) {
+    let volume_ids: Vec<String> = instances
+        .iter()
+        .flat_map(|i| i.volumes.iter().flat_map(|vs| vs.iter()))
+        .filter_map(|v| v.volume_id.clone())
+        .collect();
+    if volume_ids.is_empty() {
+        return;
+    }
+
+    let details = match describe_volumes(client, volume_ids) {
+        Ok(details) => details,
+        Err(e) => {
+            warn!("Failed to retrieve volume details: {}", e);
+            return;
+        }
+    };
+
+    for volume in instances
+        .iter_mut()
+        .flat_map(|i| i.volumes.iter_mut().flat_map(|vs| vs.iter_mut()))
+    {
+        if let Some(detail) = volume.volume_id.as_ref().and_then(|id| details.get(id)) {
+            volume.size = detail.size;
+            volume.volume_type = detail.volume_type.clone();
+            volume.iops = detail.iops;
+            volume.encrypted = detail.encrypted;
+        }
+    }
+}
+
+/// Maximum number of volume ids per `DescribeVolumes` request.
+const DESCRIBE_VOLUMES_CHUNK_SIZE: usize = 200;
+
+fn describe_volumes(client: &ec2::Ec2Client, volume_ids: Vec<String>) -> Result<HashMap<String, ec2::Volume>> {
+    let mut volumes = HashMap::new();
+    for chunk in volume_ids.chunks(DESCRIBE_VOLUMES_CHUNK_SIZE) {
+        let request = DescribeVolumesRequest {
+            volume_ids: Some(chunk.to_vec()),
+            ..Default::default()
+        };
+        let result = client
+            .describe_volumes(request)
+            .sync()
+            .chain_err(|| ErrorKind::AwsApiError)?;
+        for volume in result.volumes.unwrap_or_default() {
+            if let Some(id) = volume.volume_id.clone() {
+                volumes.insert(id, volume);
+            }
+        }
+    }
+
+    Ok(volumes)
+}
+
 fn iam_instance_profile_to_string(iip: &ec2::IamInstanceProfile) -> String {
     format!(
         "id={}, arn={}",
@@ -274,7 +642,14 @@ fn vec_tags_to_hashmap(tags: Vec<ec2::Tag>) -> HashMap<String, Option<String>> {
     tag_map
 }
 
-fn assume_role(aws: &Aws) -> Result<StsAssumeRoleSessionCredentialsProvider> {
+fn ec2_client(aws: &Aws, target: &Target) -> Result<ec2::Ec2Client> {
+    let credentials_provider = assume_role(aws, target)?;
+    let default_client = HttpClient::new().chain_err(|| ErrorKind::AwsApiError)?;
+
+    Ok(ec2::Ec2Client::new_with(default_client, credentials_provider, target.region.clone()))
+}
+
+fn assume_role(aws: &Aws, target: &Target) -> Result<StsAssumeRoleSessionCredentialsProvider> {
     // let base_provider = DefaultCredentialsProvider::new().chain_err(|| ErrorKind::AwsApiError)?;
     let base_provider = StaticProvider::new(
         aws.access_key_id.clone(),
@@ -283,11 +658,11 @@ fn assume_role(aws: &Aws) -> Result<StsAssumeRoleSessionCredentialsProvider> {
         None,
     );
     let default_client = HttpClient::new().chain_err(|| ErrorKind::AwsApiError)?;
-    let sts = StsClient::new_with(default_client, base_provider, aws.region.clone());
+    let sts = StsClient::new_with(default_client, base_provider, target.region.clone());
 
     let provider = StsAssumeRoleSessionCredentialsProvider::new(
         sts,
-        aws.role_arn.clone(),
+        target.role_arn.clone(),
         "default".to_string(),
         None,
         None,
@@ -298,6 +673,440 @@ fn assume_role(aws: &Aws) -> Result<StsAssumeRoleSessionCredentialsProvider> {
     Ok(provider)
 }
 
+/// Returns the result of `f` for the first target it succeeds for.
+///
+/// AWS reports unknown resource ids as error, so this finds the region and account of a resource.
+fn first_target<T, F>(aws: &Aws, f: F) -> Result<T>
+where
+    F: Fn(&Target) -> Result<T>,
+{
+    let mut last_error = None;
+    for target in aws.targets() {
+        match f(&target) {
+            Ok(result) => return Ok(result),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| Error::from_kind(ErrorKind::AwsApiResultError("no targets".to_string()))))
+}
+
+/// Groups instance ids by the region and account they live in.
+///
+/// If only one target is configured, no lookup is necessary and AWS will report unknown instance ids. Otherwise,
+/// each target is asked only for the instance ids not located yet.
+fn locate(aws: &Aws, instance_ids: &[InstanceId]) -> Result<Vec<(Target, Vec<InstanceId>)>> {
+    let mut targets = aws.targets();
+    if targets.len() == 1 {
+        return Ok(vec![(targets.remove(0), instance_ids.to_vec())]);
+    }
+
+    let mut located = Vec::new();
+    let mut remaining = instance_ids.to_vec();
+    for target in targets {
+        if remaining.is_empty() {
+            break;
+        }
+        let known = known_instance_ids_target(aws, &target, &remaining)?;
+        let (found, not_found): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|x| known.contains(x));
+        remaining = not_found;
+        if !found.is_empty() {
+            located.push((target, found));
+        }
+    }
+
+    if let Some(instance_id) = remaining.pop() {
+        return Err(Error::from_kind(ErrorKind::InstanceNotFound(instance_id)));
+    }
+
+    Ok(located)
+}
+
+/// Returns those of `instance_ids` which exist in `target`.
+///
+/// Instance ids are passed as filter because AWS fails for unknown ids passed as `instance_ids`.
+fn known_instance_ids_target(aws: &Aws, target: &Target, instance_ids: &[InstanceId]) -> Result<HashSet<InstanceId>> {
+    let client = ec2_client(aws, target)?;
+
+    let request = DescribeInstancesRequest {
+        filters: Some(vec![ec2::Filter {
+            name:   Some("instance-id".to_owned()),
+            values: Some(instance_ids.to_vec()),
+        }]),
+        ..Default::default()
+    };
+    let result = client
+        .describe_instances(request)
+        .sync()
+        .chain_err(|| ErrorKind::AwsApiError)?;
+
+    Ok(result
+        .reservations
+        .unwrap_or_else(Vec::new)
+        .into_iter()
+        .flat_map(|r| r.instances.unwrap_or_else(Vec::new))
+        .filter_map(|i| i.instance_id)
+        .collect())
+}
+
+fn locate_one(aws: &Aws, instance_id: &str) -> Result<Target> {
+    locate(aws, &[instance_id.to_owned()])?
+        .pop()
+        .map(|(target, _)| target)
+        .ok_or_else(|| Error::from_kind(ErrorKind::InstanceNotFound(instance_id.to_owned())))
+}
+
+fn for_each_target<F>(aws: &Aws, instance_ids: &[InstanceId], f: F) -> Result<Vec<StateChange>>
+where
+    F: Fn(&Target, &[InstanceId]) -> Result<Vec<StateChange>>,
+{
+    let mut state_changes = Vec::new();
+    for (target, instance_ids) in locate(aws, instance_ids)? {
+        state_changes.extend(f(&target, &instance_ids)?);
+    }
+
+    Ok(state_changes)
+}
+
+impl CreateTags for Aws {
+    fn create_tags(
+        &self,
+        dry: bool,
+        instance_ids: &[InstanceId],
+        tags: &HashMap<String, String>,
+    ) -> ProviderResult<()> {
+        create_tags(self, dry, instance_ids, tags).map_err(|e| {
+            ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("create_tags")))
+        })
+    }
+}
+
+fn create_tags(aws: &Aws, dry: bool, instance_ids: &[InstanceId], tags: &HashMap<String, String>) -> Result<()> {
+    for (target, instance_ids) in locate(aws, instance_ids)? {
+        let client = ec2_client(aws, &target)?;
+        tag_resources(&client, dry, instance_ids, tags)?;
+    }
+
+    Ok(())
+}
+
+/// Tags any kind of EC2 resources, e.g., instances, snapshots, or images.
+fn tag_resources(
+    client: &ec2::Ec2Client,
+    dry: bool,
+    resource_ids: Vec<String>,
+    tags: &HashMap<String, String>,
+) -> Result<()> {
+    let request = CreateTagsRequest {
+        dry_run:   Some(dry),
+        resources: resource_ids,
+        tags:      tags
+            .iter()
+            .map(|(k, v)| ec2::Tag {
+                key:   Some(k.to_owned()),
+                value: Some(v.to_owned()),
+            })
+            .collect(),
+    };
+    // If run in dry mode, AWS returns an error of type DryRunOperation
+    match client.create_tags(request).sync() {
+        Err(CreateTagsError::Unknown(ref s)) if s.contains("DryRunOperation") => Ok(()),
+        Err(CreateTagsError::Unknown(ref s)) if s.contains("UnauthorizedOperation") => Err(Error::from_kind(
+            ErrorKind::AwsApiResultError("tagging is not authorized".to_string()),
+        )),
+        result => result.chain_err(|| ErrorKind::AwsApiError),
+    }
+}
+
+impl CreateSnapshots for Aws {
+    fn create_snapshots(
+        &self,
+        dry: bool,
+        instance_id: &str,
+        tags: &HashMap<String, String>,
+    ) -> ProviderResult<Vec<Snapshot>> {
+        create_snapshots(self, dry, instance_id, tags).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("create_snapshots")),
+            )
+        })
+    }
+
+    fn snapshot_state(&self, snapshot_id: &str) -> ProviderResult<Option<String>> {
+        first_target(self, |target| snapshot_state_target(self, target, snapshot_id)).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("snapshot_state")),
+            )
+        })
+    }
+}
+
+fn create_snapshots(
+    aws: &Aws,
+    dry: bool,
+    instance_id: &str,
+    tags: &HashMap<String, String>,
+) -> Result<Vec<Snapshot>> {
+    let target = locate_one(aws, instance_id)?;
+    let client = ec2_client(aws, &target)?;
+    let instance = describe_target(aws, &target, instance_id)?;
+
+    let mut snapshots = Vec::new();
+    for volume in instance.volumes.unwrap_or_default() {
+        let volume_id = match volume.volume_id {
+            Some(volume_id) => volume_id,
+            None => continue,
+        };
+        let request = CreateSnapshotRequest {
+            description: Some(format!("Snapshot of {} of instance {}", volume_id, instance_id)),
+            dry_run:     Some(dry),
+            volume_id:   volume_id.clone(),
+            ..Default::default()
+        };
+        // If run in dry mode, AWS returns an error of type DryRunOperation
+        let (snapshot_id, state) = match client.create_snapshot(request).sync() {
+            Err(CreateSnapshotError::Unknown(ref s)) if s.contains("DryRunOperation") => {
+                (String::from("- n/a -"), None)
+            }
+            Err(CreateSnapshotError::Unknown(ref s)) if s.contains("UnauthorizedOperation") => {
+                return Err(Error::from_kind(ErrorKind::AwsApiResultError(
+                    "creating snapshots is not authorized".to_string(),
+                )))
+            }
+            result => {
+                let snapshot = result.chain_err(|| ErrorKind::AwsApiError)?;
+                let snapshot_id = snapshot.snapshot_id.ok_or_else(|| {
+                    Error::from_kind(ErrorKind::AwsApiResultError("no snapshot id received".to_string()))
+                })?;
+                tag_resources(&client, dry, vec![snapshot_id.clone()], tags)?;
+                (snapshot_id, snapshot.state)
+            }
+        };
+        snapshots.push(Snapshot {
+            snapshot_id,
+            instance_id: instance_id.to_owned(),
+            volume_id,
+            device_name: volume.device_name,
+            state,
+        });
+    }
+
+    Ok(snapshots)
+}
+
+fn snapshot_state_target(aws: &Aws, target: &Target, snapshot_id: &str) -> Result<Option<String>> {
+    let client = ec2_client(aws, target)?;
+
+    let request = DescribeSnapshotsRequest {
+        snapshot_ids: Some(vec![snapshot_id.to_owned()]),
+        ..Default::default()
+    };
+    let result = client
+        .describe_snapshots(request)
+        .sync()
+        .chain_err(|| ErrorKind::AwsApiError)?;
+
+    Ok(result
+        .snapshots
+        .and_then(|snapshots| snapshots.into_iter().next())
+        .and_then(|snapshot| snapshot.state))
+}
+
+impl CreateImage for Aws {
+    fn create_image(
+        &self,
+        dry: bool,
+        instance_id: &str,
+        name: &str,
+        no_reboot: bool,
+        tags: &HashMap<String, String>,
+    ) -> ProviderResult<Image> {
+        create_image(self, dry, instance_id, name, no_reboot, tags).map_err(|e| {
+            ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("create_image")))
+        })
+    }
+
+    fn image_state(&self, image_id: &str) -> ProviderResult<Option<String>> {
+        first_target(self, |target| image_state_target(self, target, image_id)).map_err(|e| {
+            ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("image_state")))
+        })
+    }
+}
+
+fn create_image(
+    aws: &Aws,
+    dry: bool,
+    instance_id: &str,
+    name: &str,
+    no_reboot: bool,
+    tags: &HashMap<String, String>,
+) -> Result<Image> {
+    let target = locate_one(aws, instance_id)?;
+    let client = ec2_client(aws, &target)?;
+
+    let request = CreateImageRequest {
+        description: Some(format!("Image of instance {}", instance_id)),
+        dry_run:     Some(dry),
+        instance_id: instance_id.to_owned(),
+        name:        name.to_owned(),
+        no_reboot:   Some(no_reboot),
+        ..Default::default()
+    };
+    // If run in dry mode, AWS returns an error of type DryRunOperation
+    let image_id = match client.create_image(request).sync() {
+        Err(CreateImageError::Unknown(ref s)) if s.contains("DryRunOperation") => String::from("- n/a -"),
+        Err(CreateImageError::Unknown(ref s)) if s.contains("UnauthorizedOperation") => {
+            return Err(Error::from_kind(ErrorKind::AwsApiResultError(
+                "creating images is not authorized".to_string(),
+            )))
+        }
+        result => {
+            let image_id = result.chain_err(|| ErrorKind::AwsApiError)?.image_id.ok_or_else(|| {
+                Error::from_kind(ErrorKind::AwsApiResultError("no image id received".to_string()))
+            })?;
+            tag_resources(&client, dry, vec![image_id.clone()], tags)?;
+            image_id
+        }
+    };
+
+    Ok(Image {
+        image_id,
+        instance_id: instance_id.to_owned(),
+        name:        name.to_owned(),
+        state:       None,
+    })
+}
+
+fn image_state_target(aws: &Aws, target: &Target, image_id: &str) -> Result<Option<String>> {
+    let client = ec2_client(aws, target)?;
+
+    let request = DescribeImagesRequest {
+        image_ids: Some(vec![image_id.to_owned()]),
+        ..Default::default()
+    };
+    let result = client
+        .describe_images(request)
+        .sync()
+        .chain_err(|| ErrorKind::AwsApiError)?;
+
+    Ok(result
+        .images
+        .and_then(|images| images.into_iter().next())
+        .and_then(|image| image.state))
+}
+
+impl DeleteTags for Aws {
+    fn delete_tags(&self, dry: bool, instance_ids: &[InstanceId], keys: &[String]) -> ProviderResult<()> {
+        delete_tags(self, dry, instance_ids, keys).map_err(|e| {
+            ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("delete_tags")))
+        })
+    }
+}
+
+fn delete_tags(aws: &Aws, dry: bool, instance_ids: &[InstanceId], keys: &[String]) -> Result<()> {
+    for (target, instance_ids) in locate(aws, instance_ids)? {
+        let client = ec2_client(aws, &target)?;
+        // Tags without value are deleted regardless of their current value.
+        let request = DeleteTagsRequest {
+            dry_run:   Some(dry),
+            resources: instance_ids,
+            tags:      Some(
+                keys.iter()
+                    .map(|k| ec2::Tag {
+                        key:   Some(k.to_owned()),
+                        value: None,
+                    })
+                    .collect(),
+            ),
+        };
+        // If run in dry mode, AWS returns an error of type DryRunOperation
+        match client.delete_tags(request).sync() {
+            Err(DeleteTagsError::Unknown(ref s)) if s.contains("DryRunOperation") => {}
+            Err(DeleteTagsError::Unknown(ref s)) if s.contains("UnauthorizedOperation") => {
+                return Err(Error::from_kind(ErrorKind::AwsApiResultError(
+                    "deleting tags is not authorized".to_string(),
+                )))
+            }
+            result => result.chain_err(|| ErrorKind::AwsApiError)?,
+        }
+    }
+
+    Ok(())
+}
+
+impl RebootInstances for Aws {
+    fn reboot_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> ProviderResult<()> {
+        reboot(self, dry, instance_ids).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("reboot_instances")),
+            )
+        })
+    }
+}
+
+fn reboot(aws: &Aws, dry: bool, instance_ids: &[InstanceId]) -> Result<()> {
+    for (target, instance_ids) in locate(aws, instance_ids)? {
+        reboot_target(aws, &target, dry, &instance_ids)?;
+    }
+
+    Ok(())
+}
+
+fn reboot_target(aws: &Aws, target: &Target, dry: bool, instance_ids: &[InstanceId]) -> Result<()> {
+    let client = ec2_client(aws, target)?;
+
+    let request = RebootInstancesRequest {
+        dry_run:      Some(dry),
+        instance_ids: instance_ids.to_vec(),
+    };
+    // If run in dry mode, AWS returns an error of type DryRunOperation
+    match client.reboot_instances(request).sync() {
+        Err(RebootInstancesError::Unknown(ref s)) if s.contains("DryRunOperation") => Ok(()),
+        Err(RebootInstancesError::Unknown(ref s)) if s.contains("UnauthorizedOperation") => Err(Error::from_kind(
+            ErrorKind::AwsApiResultError("reboot is not authorized".to_string()),
+        )),
+        result => result.chain_err(|| ErrorKind::AwsApiError),
+    }
+}
+
+impl ModifyInstanceType for Aws {
+    fn modify_instance_type(&self, dry: bool, instance_id: &str, instance_type: &str) -> ProviderResult<()> {
+        modify_instance_type(self, dry, instance_id, instance_type).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("modify_instance_type")),
+            )
+        })
+    }
+}
+
+fn modify_instance_type(aws: &Aws, dry: bool, instance_id: &str, instance_type: &str) -> Result<()> {
+    let target = locate_one(aws, instance_id)?;
+    let client = ec2_client(aws, &target)?;
+
+    let request = ModifyInstanceAttributeRequest {
+        dry_run:       Some(dry),
+        instance_id:   instance_id.to_owned(),
+        instance_type: Some(AttributeValue {
+            value: Some(instance_type.to_owned()),
+        }),
+        ..Default::default()
+    };
+    // If run in dry mode, AWS returns an error of type DryRunOperation
+    match client.modify_instance_attribute(request).sync() {
+        Err(ModifyInstanceAttributeError::Unknown(ref s)) if s.contains("DryRunOperation") => Ok(()),
+        Err(ModifyInstanceAttributeError::Unknown(ref s)) if s.contains("UnauthorizedOperation") => {
+            Err(Error::from_kind(ErrorKind::AwsApiResultError(
+                "modifying the instance type is not authorized".to_string(),
+            )))
+        }
+        result => result.chain_err(|| ErrorKind::AwsApiError),
+    }
+}
+
 impl StartInstances for Aws {
     fn start_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> ProviderResult<Vec<StateChange>> {
         start(self, dry, instance_ids).map_err(|e| {
@@ -310,9 +1119,11 @@ impl StartInstances for Aws {
 }
 
 fn start(aws: &Aws, dry: bool, instance_ids: &[InstanceId]) -> Result<Vec<StateChange>> {
-    let credentials_provider = assume_role(aws)?;
-    let default_client = HttpClient::new().chain_err(|| ErrorKind::AwsApiError)?;
-    let client = ec2::Ec2Client::new_with(default_client, credentials_provider, aws.region.clone());
+    for_each_target(aws, instance_ids, |target, instance_ids| start_target(aws, target, dry, instance_ids))
+}
+
+fn start_target(aws: &Aws, target: &Target, dry: bool, instance_ids: &[InstanceId]) -> Result<Vec<StateChange>> {
+    let client = ec2_client(aws, target)?;
 
     let request = StartInstancesRequest {
         additional_info: None,
@@ -351,9 +1162,17 @@ impl StopInstances for Aws {
 }
 
 fn stop(aws: &Aws, dry: bool, force: bool, instance_ids: &[InstanceId]) -> Result<Vec<StateChange>> {
-    let credentials_provider = assume_role(aws)?;
-    let default_client = HttpClient::new().chain_err(|| ErrorKind::AwsApiError)?;
-    let client = ec2::Ec2Client::new_with(default_client, credentials_provider, aws.region.clone());
+    for_each_target(aws, instance_ids, |target, instance_ids| stop_target(aws, target, dry, force, instance_ids))
+}
+
+fn stop_target(
+    aws: &Aws,
+    target: &Target,
+    dry: bool,
+    force: bool,
+    instance_ids: &[InstanceId],
+) -> Result<Vec<StateChange>> {
+    let client = ec2_client(aws, target)?;
 
     let request = StopInstancesRequest {
         dry_run:      Some(dry),
@@ -395,9 +1214,11 @@ impl TerminateInstances for Aws {
 }
 
 fn destroy(aws: &Aws, dry: bool, instance_ids: &[InstanceId]) -> Result<Vec<StateChange>> {
-    let credentials_provider = assume_role(aws)?;
-    let default_client = HttpClient::new().chain_err(|| ErrorKind::AwsApiError)?;
-    let client = ec2::Ec2Client::new_with(default_client, credentials_provider, aws.region.clone());
+    for_each_target(aws, instance_ids, |target, instance_ids| destroy_target(aws, target, dry, instance_ids))
+}
+
+fn destroy_target(aws: &Aws, target: &Target, dry: bool, instance_ids: &[InstanceId]) -> Result<Vec<StateChange>> {
+    let client = ec2_client(aws, target)?;
 
     let request = TerminateInstancesRequest {
         dry_run:      Some(dry),
@@ -426,19 +1247,6 @@ fn destroy(aws: &Aws, dry: bool, instance_ids: &[InstanceId]) -> Result<Vec<Stat
     Ok(state_changes)
 }
 
-fn create_dry_run_results(instance_ids: &[InstanceId]) -> Vec<StateChange> {
-    instance_ids
-        .iter()
-        .map(|i| {
-            StateChange {
-                instance_id:    i.to_owned(),
-                previous_state: "- n/a -".to_owned(),
-                current_state:  "- n/a -".to_owned(),
-            }
-        })
-        .collect::<Vec<_>>()
-}
-
 impl From<ec2::InstanceStateChange> for StateChange {
     fn from(x: ec2::InstanceStateChange) -> Self {
         StateChange {
@@ -474,5 +1282,53 @@ error_chain! {
         OutputError {
             description("Failed to write output.")
         }
+        InstanceNotFound(instance_id: String) {
+            description("Instance not found.")
+            display("Instance '{}' not found in any configured region or account.", instance_id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    fn aws() -> Aws {
+        Aws {
+            access_key_id:        String::from("a key id"),
+            secret_access_key:    String::from("an access key"),
+            token:                None,
+            region:               Region::EuCentral1,
+            role_arn:             String::from("arn:aws:iam::123456789012:role/OrganizationAccountAccessRole"),
+            additional_regions:   vec![Region::EuWest1],
+            additional_role_arns: vec![String::from("arn:aws:iam::210987654321:role/OrganizationAccountAccessRole")],
+        }
+    }
+
+    #[test]
+    fn targets_combine_regions_and_roles() {
+        let targets = aws().targets();
+
+        assert_that(&targets).has_length(4);
+        assert_that(&targets[0].region).is_equal_to(Region::EuCentral1);
+        assert_that(&targets[0].role_arn)
+            .is_equal_to("arn:aws:iam::123456789012:role/OrganizationAccountAccessRole".to_owned());
+        assert_that(&targets[3].region).is_equal_to(Region::EuWest1);
+        assert_that(&targets[3].role_arn)
+            .is_equal_to("arn:aws:iam::210987654321:role/OrganizationAccountAccessRole".to_owned());
+    }
+
+    #[test]
+    fn account_from_role_arn_ok() {
+        let account = account_from_role_arn("arn:aws:iam::123456789012:role/OrganizationAccountAccessRole");
+
+        assert_that(&account).is_some().is_equal_to("123456789012".to_owned());
+    }
+
+    #[test]
+    fn account_from_role_arn_invalid() {
+        assert_that(&account_from_role_arn("no arn")).is_none();
     }
 }
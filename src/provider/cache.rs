@@ -0,0 +1,437 @@
+//! On-disk cache of instance descriptions.
+//!
+//! `CachingProvider` wraps a provider and stores the result of `describe_instances` in a JSON file per cache key,
+//! i.e., per configuration file, profile, and provider configuration. As long as this file is younger than the
+//! configured TTL, instances are described from the cache which makes repeated lookups, e.g., for shell
+//! completions, instant. Every call that changes instances invalidates the cache. Looking up a single instance
+//! uses a fresh cache, but never fills it, so that waiting for state changes always sees the current state.
+//!
+//! The cache is opt-in since stale instance descriptions may surprise; it is enabled by a TTL greater than 0.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde_json;
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tempfile::NamedTempFile;
+
+use provider::{
+    CreateImage,
+    CreateSnapshots,
+    CreateTags,
+    DeleteTags,
+    DescribeImages,
+    DescribeInstance,
+    DescribeInstances,
+    DescribeSecurityGroups,
+    Error as ProviderError,
+    ErrorKind as ProviderErrorKind,
+    Image,
+    ImageDescriptor,
+    InstanceDescriptor,
+    InstanceId,
+    ModifyInstanceType,
+    Provider,
+    RebootInstances,
+    Result as ProviderResult,
+    SecurityGroup,
+    Snapshot,
+    StartInstances,
+    StateChange,
+    StopInstances,
+    TerminateInstances,
+};
+
+/// `ProviderCache` holds the cache settings of a run.
+#[derive(Debug, Clone)]
+pub struct ProviderCache {
+    /// Directory containing the cache files
+    pub dir:     PathBuf,
+    /// Configuration file of the run; part of the cache key
+    pub config:  PathBuf,
+    /// Maximum age of cached instance descriptions; 0 disables the cache
+    pub ttl:     Duration,
+    /// Ignores cached instance descriptions, but refreshes the cache
+    pub refresh: bool,
+    /// Only uses cached instance descriptions regardless of their age; all other calls fail
+    pub offline: bool,
+}
+
+impl ProviderCache {
+    /// Returns whether instance descriptions are cached at all.
+    pub fn is_enabled(&self) -> bool { self.ttl > Duration::from_secs(0) }
+
+    /// Wraps `provider` by a `CachingProvider` storing its instance descriptions under `key`.
+    pub fn wrap(&self, key: &str, provider: Box<dyn Provider>) -> CachingProvider {
+        CachingProvider {
+            provider,
+            path: self.path(key),
+            cache: self.clone(),
+        }
+    }
+
+    /// Removes the cached instance descriptions stored under `key`, e.g., after instances have been changed
+    /// without the provider.
+    pub fn invalidate(&self, key: &str) { remove_cache_file(&self.path(key)) }
+
+    fn path(&self, key: &str) -> PathBuf { self.dir.join(format!("{}.json", key)) }
+}
+
+pub struct CachingProvider {
+    provider: Box<dyn Provider>,
+    path:     PathBuf,
+    cache:    ProviderCache,
+}
+
+#[derive(Deserialize)]
+struct CacheFile {
+    created_at: DateTime<Utc>,
+    instances:  Vec<InstanceDescriptor>,
+}
+
+/// Borrowing counterpart of `CacheFile` for writing the cache
+#[derive(Serialize)]
+struct CacheFileRef<'a> {
+    created_at: DateTime<Utc>,
+    instances:  &'a [InstanceDescriptor],
+}
+
+impl CachingProvider {
+    /// Loads the cached instance descriptions if they exist and may be used.
+    fn load(&self) -> Result<Option<Vec<InstanceDescriptor>>> {
+        if self.cache.refresh {
+            return Ok(None);
+        }
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::with_chain(e, ErrorKind::CacheFileError(path_to_string(&self.path)))),
+        };
+        let cache_file: CacheFile =
+            serde_json::from_str(&content).chain_err(|| ErrorKind::CacheFileError(path_to_string(&self.path)))?;
+
+        let ttl = ChronoDuration::from_std(self.cache.ttl).unwrap_or_else(|_| ChronoDuration::max_value());
+        if self.cache.offline || Utc::now().signed_duration_since(cache_file.created_at) < ttl {
+            debug!("Using cached instance descriptions from '{}'.", path_to_string(&self.path));
+            Ok(Some(cache_file.instances))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save(&self, instances: &[InstanceDescriptor]) -> Result<()> {
+        let cache_file = CacheFileRef {
+            created_at: Utc::now(),
+            instances,
+        };
+        fs::create_dir_all(&self.cache.dir).chain_err(|| ErrorKind::CacheFileError(path_to_string(&self.path)))?;
+
+        // Write to a temporary file first, so concurrent runs never see a partially written cache
+        let mut file = NamedTempFile::new_in(&self.cache.dir)
+            .chain_err(|| ErrorKind::CacheFileError(path_to_string(&self.path)))?;
+        serde_json::to_writer(&mut file, &cache_file)
+            .chain_err(|| ErrorKind::CacheFileError(path_to_string(&self.path)))?;
+        file.persist(&self.path)
+            .chain_err(|| ErrorKind::CacheFileError(path_to_string(&self.path)))?;
+
+        Ok(())
+    }
+
+    fn invalidate(&self) { remove_cache_file(&self.path) }
+
+    /// Fails in offline mode since `operation` requires the provider.
+    fn online(&self, operation: &str) -> ProviderResult<()> {
+        if self.cache.offline {
+            Err(ProviderError::with_chain(
+                Error::from_kind(ErrorKind::Offline(operation.to_owned())),
+                ProviderErrorKind::ProviderCallFailed(operation.to_owned()),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs a call that changes instances and invalidates the cache unless it has been a dry run.
+    fn change<T, F>(&self, operation: &str, dry: bool, f: F) -> ProviderResult<T>
+    where
+        F: FnOnce(&dyn Provider) -> ProviderResult<T>,
+    {
+        self.online(operation)?;
+        let result = f(self.provider.as_ref());
+        if !dry {
+            self.invalidate();
+        }
+
+        result
+    }
+}
+
+fn remove_cache_file(path: &Path) {
+    match fs::remove_file(path) {
+        Err(ref e) if e.kind() != io::ErrorKind::NotFound => {
+            warn!("Failed to invalidate cache '{}' because {}.", path_to_string(path), e);
+        }
+        _ => {}
+    }
+}
+
+fn path_to_string(path: &Path) -> String { path.to_string_lossy().to_string() }
+
+fn cache_error(operation: &str, e: Error) -> ProviderError {
+    ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(operation.to_owned()))
+}
+
+impl DescribeInstances for CachingProvider {
+    fn describe_instances(&self) -> ProviderResult<Vec<InstanceDescriptor>> {
+        match self.load() {
+            Ok(Some(instances)) => return Ok(instances),
+            Ok(None) => {}
+            Err(e) => {
+                if self.cache.offline {
+                    return Err(cache_error("describe_instances", e));
+                }
+                warn!("Ignoring cache because {}.", e);
+            }
+        }
+        if self.cache.offline {
+            return Err(cache_error(
+                "describe_instances",
+                Error::from_kind(ErrorKind::NoCache(path_to_string(&self.path))),
+            ));
+        }
+
+        let instances = self.provider.describe_instances()?;
+        if let Err(e) = self.save(&instances) {
+            warn!("Failed to update cache because {}.", e);
+        }
+
+        Ok(instances)
+    }
+}
+
+impl DescribeInstance for CachingProvider {
+    fn describe_instance(&self, instance_id: &str) -> ProviderResult<InstanceDescriptor> {
+        let cached = self
+            .load()
+            .unwrap_or(None)
+            .and_then(|instances| {
+                instances
+                    .into_iter()
+                    .find(|x| x.instance_id.as_ref().map(|x| x == instance_id).unwrap_or(false))
+            });
+        if let Some(instance) = cached {
+            return Ok(instance);
+        }
+        self.online("describe_instance")?;
+
+        self.provider.describe_instance(instance_id)
+    }
+}
+
+impl DescribeImages for CachingProvider {
+    fn describe_images(&self) -> ProviderResult<Vec<ImageDescriptor>> {
+        self.online("describe_images")?;
+        self.provider.describe_images()
+    }
+}
+
+impl DescribeSecurityGroups for CachingProvider {
+    fn describe_security_groups(&self) -> ProviderResult<Vec<SecurityGroup>> {
+        self.online("describe_security_groups")?;
+        self.provider.describe_security_groups()
+    }
+}
+
+impl CreateImage for CachingProvider {
+    fn create_image(
+        &self,
+        dry: bool,
+        instance_id: &str,
+        name: &str,
+        no_reboot: bool,
+        tags: &HashMap<String, String>,
+    ) -> ProviderResult<Image> {
+        self.change("create_image", dry, |p| p.create_image(dry, instance_id, name, no_reboot, tags))
+    }
+
+    fn image_state(&self, image_id: &str) -> ProviderResult<Option<String>> {
+        self.online("image_state")?;
+        self.provider.image_state(image_id)
+    }
+}
+
+impl CreateSnapshots for CachingProvider {
+    fn create_snapshots(
+        &self,
+        dry: bool,
+        instance_id: &str,
+        tags: &HashMap<String, String>,
+    ) -> ProviderResult<Vec<Snapshot>> {
+        self.online("create_snapshots")?;
+        self.provider.create_snapshots(dry, instance_id, tags)
+    }
+
+    fn snapshot_state(&self, snapshot_id: &str) -> ProviderResult<Option<String>> {
+        self.online("snapshot_state")?;
+        self.provider.snapshot_state(snapshot_id)
+    }
+}
+
+impl CreateTags for CachingProvider {
+    fn create_tags(
+        &self,
+        dry: bool,
+        instance_ids: &[InstanceId],
+        tags: &HashMap<String, String>,
+    ) -> ProviderResult<()> {
+        self.change("create_tags", dry, |p| p.create_tags(dry, instance_ids, tags))
+    }
+}
+
+impl DeleteTags for CachingProvider {
+    fn delete_tags(&self, dry: bool, instance_ids: &[InstanceId], keys: &[String]) -> ProviderResult<()> {
+        self.change("delete_tags", dry, |p| p.delete_tags(dry, instance_ids, keys))
+    }
+}
+
+impl ModifyInstanceType for CachingProvider {
+    fn modify_instance_type(&self, dry: bool, instance_id: &str, instance_type: &str) -> ProviderResult<()> {
+        self.change("modify_instance_type", dry, |p| {
+            p.modify_instance_type(dry, instance_id, instance_type)
+        })
+    }
+}
+
+impl RebootInstances for CachingProvider {
+    fn reboot_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> ProviderResult<()> {
+        self.change("reboot_instances", dry, |p| p.reboot_instances(dry, instance_ids))
+    }
+}
+
+impl StartInstances for CachingProvider {
+    fn start_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> ProviderResult<Vec<StateChange>> {
+        self.change("start_instances", dry, |p| p.start_instances(dry, instance_ids))
+    }
+}
+
+impl StopInstances for CachingProvider {
+    fn stop_instances(&self, dry: bool, force: bool, instance_ids: &[InstanceId]) -> ProviderResult<Vec<StateChange>> {
+        self.change("stop_instances", dry, |p| p.stop_instances(dry, force, instance_ids))
+    }
+}
+
+impl TerminateInstances for CachingProvider {
+    fn terminate_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> ProviderResult<Vec<StateChange>> {
+        self.change("terminate_instances", dry, |p| p.terminate_instances(dry, instance_ids))
+    }
+}
+
+error_chain! {
+    errors {
+        CacheFileError(path: String) {
+            description("Failed to access cache file.")
+            display("Failed to access cache file '{}'.", path)
+        }
+        NoCache(path: String) {
+            description("No cached instance descriptions.")
+            display("No cached instance descriptions in '{}'; run without --offline first.", path)
+        }
+        Offline(operation: String) {
+            description("Operation is not available offline.")
+            display("Operation '{}' is not available in offline mode.", operation)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+    use tempfile::TempDir;
+
+    use provider::inventory::Inventory;
+
+    const INVENTORY: &str = r#"
+[[instances]]
+instance_id = "i-1"
+state = "running"
+
+[[instances]]
+instance_id = "i-2"
+state = "stopped"
+"#;
+
+    fn inventory_path(dir: &TempDir) -> PathBuf { dir.path().join("inventory.toml") }
+
+    fn create_provider(dir: &TempDir, refresh: bool, offline: bool) -> CachingProvider {
+        let path = inventory_path(dir);
+        if !path.exists() {
+            fs::write(&path, INVENTORY).unwrap();
+        }
+        let cache = ProviderCache {
+            dir: dir.path().join("cache"),
+            config: dir.path().join("ceres.conf"),
+            ttl: Duration::from_secs(60),
+            refresh,
+            offline,
+        };
+
+        cache.wrap("test", Box::new(Inventory { path }))
+    }
+
+    /// Removes all instances from the inventory behind the cache's back.
+    fn clear_inventory(dir: &TempDir) { fs::write(inventory_path(dir), "instances = []").unwrap(); }
+
+    #[test]
+    fn describe_instances_uses_cache() {
+        let dir = TempDir::new().unwrap();
+        let provider = create_provider(&dir, false, false);
+        provider.describe_instances().unwrap();
+        clear_inventory(&dir);
+
+        let instances = provider.describe_instances();
+
+        assert_that(&instances).is_ok().has_length(2);
+    }
+
+    #[test]
+    fn refresh_bypasses_cache() {
+        let dir = TempDir::new().unwrap();
+        create_provider(&dir, false, false).describe_instances().unwrap();
+        clear_inventory(&dir);
+
+        let instances = create_provider(&dir, true, false).describe_instances();
+
+        assert_that(&instances).is_ok().is_empty();
+    }
+
+    #[test]
+    fn changing_instances_invalidates_cache() {
+        let dir = TempDir::new().unwrap();
+        let provider = create_provider(&dir, false, false);
+        provider.describe_instances().unwrap();
+
+        provider.stop_instances(false, false, &["i-1".to_owned()]).unwrap();
+        let instance = provider.describe_instance("i-1").unwrap();
+
+        assert_that(&instance.state).is_equal_to(Some("stopped".to_owned()));
+    }
+
+    #[test]
+    fn offline_uses_cache_only() {
+        let dir = TempDir::new().unwrap();
+        assert_that(&create_provider(&dir, false, true).describe_instances()).is_err();
+        create_provider(&dir, false, false).describe_instances().unwrap();
+        clear_inventory(&dir);
+
+        let provider = create_provider(&dir, false, true);
+
+        assert_that(&provider.describe_instances()).is_ok().has_length(2);
+        assert_that(&provider.start_instances(false, &["i-2".to_owned()])).is_err();
+    }
+}
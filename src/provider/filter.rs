@@ -0,0 +1,932 @@
+//! Filter expressions to select instances by their description fields.
+//!
+//! A filter is a boolean expression of predicates, e.g.,
+//! `State == running and (Tags:Intent =~ "^web" or not Tags:Name) and LaunchTime < 7d`.
+//!
+//! * Predicates are combined by `and` (`&&`, `,`), `or` (`||`), `not` (`!`), and parentheses; `not`
+//!   binds strongest, `or` weakest.
+//! * A predicate is a description field followed by an operator and a value. Values may be quoted
+//!   with `"` or `'` and must be quoted if they contain white space, commas, or parentheses.
+//! * `=` and `=~` match a regular expression, `!~` negates the match. `==` and `!=` compare for
+//!   (in)equality. `<`, `<=`, `>`, and `>=` compare numerically if possible, lexicographically
+//!   otherwise. Like all operators, `!~` and `!=` never select instances which lack the field.
+//! * `LaunchTime` is compared to relative times like `30m`, `12h`, `7d`, or `2w`, which denote the
+//!   point in time that long ago, e.g., `LaunchTime < 7d` selects instances launched more than 7
+//!   days ago. Absolute times like `2020-01-31` or `2020-01-31T12:00:00Z` are supported as well.
+//! * A description field without operator checks for the field's existence. Tags are selected by
+//!   `Tags:<tag name>`.
+//! * List fields like `SecurityGroups` match if any of their elements matches.
+//! * For compatibility, `Tags=<tag name>[=<reg ex>][:...]` still selects instances bearing all
+//!   the given tags. Filters in the former syntax `<description field>=<reg ex>,...` which do not
+//!   parse as expression, e.g., because a regular expression contains parentheses, are still
+//!   parsed the former way.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use regex::Regex;
+use std::{cmp::Ordering, collections::HashMap, str::FromStr};
+
+use provider::{InstanceDescriptor, InstanceDescriptorFields};
+
+/// `Filter` selects instances matching a filter expression.
+#[derive(Debug)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn filter(&self, instance: &InstanceDescriptor) -> bool { self.expr.eval(instance) }
+
+    /// Returns whether any predicate of the filter evaluates `field`.
+    pub fn uses_field(&self, field: &InstanceDescriptorFields) -> bool { self.expr.uses_field(field) }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        match parser.parse() {
+            Ok(expr) => Ok(Filter { expr }),
+            Err(e) => parse_legacy_filter(s).ok_or(e),
+        }
+    }
+}
+
+/// Parses the former syntax `<description field>=<reg ex>,...` which does not require quoting regular expressions
+/// containing white space or parentheses; returns `None` if `s` does not follow this syntax.
+fn parse_legacy_filter(s: &str) -> Option<Filter> {
+    let mut builder = FilterBuilder::new();
+    let mut tags = HashMap::new();
+    for part in s.split(',') {
+        let mut splits = part.splitn(2, '=');
+        let (field, re) = (splits.next()?, splits.next()?);
+        match field.parse::<InstanceDescriptorFields>().ok()? {
+            InstanceDescriptorFields::Tags(_) if field == "Tags" => tags.extend(parse_tags_filter_to_hash(re).ok()?),
+            InstanceDescriptorFields::Tags(_) => return None,
+            field => builder.fields.push((field, re)),
+        }
+    }
+    if !tags.is_empty() {
+        builder = builder.tags(tags);
+    }
+
+    builder.build().ok()
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Predicate(Selector, Condition),
+}
+
+impl Expr {
+    fn eval(&self, instance: &InstanceDescriptor) -> bool {
+        match *self {
+            Expr::And(ref exprs) => exprs.iter().all(|x| x.eval(instance)),
+            Expr::Or(ref exprs) => exprs.iter().any(|x| x.eval(instance)),
+            Expr::Not(ref expr) => !expr.eval(instance),
+            Expr::Predicate(ref selector, ref condition) => condition.eval(selector.values(instance)),
+        }
+    }
+
+    fn uses_field(&self, field: &InstanceDescriptorFields) -> bool {
+        match *self {
+            Expr::And(ref exprs) | Expr::Or(ref exprs) => exprs.iter().any(|x| x.uses_field(field)),
+            Expr::Not(ref expr) => expr.uses_field(field),
+            Expr::Predicate(Selector::Field(ref x), _) => x == field,
+            Expr::Predicate(Selector::Tag(_), _) => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Selector {
+    Field(InstanceDescriptorFields),
+    Tag(String),
+}
+
+impl Selector {
+    /// Returns the selected values of an instance or `None` if the instance does not have the field or tag.
+    fn values(&self, instance: &InstanceDescriptor) -> Option<Vec<String>> {
+        match *self {
+            Selector::Field(ref field) => instance.field_values(field),
+            Selector::Tag(ref name) => instance
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.get(name))
+                .map(|value| value.iter().cloned().collect()),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Condition {
+    Exists,
+    Matches(Regex),
+    Equals(String),
+    Compare(Comparison, Operand),
+    /// Holds if the field is present and the condition does not hold, e.g., for `!=` and `!~`
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Evaluates the condition; it never holds for a missing field or tag.
+    fn eval(&self, values: Option<Vec<String>>) -> bool {
+        match values {
+            Some(values) => self.holds(&values),
+            None => false,
+        }
+    }
+
+    fn holds(&self, values: &[String]) -> bool {
+        match *self {
+            Condition::Exists => true,
+            Condition::Not(ref condition) => !condition.holds(values),
+            Condition::Matches(ref re) => values.iter().any(|x| re.is_match(x)),
+            Condition::Equals(ref s) => values.iter().any(|x| x == s),
+            Condition::Compare(ref comparison, ref operand) => values
+                .iter()
+                .filter_map(|x| operand.compare(x))
+                .any(|ordering| comparison.holds(ordering)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Operand {
+    Time(DateTime<Utc>),
+    Number(f64),
+    Text(String),
+}
+
+impl Operand {
+    /// Compares `value` to this operand; returns `None` if `value` cannot be interpreted like this operand.
+    fn compare(&self, value: &str) -> Option<Ordering> {
+        match *self {
+            Operand::Time(ref time) => DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|x| x.with_timezone(&Utc).cmp(time)),
+            Operand::Number(number) => value.parse::<f64>().ok().and_then(|x| x.partial_cmp(&number)),
+            Operand::Text(ref text) => Some(value.cmp(text.as_str())),
+        }
+    }
+}
+
+/// Parses relative times like `7d` into the point in time that long ago, or absolute times like
+/// `2020-01-31` or `2020-01-31T12:00:00Z`.
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(DateTime::from_utc(date.and_hms(0, 0, 0), Utc));
+    }
+    parse_relative_time(s)
+}
+
+/// Parses relative times like `7d` into the point in time that long ago.
+fn parse_relative_time(s: &str) -> Option<DateTime<Utc>> {
+    let unit = s.chars().last()?;
+    let amount: i64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+    let duration = match unit {
+        's' => Duration::seconds(amount),
+        'm' => Duration::minutes(amount),
+        'h' => Duration::hours(amount),
+        'd' => Duration::days(amount),
+        'w' => Duration::weeks(amount),
+        _ => return None,
+    };
+
+    Some(Utc::now() - duration)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Match,
+    NotMatch,
+    Equal,
+    NotEqual,
+    Compare(Comparison),
+}
+
+// Longer operators have to come first, because `=` is a prefix of `=~` and `==`.
+const OPERATORS: &[(&str, Operator)] = &[
+    ("=~", Operator::Match),
+    ("==", Operator::Equal),
+    ("!~", Operator::NotMatch),
+    ("!=", Operator::NotEqual),
+    ("<=", Operator::Compare(Comparison::LessOrEqual)),
+    (">=", Operator::Compare(Comparison::GreaterOrEqual)),
+    ("=", Operator::Match),
+    ("<", Operator::Compare(Comparison::Less)),
+    (">", Operator::Compare(Comparison::Greater)),
+];
+
+/// Recursive descent parser for filter expressions.
+///
+/// expr      := and ( ( "or" | "||" ) and )*
+/// and       := unary ( ( "and" | "&&" | "," ) unary )*
+/// unary     := ( "not" | "!" ) unary | "(" expr ")" | predicate
+/// predicate := field [ ":" tag name ] [ operator value ]
+struct Parser<'a> {
+    input: &'a str,
+    chars: Vec<char>,
+    pos:   usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            input,
+            chars: input.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn parse(&mut self) -> Result<Expr> {
+        let expr = self.parse_or()?;
+        self.skip_whitespace();
+        if self.pos < self.chars.len() {
+            return Err(self.error_at(self.pos, "of unexpected input"));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.parse_and()?];
+        loop {
+            self.skip_whitespace();
+            if self.eat_keyword("or") || self.eat("||") {
+                exprs.push(self.parse_and()?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { Expr::Or(exprs) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.parse_unary()?];
+        loop {
+            self.skip_whitespace();
+            if self.eat_keyword("and") || self.eat("&&") || self.eat(",") {
+                exprs.push(self.parse_unary()?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { Expr::And(exprs) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        if self.eat_keyword("not") || self.eat("!") {
+            let expr = self.parse_unary()?;
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+        if self.eat("(") {
+            let expr = self.parse_or()?;
+            self.skip_whitespace();
+            if !self.eat(")") {
+                return Err(self.error_at(self.pos, "')' is missing"));
+            }
+            return Ok(expr);
+        }
+
+        self.parse_predicate()
+    }
+
+    fn parse_predicate(&mut self) -> Result<Expr> {
+        let field_pos = self.pos;
+        let field_name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+        if field_name.is_empty() {
+            return Err(self.error_at(field_pos, "a description field is expected"));
+        }
+
+        let selector = if field_name == "Tags" && self.eat(":") {
+            let tag_pos = self.pos;
+            let tag_name = self.parse_value(|c| "=!<>~".contains(c))?;
+            if tag_name.is_empty() {
+                return Err(self.error_at(tag_pos, "a tag name is expected"));
+            }
+            Selector::Tag(tag_name)
+        } else {
+            let field = field_name
+                .parse::<InstanceDescriptorFields>()
+                .map_err(|_| self.error_at(field_pos, &format!("'{}' is not a description field", field_name)))?;
+            Selector::Field(field)
+        };
+
+        self.skip_whitespace();
+        let operator = match self.parse_operator() {
+            Some(operator) => operator,
+            None => return Ok(Expr::Predicate(selector, Condition::Exists)),
+        };
+
+        self.skip_whitespace();
+        let value_pos = self.pos;
+        let value = self.parse_value(|_| false)?;
+        if value.is_empty() {
+            return Err(self.error_at(value_pos, "a value is expected"));
+        }
+
+        match (selector, operator) {
+            (Selector::Field(InstanceDescriptorFields::Tags(_)), Operator::Match) => {
+                self.legacy_tags_predicate(&value, value_pos)
+            }
+            (selector, operator) => self.predicate(selector, operator, &value, value_pos),
+        }
+    }
+
+    fn predicate(&self, selector: Selector, operator: Operator, value: &str, value_pos: usize) -> Result<Expr> {
+        let condition = match operator {
+            Operator::Match | Operator::NotMatch => {
+                let re = Regex::new(value)
+                    .map_err(|e| self.error_at(value_pos, &format!("of invalid regular expression: {}", e)))?;
+                Condition::Matches(re)
+            }
+            Operator::Equal | Operator::NotEqual => Condition::Equals(value.to_owned()),
+            Operator::Compare(comparison) => {
+                let operand = match selector {
+                    Selector::Field(InstanceDescriptorFields::LaunchTime) => {
+                        let time = parse_time(value)
+                            .ok_or_else(|| self.error_at(value_pos, "a time like '7d' or '2020-01-31' is expected"))?;
+                        Operand::Time(time)
+                    }
+                    _ => value
+                        .parse::<f64>()
+                        .map(Operand::Number)
+                        .unwrap_or_else(|_| Operand::Text(value.to_owned())),
+                };
+                Condition::Compare(comparison, operand)
+            }
+        };
+
+        let condition = match operator {
+            Operator::NotMatch | Operator::NotEqual => Condition::Not(Box::new(condition)),
+            _ => condition,
+        };
+
+        Ok(Expr::Predicate(selector, condition))
+    }
+
+    /// Supports the former syntax `Tags=<tag name>[=<reg ex>][:...]`.
+    fn legacy_tags_predicate(&self, value: &str, value_pos: usize) -> Result<Expr> {
+        let tags = parse_tags_filter_to_hash(value).map_err(|_| self.error_at(value_pos, "of invalid tags filter"))?;
+        let mut exprs = Vec::new();
+        for (name, re) in tags {
+            let selector = Selector::Tag(name);
+            let expr = match re {
+                Some(re) => self.predicate(selector, Operator::Match, re, value_pos)?,
+                None => Expr::Predicate(selector, Condition::Exists),
+            };
+            exprs.push(expr);
+        }
+
+        Ok(Expr::And(exprs))
+    }
+
+    fn parse_operator(&mut self) -> Option<Operator> {
+        for &(symbol, operator) in OPERATORS {
+            if self.eat(symbol) {
+                return Some(operator);
+            }
+        }
+
+        None
+    }
+
+    /// Parses a quoted or bare value; bare values end at white space, commas, parentheses, or `is_end`.
+    fn parse_value<F: Fn(char) -> bool>(&mut self, is_end: F) -> Result<String> {
+        let quote = match self.peek() {
+            Some(c) if c == '"' || c == '\'' => c,
+            _ => {
+                return Ok(self.take_while(|c| !(c.is_whitespace() || c == ',' || c == '(' || c == ')' || is_end(c))))
+            }
+        };
+
+        let start = self.pos;
+        self.pos += 1;
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '\\' => match self.peek() {
+                    Some(next) if next == quote || next == '\\' => {
+                        value.push(next);
+                        self.pos += 1;
+                    }
+                    _ => value.push(c),
+                },
+                c if c == quote => return Ok(value),
+                c => value.push(c),
+            }
+        }
+
+        Err(self.error_at(start, "of unterminated quote"))
+    }
+
+    fn peek(&self) -> Option<char> { self.chars.get(self.pos).cloned() }
+
+    fn skip_whitespace(&mut self) { self.take_while(char::is_whitespace); }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> String {
+        let start = self.pos;
+        while self.peek().map(&predicate).unwrap_or(false) {
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        let matches =
+            self.pos + len <= self.chars.len() && s.chars().eq(self.chars[self.pos..self.pos + len].iter().cloned());
+        if matches {
+            self.pos += len;
+        }
+
+        matches
+    }
+
+    /// Eats the case insensitive keyword `keyword` if it is not just the prefix of a longer word.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let len = keyword.chars().count();
+        if self.pos + len > self.chars.len() {
+            return false;
+        }
+        let word: String = self.chars[self.pos..self.pos + len].iter().collect();
+        let is_delimited = self
+            .chars
+            .get(self.pos + len)
+            .map(|c| c.is_whitespace() || *c == '(')
+            .unwrap_or(true);
+        if word.eq_ignore_ascii_case(keyword) && is_delimited {
+            self.pos += len;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error_at(&self, pos: usize, reason: &str) -> Error {
+        Error::from_kind(ErrorKind::FilterSyntaxError(self.input.to_owned(), pos + 1, reason.to_owned()))
+    }
+}
+
+/// `FilterBuilder` builds a `Filter` that selects instances matching all given regular expressions and tags.
+#[derive(Default)]
+pub struct FilterBuilder<'a> {
+    fields: Vec<(InstanceDescriptorFields, &'a str)>,
+    tags:   Option<HashMap<String, Option<&'a str>>>,
+}
+
+macro_rules! field_setters {
+    ($($field:ident => $variant:ident),+) => {
+        $(
+        pub fn $field(mut self, $field: &'a str) -> Self {
+            self.fields.push((InstanceDescriptorFields::$variant, $field));
+            self
+        }
+        )*
+    }
+}
+
+impl<'a> FilterBuilder<'a> {
+    pub fn new() -> Self { Default::default() }
+
+    field_setters!(
+        account => Account,
+        block_device_mappings => BlockDeviceMappings,
+        cost => Cost,
+        hypervisor => Hypervisor,
+        iam_instance_profile => IamInstanceProfile,
+        image_id => ImageId,
+        instance_id => InstanceId,
+        instance_type => InstanceType,
+        ipv6_addresses => Ipv6Addresses,
+        monitoring => Monitoring,
+        network_interfaces => NetworkInterfaces,
+        placement => Placement,
+        private_dns_name => PrivateDnsName,
+        private_ip_address => PrivateIpAddress,
+        private_ip_addresses => PrivateIpAddresses,
+        profile => Profile,
+        public_dns_name => PublicDnsName,
+        public_ip_address => PublicIpAddress,
+        region => Region,
+        root_device_name => RootDeviceName,
+        root_device_type => RootDeviceType,
+        security_groups => SecurityGroups,
+        state => State,
+        state_reason => StateReason,
+        virtualization_type => VirtualizationType,
+        volumes => Volumes,
+        vpc_id => VpcId
+    );
+
+    pub fn tags(mut self, tags: HashMap<String, Option<&'a str>>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    pub fn build(self) -> Result<Filter> {
+        let mut exprs = Vec::new();
+
+        for (field, re) in self.fields {
+            let regex =
+                Regex::new(re).chain_err(|| ErrorKind::FilterRegexError(re.to_owned(), format!("{:?}", field)))?;
+            exprs.push(Expr::Predicate(Selector::Field(field), Condition::Matches(regex)));
+        }
+
+        for (name, re) in self.tags.unwrap_or_default() {
+            let condition = match re {
+                Some(re) => Condition::Matches(
+                    Regex::new(re).chain_err(|| ErrorKind::FilterRegexError(re.to_owned(), name.clone()))?,
+                ),
+                None => Condition::Exists,
+            };
+            exprs.push(Expr::Predicate(Selector::Tag(name), condition));
+        }
+
+        Ok(Filter {
+            expr: Expr::And(exprs),
+        })
+    }
+}
+
+fn parse_tags_filter_to_hash(tags_filter: &str) -> Result<HashMap<String, Option<&str>>> {
+    let mut hm = HashMap::new();
+    for tag in tags_filter.split(':') {
+        let mut kv: Vec<_> = tag.split('=').collect();
+        match kv.len() {
+            2 => hm.insert(kv.remove(0).to_owned(), Some(kv.remove(0))),
+            1 => hm.insert(kv.remove(0).to_owned(), None),
+            _ => {
+                return Err(Error::from_kind(ErrorKind::FilterParsingFailed(
+                    tags_filter.to_owned(),
+                    "splitting fields failed".to_owned(),
+                )))
+            }
+        };
+    }
+
+    Ok(hm)
+}
+
+error_chain! {
+    errors {
+        FilterRegexError(re: String, field: String) {
+            description("Failed to build reg exp.")
+            display("Failed to build reg exp '{}' for field '{}'", re, field)
+        }
+        FilterParsingFailed(s: String, reason: String) {
+            description("Failed to parse Filter from String.")
+            display("Failed to parse Filter from String '{}' becaue {}", s, reason)
+        }
+        FilterSyntaxError(s: String, column: usize, reason: String) {
+            description("Invalid filter syntax.")
+            display(
+                "Failed to parse filter at column {} because {}\n{}\n{:>width$}",
+                column, reason, s, "^", width = column
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+    use provider::{NetworkInterface, Volume};
+    use spectral::prelude::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn parse_filter_no_tags_okay() {
+        let filter_arg = "InstanceId=i-.*,State=stopped";
+        let _ = filter_arg.parse::<Filter>().unwrap();
+    }
+
+    #[test]
+    fn parse_filter_with_tags_okay() {
+        let filter_arg = "InstanceId=i-.*,Tags=Name:AnsibleHostGroup=batch_.*,State=stopped";
+        let _ = filter_arg.parse::<Filter>().unwrap();
+    }
+
+    #[test]
+    fn parse_tags_filter_to_hash_okay() {
+        let tags_filter = "Name:AnsibleHostGroup=batch_.*";
+
+        let hm = parse_tags_filter_to_hash(tags_filter);
+
+        assert_that(&hm).is_ok().contains_entry("Name".to_owned(), None);
+        assert_that(&hm)
+            .is_ok()
+            .contains_entry("AnsibleHostGroup".to_owned(), Some("batch_.*"));
+    }
+
+    #[test]
+    fn filter_instance_with_invalid_re() {
+        let filter = FilterBuilder::new().instance_id("\\i-.*").build();
+
+        assert!(&filter.is_err())
+    }
+
+    fn create_instance() -> InstanceDescriptor {
+        let mut tags = HashMap::new();
+        tags.insert("Name".to_owned(), Some("my_instance".to_owned()));
+        tags.insert("Intent".to_owned(), Some("my_project".to_owned()));
+
+        InstanceDescriptor {
+            instance_id: Some("i-12345".to_owned()),
+            image_id: Some("ami-12345".to_owned()),
+            tags: Some(tags),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn filter_instance_with_empty_filter() {
+        let instance = create_instance();
+        let filter = FilterBuilder::new().build().expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_true()
+    }
+
+    #[test]
+    fn filter_instance_with_instance_id_okay() {
+        let instance = create_instance();
+        let filter = FilterBuilder::new()
+            .instance_id("i-.*")
+            .build()
+            .expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_true()
+    }
+
+    #[test]
+    fn filter_instance_with_instance_id_fail() {
+        let instance = create_instance();
+        let filter = FilterBuilder::new()
+            .instance_id("instance-.*")
+            .build()
+            .expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_false()
+    }
+
+    #[test]
+    fn filter_instance_with_instance_id_and_image_id_okay() {
+        let instance = create_instance();
+        let filter = FilterBuilder::new()
+            .instance_id("i-.*")
+            .image_id("ami-.*")
+            .build()
+            .expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_true()
+    }
+
+    #[test]
+    fn filter_instance_with_instance_id_and_image_id_fail() {
+        let instance = create_instance();
+        let filter = FilterBuilder::new()
+            .instance_id("i-.*")
+            .image_id("image-.*")
+            .build()
+            .expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_false()
+    }
+
+    #[test]
+    fn filter_instance_with_tags_okay() {
+        let instance = create_instance();
+
+        let mut tags = HashMap::new();
+        tags.insert("Name".to_owned(), Some("my_.*"));
+        tags.insert("Intent".to_owned(), Some("my_.*"));
+        let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_true()
+    }
+
+    #[test]
+    fn filter_instance_with_tag_without_value_okay() {
+        let instance = create_instance();
+
+        let mut tags = HashMap::new();
+        tags.insert("Name".to_owned(), None);
+        let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_true()
+    }
+
+    #[test]
+    fn filter_instance_with_missing_tag() {
+        let instance = create_instance();
+
+        let mut tags = HashMap::new();
+        tags.insert("NoSuchTagName".to_owned(), Some("my_.*"));
+        let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_false()
+    }
+
+    #[test]
+    fn filter_instance_with_tags_fail() {
+        let instance = create_instance();
+
+        let mut tags = HashMap::new();
+        tags.insert("Name".to_owned(), Some("my_.*"));
+        tags.insert("Intent".to_owned(), Some("not_my_.*"));
+        let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
+
+        assert_that(&filter.filter(&instance)).is_false()
+    }
+
+    fn parse(filter: &str) -> Filter { filter.parse::<Filter>().unwrap() }
+
+    fn create_instance_with_state(state: &str) -> InstanceDescriptor {
+        InstanceDescriptor {
+            state: Some(state.to_owned()),
+            launch_time: Some(Utc.ymd(2018, 7, 13).and_hms(10, 23, 4)),
+            security_groups: Some(vec!["id=sg-1, name=default".to_owned(), "id=sg-2, name=web".to_owned()]),
+            ..create_instance()
+        }
+    }
+
+    #[test]
+    fn parse_filter_expression_okay() {
+        let filter_arg = "State == running and (Tags:Intent =~ '^my_' or not Tags:Name) && LaunchTime < 7d";
+
+        let res = filter_arg.parse::<Filter>();
+
+        assert_that(&res).is_ok();
+    }
+
+    #[test]
+    fn parse_filter_unknown_field_reports_column() {
+        let res = "State == running and NoSuchField".parse::<Filter>();
+
+        match res.map_err(|e| e.0) {
+            Err(ErrorKind::FilterSyntaxError(_, column, _)) => assert_that(&column).is_equal_to(22),
+            _ => panic!("expected syntax error"),
+        }
+    }
+
+    #[test]
+    fn parse_filter_missing_paren_fails() {
+        assert_that(&"(State == running".parse::<Filter>()).is_err();
+    }
+
+    #[test]
+    fn parse_filter_unterminated_quote_fails() {
+        assert_that(&"State == 'running".parse::<Filter>()).is_err();
+    }
+
+    #[test]
+    fn parse_filter_trailing_input_fails() {
+        assert_that(&"State == running )".parse::<Filter>()).is_err();
+    }
+
+    #[test]
+    fn filter_equality_and_negation() {
+        let instance = create_instance_with_state("running");
+
+        assert_that(&parse("State == running").filter(&instance)).is_true();
+        assert_that(&parse("State == run").filter(&instance)).is_false();
+        assert_that(&parse("State != running").filter(&instance)).is_false();
+        assert_that(&parse("State !~ ^stop").filter(&instance)).is_true();
+        assert_that(&parse("!State=running").filter(&instance)).is_false();
+    }
+
+    #[test]
+    fn filter_or_and_precedence() {
+        let instance = create_instance_with_state("stopped");
+
+        assert_that(&parse("State == running or State == stopped").filter(&instance)).is_true();
+        assert_that(&parse("State == running or State == stopped and ImageId == ami-0").filter(&instance)).is_false();
+        assert_that(&parse("(State == running or State == stopped) and ImageId == ami-12345").filter(&instance))
+            .is_true();
+    }
+
+    #[test]
+    fn filter_tag_existence() {
+        let instance = create_instance_with_state("running");
+
+        assert_that(&parse("Tags:Name").filter(&instance)).is_true();
+        assert_that(&parse("not Tags:NoSuchTagName").filter(&instance)).is_true();
+        assert_that(&parse("Tags:Intent == my_project").filter(&instance)).is_true();
+        assert_that(&parse("PublicIpAddress").filter(&instance)).is_false();
+    }
+
+    #[test]
+    fn filter_list_field_matches_any_element() {
+        let instance = create_instance_with_state("running");
+
+        assert_that(&parse("SecurityGroups =~ 'name=web'").filter(&instance)).is_true();
+        assert_that(&parse("SecurityGroups =~ 'name=db'").filter(&instance)).is_false();
+    }
+
+    #[test]
+    fn filter_network_interfaces_and_volumes() {
+        let instance = InstanceDescriptor {
+            network_interfaces: Some(vec![
+                NetworkInterface {
+                    private_ip_addresses: vec!["10.0.0.1".to_owned()],
+                    ..Default::default()
+                },
+                NetworkInterface {
+                    private_ip_addresses: vec!["10.0.1.1".to_owned(), "10.0.1.2".to_owned()],
+                    ..Default::default()
+                },
+            ]),
+            volumes: Some(vec![Volume {
+                volume_id: Some("vol-12345".to_owned()),
+                encrypted: Some(false),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        assert_that(&parse("PrivateIpAddresses == 10.0.1.2").filter(&instance)).is_true();
+        assert_that(&parse("PrivateIpAddresses == 10.0.2.1").filter(&instance)).is_false();
+        assert_that(&parse("Volumes =~ encrypted=false").filter(&instance)).is_true();
+        assert_that(&parse("Ipv6Addresses").filter(&instance)).is_false();
+    }
+
+    #[test]
+    fn filter_launch_time() {
+        let instance = create_instance_with_state("running");
+
+        assert_that(&parse("LaunchTime < 7d").filter(&instance)).is_true();
+        assert_that(&parse("LaunchTime > 7d").filter(&instance)).is_false();
+        assert_that(&"LaunchTime < yesterday".parse::<Filter>()).is_err();
+    }
+
+    #[test]
+    fn filter_launch_time_absolute() {
+        let instance = create_instance_with_state("running");
+
+        assert_that(&parse("LaunchTime < 2019-01-01").filter(&instance)).is_true();
+        assert_that(&parse("LaunchTime >= 2018-07-14").filter(&instance)).is_false();
+        assert_that(&parse("LaunchTime >= '2018-07-13T10:23:04Z'").filter(&instance)).is_true();
+    }
+
+    #[test]
+    fn filter_legacy_syntax() {
+        let instance = create_instance_with_state("running");
+
+        assert_that(&parse("InstanceId=i-.*,Tags=Name:Intent=my_.*,State=running").filter(&instance)).is_true();
+        assert_that(&parse("InstanceId=i-.*,Tags=Name:Intent=other_.*").filter(&instance)).is_false();
+        assert_that(&parse("State=(running|stopped)").filter(&instance)).is_true();
+        assert_that(&parse("InstanceId=i-.*,Tags=Intent=^my_(project|team)$").filter(&instance)).is_true();
+        assert_that(&"State=(running".parse::<Filter>()).is_err();
+    }
+
+    #[test]
+    fn filter_uses_field() {
+        let filter = parse("State == running and (Tags:Cost or not Cost > 0.1)");
+
+        assert_that(&filter.uses_field(&InstanceDescriptorFields::Cost)).is_true();
+        assert_that(&filter.uses_field(&InstanceDescriptorFields::InstanceType)).is_false();
+    }
+
+    #[test]
+    fn filter_negation_requires_field() {
+        let instance = create_instance_with_state("running");
+
+        assert_that(&parse("Tags:Intent != other").filter(&instance)).is_true();
+        assert_that(&parse("Tags:NoSuchTagName != prod").filter(&instance)).is_false();
+        assert_that(&parse("PublicIpAddress !~ ^10\\.").filter(&instance)).is_false();
+        assert_that(&parse("not Tags:NoSuchTagName == prod").filter(&instance)).is_true();
+    }
+}
@@ -0,0 +1,467 @@
+use serde_json;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use toml;
+
+use provider::{
+    create_dry_run_results,
+    CreateImage,
+    CreateSnapshots,
+    CreateTags,
+    DeleteTags,
+    DescribeImages,
+    DescribeInstance,
+    DescribeInstances,
+    DescribeSecurityGroups,
+    Error as ProviderError,
+    ErrorKind as ProviderErrorKind,
+    Image,
+    ImageDescriptor,
+    InstanceDescriptor,
+    InstanceId,
+    ModifyInstanceType,
+    RebootInstances,
+    Result as ProviderResult,
+    SecurityGroup,
+    Snapshot,
+    StartInstances,
+    StateChange,
+    StopInstances,
+    TerminateInstances,
+};
+
+const RUNNING: &str = "running";
+const STOPPED: &str = "stopped";
+const TERMINATED: &str = "terminated";
+
+/// `Inventory` is a provider backed by a local file of instance descriptions.
+///
+/// The file contains a list of `InstanceDescriptor`s in TOML or JSON format -- JSON is chosen by the
+/// file extension `.json`. Starting, stopping, and terminating instances changes the instances'
+/// states in that file. This allows to use ceres without a cloud provider, e.g., for tests and demos.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Inventory {
+    pub path: PathBuf,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct InventoryFile {
+    #[serde(default)]
+    instances: Vec<InstanceDescriptor>,
+}
+
+impl Inventory {
+    fn is_json(&self) -> bool { self.path.extension().map(|x| x == "json").unwrap_or(false) }
+
+    fn load(&self) -> Result<InventoryFile> {
+        let content =
+            fs::read_to_string(&self.path).chain_err(|| ErrorKind::InventoryFileError(path_to_string(&self.path)))?;
+        let inventory = if self.is_json() {
+            serde_json::from_str(&content)
+                .chain_err(|| ErrorKind::InventoryFileError(path_to_string(&self.path)))?
+        } else {
+            toml::from_str(&content).chain_err(|| ErrorKind::InventoryFileError(path_to_string(&self.path)))?
+        };
+
+        Ok(inventory)
+    }
+
+    fn save(&self, inventory: &InventoryFile) -> Result<()> {
+        let content = if self.is_json() {
+            serde_json::to_string_pretty(inventory)
+                .chain_err(|| ErrorKind::InventoryFileError(path_to_string(&self.path)))?
+        } else {
+            // Serializing via `toml::Value` makes sure tables are emitted after plain values.
+            let value = toml::Value::try_from(inventory)
+                .chain_err(|| ErrorKind::InventoryFileError(path_to_string(&self.path)))?;
+            toml::to_string(&value).chain_err(|| ErrorKind::InventoryFileError(path_to_string(&self.path)))?
+        };
+
+        fs::write(&self.path, content).chain_err(|| ErrorKind::InventoryFileError(path_to_string(&self.path)))
+    }
+}
+
+fn path_to_string(path: &Path) -> String { path.to_string_lossy().to_string() }
+
+impl DescribeInstances for Inventory {
+    fn describe_instances(&self) -> ProviderResult<Vec<InstanceDescriptor>> {
+        self.load().map(|inventory| inventory.instances).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("describe_instances")),
+            )
+        })
+    }
+}
+
+impl DescribeInstance for Inventory {
+    fn describe_instance(&self, instance_id: &str) -> ProviderResult<InstanceDescriptor> {
+        describe(self, instance_id).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("describe_instance")),
+            )
+        })
+    }
+}
+
+fn describe(inventory: &Inventory, instance_id: &str) -> Result<InstanceDescriptor> {
+    inventory
+        .load()?
+        .instances
+        .into_iter()
+        .find(|i| i.instance_id.as_ref().map(|x| x == instance_id).unwrap_or(false))
+        .ok_or_else(|| Error::from_kind(ErrorKind::NoSuchInstance(instance_id.to_owned())))
+}
+
+impl StartInstances for Inventory {
+    fn start_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> ProviderResult<Vec<StateChange>> {
+        change_state(self, dry, instance_ids, RUNNING).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("start_instances")),
+            )
+        })
+    }
+}
+
+impl StopInstances for Inventory {
+    fn stop_instances(&self, dry: bool, _: bool, instance_ids: &[InstanceId]) -> ProviderResult<Vec<StateChange>> {
+        change_state(self, dry, instance_ids, STOPPED).map_err(|e| {
+            ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("stop_instances")))
+        })
+    }
+}
+
+impl TerminateInstances for Inventory {
+    fn terminate_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> ProviderResult<Vec<StateChange>> {
+        change_state(self, dry, instance_ids, TERMINATED).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("terminate_instances")),
+            )
+        })
+    }
+}
+
+impl CreateSnapshots for Inventory {
+    fn create_snapshots(&self, _: bool, _: &str, _: &HashMap<String, String>) -> ProviderResult<Vec<Snapshot>> {
+        Err(unsupported("create_snapshots"))
+    }
+
+    fn snapshot_state(&self, _: &str) -> ProviderResult<Option<String>> { Err(unsupported("snapshot_state")) }
+}
+
+impl CreateImage for Inventory {
+    fn create_image(&self, _: bool, _: &str, _: &str, _: bool, _: &HashMap<String, String>) -> ProviderResult<Image> {
+        Err(unsupported("create_image"))
+    }
+
+    fn image_state(&self, _: &str) -> ProviderResult<Option<String>> { Err(unsupported("image_state")) }
+}
+
+impl DescribeImages for Inventory {
+    fn describe_images(&self) -> ProviderResult<Vec<ImageDescriptor>> { Err(unsupported("describe_images")) }
+}
+
+impl DescribeSecurityGroups for Inventory {
+    fn describe_security_groups(&self) -> ProviderResult<Vec<SecurityGroup>> {
+        Err(unsupported("describe_security_groups"))
+    }
+}
+
+/// An inventory only knows instances, but neither volumes, images, nor security groups.
+fn unsupported(operation: &str) -> ProviderError {
+    ProviderError::with_chain(
+        Error::from_kind(ErrorKind::Unsupported(operation.to_owned())),
+        ProviderErrorKind::ProviderCallFailed(operation.to_owned()),
+    )
+}
+
+impl CreateTags for Inventory {
+    fn create_tags(
+        &self,
+        dry: bool,
+        instance_ids: &[InstanceId],
+        tags: &HashMap<String, String>,
+    ) -> ProviderResult<()> {
+        change_tags(self, dry, instance_ids, |instance_tags| {
+            for (k, v) in tags {
+                instance_tags.insert(k.to_owned(), Some(v.to_owned()));
+            }
+        })
+        .map_err(|e| ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("create_tags"))))
+    }
+}
+
+impl DeleteTags for Inventory {
+    fn delete_tags(&self, dry: bool, instance_ids: &[InstanceId], keys: &[String]) -> ProviderResult<()> {
+        change_tags(self, dry, instance_ids, |instance_tags| {
+            for k in keys {
+                instance_tags.remove(k);
+            }
+        })
+        .map_err(|e| ProviderError::with_chain(e, ProviderErrorKind::ProviderCallFailed(String::from("delete_tags"))))
+    }
+}
+
+fn change_tags<F>(inventory: &Inventory, dry: bool, instance_ids: &[InstanceId], f: F) -> Result<()>
+where
+    F: Fn(&mut HashMap<String, Option<String>>),
+{
+    let mut file = inventory.load()?;
+
+    for instance_id in instance_ids {
+        let instance = file
+            .instances
+            .iter_mut()
+            .find(|i| i.instance_id.as_ref() == Some(instance_id))
+            .ok_or_else(|| Error::from_kind(ErrorKind::NoSuchInstance(instance_id.to_owned())))?;
+        f(instance.tags.get_or_insert_with(HashMap::new));
+    }
+
+    if dry {
+        return Ok(());
+    }
+    inventory.save(&file)
+}
+
+impl RebootInstances for Inventory {
+    fn reboot_instances(&self, _: bool, instance_ids: &[InstanceId]) -> ProviderResult<()> {
+        reboot(self, instance_ids).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("reboot_instances")),
+            )
+        })
+    }
+}
+
+/// Rebooting does not change an instance's state, so only running instances are checked for.
+fn reboot(inventory: &Inventory, instance_ids: &[InstanceId]) -> Result<()> {
+    for instance_id in instance_ids {
+        let instance = describe(inventory, instance_id)?;
+        let state = instance.state.unwrap_or_else(|| String::from("- n/a -"));
+        if state != RUNNING {
+            return Err(Error::from_kind(ErrorKind::InvalidStateChange(
+                instance_id.to_owned(),
+                state,
+                String::from("rebooting"),
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+impl ModifyInstanceType for Inventory {
+    fn modify_instance_type(&self, dry: bool, instance_id: &str, instance_type: &str) -> ProviderResult<()> {
+        modify_instance_type(self, dry, instance_id, instance_type).map_err(|e| {
+            ProviderError::with_chain(
+                e,
+                ProviderErrorKind::ProviderCallFailed(String::from("modify_instance_type")),
+            )
+        })
+    }
+}
+
+fn modify_instance_type(inventory: &Inventory, dry: bool, instance_id: &str, instance_type: &str) -> Result<()> {
+    let mut file = inventory.load()?;
+
+    {
+        let instance = file
+            .instances
+            .iter_mut()
+            .find(|i| i.instance_id.as_ref().map(|x| x == instance_id).unwrap_or(false))
+            .ok_or_else(|| Error::from_kind(ErrorKind::NoSuchInstance(instance_id.to_owned())))?;
+        // A dry run of `instances resize` checks the type change before the instance has actually been stopped
+        if !dry && instance.state.as_ref().map(String::as_str) != Some(STOPPED) {
+            return Err(Error::from_kind(ErrorKind::InstanceNotStopped(instance_id.to_owned())));
+        }
+        instance.instance_type = Some(instance_type.to_owned());
+    }
+
+    if dry {
+        return Ok(());
+    }
+    inventory.save(&file)
+}
+
+fn change_state(
+    inventory: &Inventory,
+    dry: bool,
+    instance_ids: &[InstanceId],
+    state: &str,
+) -> Result<Vec<StateChange>> {
+    let mut file = inventory.load()?;
+
+    let mut state_changes = Vec::new();
+    for instance_id in instance_ids {
+        let instance = file
+            .instances
+            .iter_mut()
+            .find(|i| i.instance_id.as_ref() == Some(instance_id))
+            .ok_or_else(|| Error::from_kind(ErrorKind::NoSuchInstance(instance_id.to_owned())))?;
+        let previous_state = instance.state.clone().unwrap_or_else(|| String::from("- n/a -"));
+        if previous_state == TERMINATED {
+            return Err(Error::from_kind(ErrorKind::InvalidStateChange(
+                instance_id.to_owned(),
+                previous_state,
+                state.to_owned(),
+            )));
+        }
+        instance.state = Some(state.to_owned());
+        state_changes.push(StateChange {
+            instance_id:    instance_id.to_owned(),
+            current_state:  state.to_owned(),
+            previous_state,
+        });
+    }
+
+    if dry {
+        return Ok(create_dry_run_results(instance_ids));
+    }
+    inventory.save(&file)?;
+
+    Ok(state_changes)
+}
+
+error_chain! {
+    errors {
+        InventoryFileError(path: String) {
+            description("Failed to access inventory file.")
+            display("Failed to access inventory file '{}'.", path)
+        }
+        NoSuchInstance(instance_id: String) {
+            description("No such instance in inventory.")
+            display("No instance with id '{}' in inventory.", instance_id)
+        }
+        InvalidStateChange(instance_id: String, from: String, to: String) {
+            description("Invalid instance state change.")
+            display("Cannot change state of instance '{}' from '{}' to '{}'.", instance_id, from, to)
+        }
+        Unsupported(operation: String) {
+            description("Operation is not supported by inventory.")
+            display("Operation '{}' is not supported by inventory.", operation)
+        }
+        InstanceNotStopped(instance_id: String) {
+            description("Instance is not stopped.")
+            display("Instance '{}' has to be stopped to change its instance type.", instance_id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+    use tempfile::NamedTempFile;
+
+    fn create_inventory() -> (NamedTempFile, Inventory) {
+        let file = NamedTempFile::new().unwrap();
+        let content = r#"
+[[instances]]
+instance_id = "i-1"
+state = "running"
+
+[[instances]]
+instance_id = "i-2"
+state = "terminated"
+"#;
+        fs::write(file.path(), content).unwrap();
+        let inventory = Inventory {
+            path: file.path().to_path_buf(),
+        };
+
+        (file, inventory)
+    }
+
+    #[test]
+    fn stop_instance_persists_state() {
+        let (_file, inventory) = create_inventory();
+
+        let res = inventory.stop_instances(false, false, &["i-1".to_owned()]);
+        assert_that(&res).is_ok().has_length(1);
+
+        let instance = inventory.describe_instance("i-1").unwrap();
+        assert_that(&instance.state).is_some().is_equal_to("stopped".to_owned());
+    }
+
+    #[test]
+    fn stop_instance_dry_run_does_not_persist_state() {
+        let (_file, inventory) = create_inventory();
+
+        let res = inventory.stop_instances(true, false, &["i-1".to_owned()]);
+        assert_that(&res).is_ok().has_length(1);
+
+        let instance = inventory.describe_instance("i-1").unwrap();
+        assert_that(&instance.state).is_some().is_equal_to("running".to_owned());
+    }
+
+    #[test]
+    fn modify_instance_type_of_running_instance_fails() {
+        let (_file, inventory) = create_inventory();
+
+        let res = inventory.modify_instance_type(false, "i-1", "m5.large");
+
+        assert_that(&res).is_err();
+    }
+
+    #[test]
+    fn resize_dry_run_of_running_instance_succeeds() {
+        let (_file, inventory) = create_inventory();
+
+        // Like `instances resize --dry`, which neither stops nor starts the instance
+        let _ = inventory.stop_instances(true, false, &["i-1".to_owned()]).unwrap();
+        let res = inventory.modify_instance_type(true, "i-1", "m5.large");
+        assert_that(&res).is_ok();
+
+        let instance = inventory.describe_instance("i-1").unwrap();
+        assert_that(&instance.state).is_equal_to(Some("running".to_owned()));
+        assert_that(&instance.instance_type).is_none();
+    }
+
+    #[test]
+    fn modify_instance_type_of_stopped_instance_persists_type() {
+        let (_file, inventory) = create_inventory();
+        let _ = inventory.stop_instances(false, false, &["i-1".to_owned()]).unwrap();
+
+        let res = inventory.modify_instance_type(false, "i-1", "m5.large");
+        assert_that(&res).is_ok();
+
+        let instance = inventory.describe_instance("i-1").unwrap();
+        assert_that(&instance.instance_type).is_some().is_equal_to("m5.large".to_owned());
+    }
+
+    #[test]
+    fn create_and_delete_tags_persists_tags() {
+        let (_file, inventory) = create_inventory();
+        let mut tags = HashMap::new();
+        tags.insert("Intent".to_owned(), "webserver".to_owned());
+
+        let res = inventory.create_tags(false, &["i-1".to_owned()], &tags);
+        assert_that(&res).is_ok();
+        let instance = inventory.describe_instance("i-1").unwrap();
+        assert_that(&instance.tags)
+            .is_some()
+            .contains_entry("Intent".to_owned(), Some("webserver".to_owned()));
+
+        let res = inventory.delete_tags(false, &["i-1".to_owned()], &["Intent".to_owned()]);
+        assert_that(&res).is_ok();
+        let instance = inventory.describe_instance("i-1").unwrap();
+        assert_that(&instance.tags).is_some().does_not_contain_key("Intent".to_owned());
+    }
+
+    #[test]
+    fn start_terminated_instance_fails() {
+        let (_file, inventory) = create_inventory();
+
+        let res = inventory.start_instances(false, &["i-2".to_owned()]);
+
+        assert_that(&res).is_err();
+    }
+}
@@ -1,6 +1,61 @@
-use std::{collections::HashMap, str::FromStr};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::{collections::HashMap, fmt, str::FromStr};
 
 pub mod aws;
+pub mod cache;
+pub mod filter;
+pub mod inventory;
+pub mod pricing;
+pub mod selector;
+pub mod sort;
+
+/// `Provider` bundles all capabilities a provider backend has to offer.
+///
+/// Modules should not depend on a specific backend like AWS, but obtain a `Box<dyn Provider>` for
+/// the active profile via `modules::get_provider`. Providers are `Send + Sync` so that instances
+/// can be queried concurrently, e.g., while waiting for state changes.
+pub trait Provider:
+    CreateImage
+    + CreateSnapshots
+    + CreateTags
+    + DeleteTags
+    + DescribeImages
+    + DescribeInstance
+    + DescribeInstances
+    + DescribeSecurityGroups
+    + ModifyInstanceType
+    + RebootInstances
+    + StartInstances
+    + StopInstances
+    + TerminateInstances
+    + Send
+    + Sync
+{
+}
+
+impl<T> Provider for T where
+    T: CreateImage
+        + CreateSnapshots
+        + CreateTags
+        + DeleteTags
+        + DescribeImages
+        + DescribeInstance
+        + DescribeInstances
+        + DescribeSecurityGroups
+        + ModifyInstanceType
+        + RebootInstances
+        + StartInstances
+        + StopInstances
+        + TerminateInstances
+        + Send
+        + Sync
+{
+}
+
+pub trait DescribeImages {
+    /// Describes all images owned by the provider's accounts.
+    fn describe_images(&self) -> Result<Vec<ImageDescriptor>>;
+}
 
 pub trait DescribeInstances {
     fn describe_instances(&self) -> Result<Vec<InstanceDescriptor>>;
@@ -10,20 +65,56 @@ pub trait DescribeInstance {
     fn describe_instance(&self, instance_id: &str) -> Result<InstanceDescriptor>;
 }
 
+/// Describes the instances `instance_ids` in the given order using a single `describe_instances` call.
+///
+/// Instances missing from the bulk result are looked up individually, so unknown ids fail as they would for
+/// `describe_instance`.
+pub fn describe_instances_by_ids<P: DescribeInstances + DescribeInstance + ?Sized>(
+    provider: &P,
+    instance_ids: &[InstanceId],
+) -> Result<Vec<InstanceDescriptor>> {
+    let mut instances: HashMap<_, _> = provider
+        .describe_instances()?
+        .into_iter()
+        .filter_map(|i| i.instance_id.clone().map(|id| (id, i)))
+        .collect();
+
+    instance_ids
+        .iter()
+        .map(|id| match instances.remove(id) {
+            Some(instance) => Ok(instance),
+            None => provider.describe_instance(id),
+        })
+        .collect()
+}
+
+pub trait DescribeSecurityGroups {
+    /// Describes all security groups including their rules.
+    fn describe_security_groups(&self) -> Result<Vec<SecurityGroup>>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum InstanceDescriptorFields {
+    Account,
     BlockDeviceMappings,
+    Cost,
     Hypervisor,
     IamInstanceProfile,
     ImageId,
     InstanceId,
     InstanceType,
+    Ipv6Addresses,
     LaunchTime,
     Monitoring,
+    NetworkInterfaces,
     Placement,
     PrivateDnsName,
     PrivateIpAddress,
+    PrivateIpAddresses,
+    Profile,
     PublicDnsName,
     PublicIpAddress,
+    Region,
     RootDeviceName,
     RootDeviceType,
     SecurityGroups,
@@ -31,6 +122,7 @@ pub enum InstanceDescriptorFields {
     StateReason,
     Tags(Option<Vec<String>>),
     VirtualizationType,
+    Volumes,
     VpcId,
 }
 
@@ -39,19 +131,26 @@ impl FromStr for InstanceDescriptorFields {
 
     fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
         match s {
+            "Account" => Ok(InstanceDescriptorFields::Account),
             "BlockDeviceMappings" => Ok(InstanceDescriptorFields::BlockDeviceMappings),
+            "Cost" => Ok(InstanceDescriptorFields::Cost),
             "Hypervisor" => Ok(InstanceDescriptorFields::Hypervisor),
             "IamInstanceProfile" => Ok(InstanceDescriptorFields::IamInstanceProfile),
             "ImageId" => Ok(InstanceDescriptorFields::ImageId),
             "InstanceId" => Ok(InstanceDescriptorFields::InstanceId),
             "InstanceType" => Ok(InstanceDescriptorFields::InstanceType),
+            "Ipv6Addresses" => Ok(InstanceDescriptorFields::Ipv6Addresses),
             "LaunchTime" => Ok(InstanceDescriptorFields::LaunchTime),
             "Monitoring" => Ok(InstanceDescriptorFields::Monitoring),
+            "NetworkInterfaces" => Ok(InstanceDescriptorFields::NetworkInterfaces),
             "Placement" => Ok(InstanceDescriptorFields::Placement),
             "PrivateDnsName" => Ok(InstanceDescriptorFields::PrivateDnsName),
             "PrivateIpAddress" => Ok(InstanceDescriptorFields::PrivateIpAddress),
+            "PrivateIpAddresses" => Ok(InstanceDescriptorFields::PrivateIpAddresses),
+            "Profile" => Ok(InstanceDescriptorFields::Profile),
             "PublicDnsName" => Ok(InstanceDescriptorFields::PublicDnsName),
             "PublicIpAddress" => Ok(InstanceDescriptorFields::PublicIpAddress),
+            "Region" => Ok(InstanceDescriptorFields::Region),
             "RootDeviceName" => Ok(InstanceDescriptorFields::RootDeviceName),
             "RootDeviceType" => Ok(InstanceDescriptorFields::RootDeviceType),
             "SecurityGroups" => Ok(InstanceDescriptorFields::SecurityGroups),
@@ -62,6 +161,7 @@ impl FromStr for InstanceDescriptorFields {
                 Ok(InstanceDescriptorFields::Tags(tags_filter))
             }
             "VirtualizationType" => Ok(InstanceDescriptorFields::VirtualizationType),
+            "Volumes" => Ok(InstanceDescriptorFields::Volumes),
             "VpcId" => Ok(InstanceDescriptorFields::VpcId),
             _ => {
                 Err(Error::from_kind(ErrorKind::InstanceDescriptorFieldsParsingFailed(
@@ -82,12 +182,16 @@ fn extract_tags_filter(tags_str: &str) -> Option<Vec<String>> {
     Some(tags_filter)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct InstanceDescriptor {
+    pub account:                  Option<String>,
     pub ami_launch_index:         Option<i64>,
     pub architecture:             Option<String>,
     pub block_device_mappings:    Option<Vec<String>>,
     pub client_token:             Option<String>,
+    /// Estimated cost in USD per hour; cf. `pricing::PriceTable`
+    #[serde(default)]
+    pub cost:                     Option<f64>,
     pub ebs_optimized:            Option<bool>,
     // Won't convert this
     // pub elastic_gpu_associations: Option<Vec<ElasticGpuAssociation>>,
@@ -100,20 +204,20 @@ pub struct InstanceDescriptor {
     pub instance_type:            Option<String>,
     pub kernel_id:                Option<String>,
     pub key_name:                 Option<String>,
-    pub launch_time:              Option<String>,
+    pub launch_time:              Option<DateTime<Utc>>,
     pub monitoring:               Option<String>,
-    // network_interfaces contains a lot of useful information but it's a data structure rabbit
-    // hole, but the most important information is already available in InstanceDescriptor.
-    // pub network_interfaces: Option<Vec<InstanceNetworkInterface>>,
+    pub network_interfaces:       Option<Vec<NetworkInterface>>,
     pub placement:                Option<String>,
     pub platform:                 Option<String>,
     pub private_dns_name:         Option<String>,
     pub private_ip_address:       Option<String>,
+    pub profile:                  Option<String>,
     // Won't convert this
     // pub product_codes: Option<Vec<ProductCode>>,
     pub public_dns_name:          Option<String>,
     pub public_ip_address:        Option<String>,
     pub ramdisk_id:               Option<String>,
+    pub region:                   Option<String>,
     pub root_device_name:         Option<String>,
     pub root_device_type:         Option<String>,
     pub security_groups:          Option<Vec<String>>,
@@ -126,16 +230,180 @@ pub struct InstanceDescriptor {
     pub subnet_id:                Option<String>,
     pub tags:                     Option<HashMap<String, Option<String>>>,
     pub virtualization_type:      Option<String>,
+    pub volumes:                  Option<Vec<Volume>>,
     pub vpc_id:                   Option<String>,
 }
 
+impl InstanceDescriptor {
+    /// Returns the values of `field` as strings or `None` if this instance does not have that field.
+    ///
+    /// List fields like `SecurityGroups` return all their elements; `Tags` returns the tag names.
+    pub fn field_values(&self, field: &InstanceDescriptorFields) -> Option<Vec<String>> {
+        fn single(value: &Option<String>) -> Option<Vec<String>> { value.as_ref().map(|x| vec![x.clone()]) }
+        fn non_empty(values: Vec<String>) -> Option<Vec<String>> {
+            if values.is_empty() {
+                None
+            } else {
+                Some(values)
+            }
+        }
+
+        match *field {
+            InstanceDescriptorFields::Account => single(&self.account),
+            InstanceDescriptorFields::BlockDeviceMappings => self.block_device_mappings.clone(),
+            InstanceDescriptorFields::Cost => self.cost.map(|x| vec![format_cost(x)]),
+            InstanceDescriptorFields::Hypervisor => single(&self.hypervisor),
+            InstanceDescriptorFields::IamInstanceProfile => single(&self.iam_instance_profile),
+            InstanceDescriptorFields::ImageId => single(&self.image_id),
+            InstanceDescriptorFields::InstanceId => single(&self.instance_id),
+            InstanceDescriptorFields::InstanceType => single(&self.instance_type),
+            InstanceDescriptorFields::Ipv6Addresses => self.network_interfaces.as_ref().and_then(|nis| {
+                non_empty(nis.iter().flat_map(|ni| ni.ipv6_addresses.iter().cloned()).collect())
+            }),
+            InstanceDescriptorFields::LaunchTime => self.launch_time.map(|x| vec![format_launch_time(&x)]),
+            InstanceDescriptorFields::Monitoring => single(&self.monitoring),
+            InstanceDescriptorFields::NetworkInterfaces => self
+                .network_interfaces
+                .as_ref()
+                .map(|nis| nis.iter().map(ToString::to_string).collect()),
+            InstanceDescriptorFields::Placement => single(&self.placement),
+            InstanceDescriptorFields::PrivateDnsName => single(&self.private_dns_name),
+            InstanceDescriptorFields::PrivateIpAddress => single(&self.private_ip_address),
+            InstanceDescriptorFields::PrivateIpAddresses => self.network_interfaces.as_ref().and_then(|nis| {
+                non_empty(nis.iter().flat_map(|ni| ni.private_ip_addresses.iter().cloned()).collect())
+            }),
+            InstanceDescriptorFields::Profile => single(&self.profile),
+            InstanceDescriptorFields::PublicDnsName => single(&self.public_dns_name),
+            InstanceDescriptorFields::PublicIpAddress => single(&self.public_ip_address),
+            InstanceDescriptorFields::Region => single(&self.region),
+            InstanceDescriptorFields::RootDeviceName => single(&self.root_device_name),
+            InstanceDescriptorFields::RootDeviceType => single(&self.root_device_type),
+            InstanceDescriptorFields::SecurityGroups => self.security_groups.clone(),
+            InstanceDescriptorFields::State => single(&self.state),
+            InstanceDescriptorFields::StateReason => single(&self.state_reason),
+            InstanceDescriptorFields::Tags(_) => self
+                .tags
+                .as_ref()
+                .filter(|tags| !tags.is_empty())
+                .map(|tags| tags.keys().cloned().collect()),
+            InstanceDescriptorFields::VirtualizationType => single(&self.virtualization_type),
+            InstanceDescriptorFields::Volumes => self
+                .volumes
+                .as_ref()
+                .map(|volumes| volumes.iter().map(ToString::to_string).collect()),
+            InstanceDescriptorFields::VpcId => single(&self.vpc_id),
+        }
+    }
+
+    /// Returns the ids of all security groups attached to this instance or its network interfaces.
+    pub fn security_group_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .security_groups
+            .iter()
+            .flat_map(|sgs| sgs.iter())
+            .filter_map(|sg| {
+                // Security groups are formatted as "id=<id>, name=<name>"
+                sg.split(", ")
+                    .find(|x| x.starts_with("id="))
+                    .map(|x| x[3..].to_owned())
+            })
+            .chain(
+                self.network_interfaces
+                    .iter()
+                    .flat_map(|nis| nis.iter())
+                    .flat_map(|ni| ni.security_groups.iter().cloned()),
+            )
+            .filter(|x| x != EMPTY)
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        ids
+    }
+}
+
+/// `NetworkInterface` describes an elastic network interface attached to an instance.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NetworkInterface {
+    pub network_interface_id: Option<String>,
+    pub subnet_id:            Option<String>,
+    #[serde(default)]
+    pub private_ip_addresses: Vec<String>,
+    #[serde(default)]
+    pub ipv6_addresses:       Vec<String>,
+    /// Ids of the security groups attached to this interface
+    #[serde(default)]
+    pub security_groups:      Vec<String>,
+}
+
+impl fmt::Display for NetworkInterface {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "id={}, subnet={}, ips={}, ipv6={}, sgs={}",
+            self.network_interface_id.as_ref().map(String::as_str).unwrap_or(EMPTY),
+            self.subnet_id.as_ref().map(String::as_str).unwrap_or(EMPTY),
+            join_or_empty(&self.private_ip_addresses),
+            join_or_empty(&self.ipv6_addresses),
+            join_or_empty(&self.security_groups),
+        )
+    }
+}
+
+/// `Volume` describes an EBS volume attached to an instance.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Volume {
+    pub volume_id:   Option<String>,
+    pub device_name: Option<String>,
+    /// Size in GiB
+    pub size:        Option<i64>,
+    pub volume_type: Option<String>,
+    pub iops:        Option<i64>,
+    pub encrypted:   Option<bool>,
+}
+
+impl fmt::Display for Volume {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "id={}, dev={}, size={}, type={}, iops={}, encrypted={}",
+            self.volume_id.as_ref().map(String::as_str).unwrap_or(EMPTY),
+            self.device_name.as_ref().map(String::as_str).unwrap_or(EMPTY),
+            self.size.map(|x| format!("{}GiB", x)).unwrap_or_else(|| EMPTY.to_owned()),
+            self.volume_type.as_ref().map(String::as_str).unwrap_or(EMPTY),
+            self.iops.map(|x| x.to_string()).unwrap_or_else(|| EMPTY.to_owned()),
+            self.encrypted.map(|x| x.to_string()).unwrap_or_else(|| EMPTY.to_owned()),
+        )
+    }
+}
+
+const EMPTY: &str = "-";
+
+fn join_or_empty(values: &[String]) -> String {
+    if values.is_empty() {
+        EMPTY.to_owned()
+    } else {
+        values.join(" ")
+    }
+}
+
+/// Formats a cost in USD with sub-cent precision since hourly prices of small instances are fractions of a cent.
+pub fn format_cost(cost: f64) -> String { format!("{:.4}", cost) }
+
+/// Formats a launch time as RFC 3339 timestamp in UTC like AWS does.
+pub fn format_launch_time(launch_time: &DateTime<Utc>) -> String {
+    launch_time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
 impl Default for InstanceDescriptor {
     fn default() -> Self {
         InstanceDescriptor {
+            account:                  None,
             ami_launch_index:         None,
             architecture:             None,
             block_device_mappings:    None,
             client_token:             None,
+            cost:                     None,
             ebs_optimized:            None,
             ena_support:              None,
             hypervisor:               None,
@@ -148,13 +416,16 @@ impl Default for InstanceDescriptor {
             key_name:                 None,
             launch_time:              None,
             monitoring:               None,
+            network_interfaces:       None,
             placement:                None,
             platform:                 None,
             private_dns_name:         None,
             private_ip_address:       None,
+            profile:                  None,
             public_dns_name:          None,
             public_ip_address:        None,
             ramdisk_id:               None,
+            region:                   None,
             root_device_name:         None,
             root_device_type:         None,
             security_groups:          None,
@@ -167,6 +438,7 @@ impl Default for InstanceDescriptor {
             subnet_id:                None,
             tags:                     None,
             virtualization_type:      None,
+            volumes:                  None,
             vpc_id:                   None,
         }
     }
@@ -174,6 +446,48 @@ impl Default for InstanceDescriptor {
 
 pub type InstanceId = String;
 
+pub trait CreateImage {
+    /// Creates an image of an instance; unless `no_reboot` is set, the instance is rebooted for a consistent
+    /// file system state.
+    fn create_image(
+        &self,
+        dry: bool,
+        instance_id: &str,
+        name: &str,
+        no_reboot: bool,
+        tags: &HashMap<String, String>,
+    ) -> Result<Image>;
+
+    /// Returns the current state of an image, e.g., "pending" or "available".
+    fn image_state(&self, image_id: &str) -> Result<Option<String>>;
+}
+
+pub trait CreateSnapshots {
+    /// Snapshots all EBS volumes attached to an instance.
+    fn create_snapshots(&self, dry: bool, instance_id: &str, tags: &HashMap<String, String>) -> Result<Vec<Snapshot>>;
+
+    /// Returns the current state of a snapshot, e.g., "pending" or "completed".
+    fn snapshot_state(&self, snapshot_id: &str) -> Result<Option<String>>;
+}
+
+pub trait CreateTags {
+    /// Adds or overwrites tags on instances.
+    fn create_tags(&self, dry: bool, instance_ids: &[InstanceId], tags: &HashMap<String, String>) -> Result<()>;
+}
+
+pub trait DeleteTags {
+    fn delete_tags(&self, dry: bool, instance_ids: &[InstanceId], keys: &[String]) -> Result<()>;
+}
+
+pub trait ModifyInstanceType {
+    /// Changes the instance type of a stopped instance.
+    fn modify_instance_type(&self, dry: bool, instance_id: &str, instance_type: &str) -> Result<()>;
+}
+
+pub trait RebootInstances {
+    fn reboot_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> Result<()>;
+}
+
 pub trait StartInstances {
     fn start_instances(&self, dry: bool, instance_ids: &[InstanceId]) -> Result<Vec<StateChange>>;
 }
@@ -193,6 +507,121 @@ pub struct StateChange {
     pub previous_state: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    pub snapshot_id: String,
+    pub instance_id: InstanceId,
+    pub volume_id:   String,
+    pub device_name: Option<String>,
+    pub state:       Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Image {
+    pub image_id:    String,
+    pub instance_id: InstanceId,
+    pub name:        String,
+    pub state:       Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageDescriptor {
+    pub account:       Option<String>,
+    pub creation_date: Option<DateTime<Utc>>,
+    pub image_id:      String,
+    pub name:          Option<String>,
+    pub region:        Option<String>,
+    pub state:         Option<String>,
+    pub tags:          Option<HashMap<String, Option<String>>>,
+}
+
+/// `SecurityGroup` describes a security group and its ingress and egress rules.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityGroup {
+    pub account:     Option<String>,
+    pub region:      Option<String>,
+    pub group_id:    String,
+    pub group_name:  Option<String>,
+    pub description: Option<String>,
+    pub vpc_id:      Option<String>,
+    pub ingress:     Vec<SecurityGroupRule>,
+    pub egress:      Vec<SecurityGroupRule>,
+}
+
+/// `SecurityGroupRule` allows traffic of a protocol and port range from or to its sources.
+///
+/// Sources are IPv4 and IPv6 CIDR blocks, ids of security groups, and ids of prefix lists. A missing port range
+/// means all ports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SecurityGroupRule {
+    pub protocol:  String,
+    pub from_port: Option<i64>,
+    pub to_port:   Option<i64>,
+    pub sources:   Vec<String>,
+}
+
+impl SecurityGroupRule {
+    /// AWS denotes all protocols as "-1".
+    pub fn is_all_protocols(&self) -> bool { self.protocol == "-1" }
+
+    /// Returns true if traffic is allowed from or to anywhere.
+    pub fn is_open_to_world(&self) -> bool {
+        self.sources.iter().any(|x| x == "0.0.0.0/0" || x == "::/0")
+    }
+}
+
+impl fmt::Display for SecurityGroupRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let protocol = if self.is_all_protocols() {
+            "all"
+        } else {
+            self.protocol.as_str()
+        };
+        let ports = match (self.from_port, self.to_port) {
+            (Some(from), Some(to)) if from == to => from.to_string(),
+            (Some(from), Some(to)) => format!("{}-{}", from, to),
+            _ => "all".to_owned(),
+        };
+        write!(f, "{} {} {}", protocol, ports, join_or_empty(&self.sources))
+    }
+}
+
+/// Tag of snapshots and images containing the id of the instance they have been created from.
+pub const SOURCE_INSTANCE_ID_TAG: &str = "SourceInstanceId";
+/// Tag of snapshots and images containing their creation time.
+pub const CREATED_AT_TAG: &str = "CreatedAt";
+
+/// Returns the tags for snapshots and images created from `instance_id` at `created_at`.
+pub fn backup_tags(instance_id: &str, created_at: &DateTime<Utc>) -> HashMap<String, String> {
+    let mut tags = HashMap::new();
+    tags.insert(SOURCE_INSTANCE_ID_TAG.to_owned(), instance_id.to_owned());
+    tags.insert(CREATED_AT_TAG.to_owned(), created_at.to_rfc3339_opts(SecondsFormat::Secs, true));
+
+    tags
+}
+
+/// `TagChange` describes a change of a tag's value; `None` denotes an absent tag.
+#[derive(Debug, Serialize)]
+pub struct TagChange {
+    pub instance_id:    InstanceId,
+    pub key:            String,
+    pub previous_value: Option<String>,
+    pub value:          Option<String>,
+}
+
+fn create_dry_run_results(instance_ids: &[InstanceId]) -> Vec<StateChange> {
+    instance_ids
+        .iter()
+        .map(|i| {
+            StateChange {
+                instance_id:    i.to_owned(),
+                previous_state: "- n/a -".to_owned(),
+                current_state:  "- n/a -".to_owned(),
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
 error_chain! {
     errors {
         ProviderCallFailed(call: String) {
@@ -241,448 +670,3 @@ mod tests {
             .is_equal_to(vec!["Name".to_owned(), "SomeOtherTag".to_owned()]);
     }
 }
-
-pub mod filter {
-    use regex::Regex;
-
-    use provider::{InstanceDescriptor, InstanceDescriptorFields};
-    use std::{collections::HashMap, str::FromStr};
-
-    macro_rules! filter_builder {
-        ($($field:tt),+) => {
-            pub struct FilterBuilder<'a> {
-                $($field: Option<&'a str>),*,
-                block_device_mappings: Option<&'a str>,
-                security_groups: Option<&'a str>,
-                tags: Option<HashMap<String, Option<&'a str>>>
-            }
-
-            impl < 'a > FilterBuilder < 'a > {
-                pub fn new() -> Self {
-                    FilterBuilder {
-                        $($field: None),*,
-                        block_device_mappings: None,
-                        security_groups: None,
-                        tags: None
-                    }
-                }
-
-                $(
-                pub fn $field(mut self, $field: &'a str) -> Self {
-                    self.$field = Some($field);
-                    self
-                }
-                )*
-
-                pub fn block_device_mappings(mut self, block_device_mappings: &'a str) -> Self {
-                    self.block_device_mappings = Some(block_device_mappings);
-                    self
-                }
-
-                pub fn security_groups(mut self, security_groups: &'a str) -> Self {
-                    self.security_groups = Some(security_groups);
-                    self
-                }
-
-                pub fn tags(mut self, tags: HashMap<String, Option<&'a str>>) -> Self {
-                    self.tags = Some(tags);
-                    self
-                }
-
-                pub fn build(self) -> Result<Filter> {
-                    let filter = Filter {
-                        $(
-                        $field: if let Some(re) = self.$field {
-                            Some(Regex::new(re)
-                                .chain_err(|| ErrorKind::FilterRegexError(re.to_owned(), "$field".to_owned()))?)
-                        } else {
-                            None
-                        }
-                        ),*,
-
-                        block_device_mappings: if let Some(re) = self.block_device_mappings {
-                            Some(Regex::new(re)
-                                .chain_err(|| ErrorKind::FilterRegexError(re.to_owned(), "block_device_mappings".to_owned()))?)
-                        } else {
-                            None
-                        },
-
-                        security_groups: if let Some(re) = self.security_groups {
-                            Some(Regex::new(re)
-                                .chain_err(|| ErrorKind::FilterRegexError(re.to_owned(), "security_groups".to_owned()))?)
-                        } else {
-                            None
-                        },
-
-                        tags: if let Some(tags) = self.tags {
-                            let h = tags.into_iter().map(|(k, v)| (
-                                k,
-                                if let Some(v) = v { Regex::new(v).ok() } else { None }
-                            )).collect();
-                            Some(h)
-                        } else {
-                            None
-                        },
-                    };
-
-                    Ok(filter)
-                }
-            }
-
-            pub struct Filter {
-                $($field: Option<Regex>),*,
-                security_groups: Option<Regex>,
-                block_device_mappings: Option<Regex>,
-                tags: Option<HashMap<String, Option<Regex>>>
-            }
-
-           impl Filter {
-                pub fn filter(&self, instance: &InstanceDescriptor) -> bool {
-                    $(
-                    if let Some(ref re) = self.$field {
-                        if !re.is_match(instance.$field.as_ref().unwrap()) { return false };
-                    }
-                    )*
-
-                    if let Some(ref re) = self.block_device_mappings {
-                        for s in instance.block_device_mappings.as_ref().unwrap() {
-                            if !re.is_match(s) { return false };
-                        }
-                    }
-
-                    if let Some(ref re) = self.security_groups {
-                        for s in instance.security_groups.as_ref().unwrap() {
-                            if !re.is_match(s) { return false };
-                        }
-                    }
-
-                    match (&self.tags, &instance.tags) {
-                        (&Some(ref filter_tags), &Some(ref instance_tags)) => {
-                            for tag in filter_tags.keys() {
-                                if !instance_tags.contains_key(tag) { return false };
-                                // We now know that the instance has the the desired tag
-                                match (&filter_tags[tag], &instance_tags[tag]) {
-                                    (&Some(ref re), &Some(ref value)) if !re.is_match(value)  => return false,
-                                    (&Some(_), &None) => return false,
-                                    _ => {}
-                                }
-                            }
-                        },
-                        (&Some(_), &None) => return false,
-                        _ => {},
-
-                    };
-
-                    true
-                }
-            }
-        }
-    }
-
-    filter_builder!(
-        hypervisor,
-        iam_instance_profile,
-        image_id,
-        instance_id,
-        instance_type,
-        monitoring,
-        placement,
-        private_dns_name,
-        private_ip_address,
-        public_dns_name,
-        public_ip_address,
-        root_device_name,
-        root_device_type,
-        state,
-        state_reason,
-        virtualization_type,
-        vpc_id
-    );
-
-    impl FromStr for Filter {
-        type Err = Error;
-
-        fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
-            let tags = s.split(',');
-            let kvs: Result<Vec<(&str, Option<&str>)>> = tags
-                .map(|tag| {
-                    let mut splits: Vec<_> = tag.splitn(2, '=').collect();
-                    match splits.len() {
-                        2 => Ok((splits.remove(0), Some(splits.remove(0)))),
-                        1 => Ok((splits.remove(0), None)),
-                        _ => {
-                            Err(Error::from_kind(ErrorKind::FilterParsingFailed(
-                                s.to_owned(),
-                                "splitting fields failed".to_owned(),
-                            )))
-                        }
-                    }
-                })
-                .collect();
-            let kvs = kvs?;
-
-            let mut f_builder = FilterBuilder::new();
-            for (key, value) in kvs {
-                if let Some(v) = value {
-                    match key.parse::<InstanceDescriptorFields>().chain_err(|| {
-                        Error::from_kind(ErrorKind::FilterParsingFailed(
-                            s.to_owned(),
-                            "parsing instance descriptor field failed".to_owned(),
-                        ))
-                    })? {
-                        InstanceDescriptorFields::BlockDeviceMappings => f_builder = f_builder.block_device_mappings(v),
-                        InstanceDescriptorFields::Hypervisor => f_builder = f_builder.hypervisor(v),
-                        InstanceDescriptorFields::IamInstanceProfile => {
-                            f_builder = f_builder.iam_instance_profile(v);
-                        }
-                        InstanceDescriptorFields::ImageId => {
-                            f_builder = f_builder.image_id(v);
-                        }
-                        InstanceDescriptorFields::InstanceId => {
-                            f_builder = f_builder.instance_id(v);
-                        }
-                        InstanceDescriptorFields::InstanceType => {
-                            f_builder = f_builder.instance_type(v);
-                        }
-                        InstanceDescriptorFields::LaunchTime => {
-                            // A string based time matcher does not make sense
-                        }
-                        InstanceDescriptorFields::Monitoring => {
-                            f_builder = f_builder.monitoring(v);
-                        }
-                        InstanceDescriptorFields::Placement => {
-                            f_builder = f_builder.placement(v);
-                        }
-                        InstanceDescriptorFields::PrivateDnsName => {
-                            f_builder = f_builder.private_dns_name(v);
-                        }
-                        InstanceDescriptorFields::PrivateIpAddress => {
-                            f_builder = f_builder.private_ip_address(v);
-                        }
-                        InstanceDescriptorFields::PublicDnsName => {
-                            f_builder = f_builder.public_dns_name(v);
-                        }
-                        InstanceDescriptorFields::PublicIpAddress => {
-                            f_builder = f_builder.public_ip_address(v);
-                        }
-                        InstanceDescriptorFields::RootDeviceName => {
-                            f_builder = f_builder.root_device_name(v);
-                        }
-                        InstanceDescriptorFields::RootDeviceType => {
-                            f_builder = f_builder.root_device_type(v);
-                        }
-                        InstanceDescriptorFields::SecurityGroups => {
-                            f_builder = f_builder.security_groups(v);
-                        }
-                        InstanceDescriptorFields::State => {
-                            f_builder = f_builder.state(v);
-                        }
-                        InstanceDescriptorFields::StateReason => {
-                            f_builder = f_builder.state_reason(v);
-                        }
-                        InstanceDescriptorFields::Tags(_) => {
-                            f_builder = f_builder.tags(parse_tags_filter_to_hash(v)?);
-                        }
-                        InstanceDescriptorFields::VirtualizationType => {
-                            f_builder = f_builder.virtualization_type(v);
-                        }
-                        InstanceDescriptorFields::VpcId => {
-                            f_builder = f_builder.vpc_id(v);
-                        }
-                    }
-                }
-            }
-
-            f_builder.build().chain_err(|| {
-                Error::from_kind(ErrorKind::FilterParsingFailed(
-                    s.to_owned(),
-                    "building filter failed".to_owned(),
-                ))
-            })
-        }
-    }
-
-    fn parse_tags_filter_to_hash(tags_filter: &str) -> Result<HashMap<String, Option<&str>>> {
-        let mut hm = HashMap::new();
-        for tag in tags_filter.split(':') {
-            let mut kv: Vec<_> = tag.split('=').collect();
-            match kv.len() {
-                2 => hm.insert(kv.remove(0).to_owned(), Some(kv.remove(0))),
-                1 => hm.insert(kv.remove(0).to_owned(), None),
-                _ => {
-                    return Err(Error::from_kind(ErrorKind::FilterParsingFailed(
-                        tags_filter.to_owned(),
-                        "splitting fields failed".to_owned(),
-                    )))
-                }
-            };
-        }
-
-        Ok(hm)
-    }
-
-    error_chain! {
-        errors {
-            FilterRegexError(re: String, field: String) {
-                description("Failed to build reg exp.")
-                display("Failed to build reg exp '{}' for field '{}'", re, field)
-            }
-            FilterParsingFailed(s: String, reason: String) {
-                description("Failed to parse Filter from String.")
-                display("Failed to parse Filter from String '{}' becaue {}", s, reason)
-            }
-        }
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-
-        use spectral::prelude::*;
-        use std::collections::HashMap;
-
-        #[test]
-        fn parse_filter_no_tags_okay() {
-            let filter_arg = "InstanceId=i-.*,State=stopped";
-            let _ = filter_arg.parse::<Filter>().unwrap();
-        }
-
-        #[test]
-        fn parse_filter_with_tags_okay() {
-            let filter_arg = "InstanceId=i-.*,Tags=Name:AnsibleHostGroup=batch_.*,State=stopped";
-            let _ = filter_arg.parse::<Filter>().unwrap();
-        }
-
-        #[test]
-        fn parse_tags_filter_to_hash_okay() {
-            let tags_filter = "Name:AnsibleHostGroup=batch_.*";
-
-            let hm = parse_tags_filter_to_hash(tags_filter);
-
-            assert_that(&hm).is_ok().contains_entry("Name".to_owned(), None);
-            assert_that(&hm)
-                .is_ok()
-                .contains_entry("AnsibleHostGroup".to_owned(), Some("batch_.*"));
-        }
-
-        #[test]
-        fn filter_instance_with_invalid_re() {
-            let filter = FilterBuilder::new().instance_id("\\i-.*").build();
-
-            assert!(&filter.is_err())
-        }
-
-        fn create_instance() -> InstanceDescriptor {
-            let mut tags = HashMap::new();
-            tags.insert("Name".to_owned(), Some("my_instance".to_owned()));
-            tags.insert("Intent".to_owned(), Some("my_project".to_owned()));
-
-            InstanceDescriptor {
-                instance_id: Some("i-12345".to_owned()),
-                image_id: Some("ami-12345".to_owned()),
-                tags: Some(tags),
-                ..Default::default()
-            }
-        }
-
-        #[test]
-        fn filter_instance_with_empty_filter() {
-            let instance = create_instance();
-            let filter = FilterBuilder::new().build().expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_true()
-        }
-
-        #[test]
-        fn filter_instance_with_instance_id_okay() {
-            let instance = create_instance();
-            let filter = FilterBuilder::new()
-                .instance_id("i-.*")
-                .build()
-                .expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_true()
-        }
-
-        #[test]
-        fn filter_instance_with_instance_id_fail() {
-            let instance = create_instance();
-            let filter = FilterBuilder::new()
-                .instance_id("instance-.*")
-                .build()
-                .expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_false()
-        }
-
-        #[test]
-        fn filter_instance_with_instance_id_and_image_id_okay() {
-            let instance = create_instance();
-            let filter = FilterBuilder::new()
-                .instance_id("i-.*")
-                .image_id("ami-.*")
-                .build()
-                .expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_true()
-        }
-
-        #[test]
-        fn filter_instance_with_instance_id_and_image_id_fail() {
-            let instance = create_instance();
-            let filter = FilterBuilder::new()
-                .instance_id("i-.*")
-                .image_id("image-.*")
-                .build()
-                .expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_false()
-        }
-
-        #[test]
-        fn filter_instance_with_tags_okay() {
-            let instance = create_instance();
-
-            let mut tags = HashMap::new();
-            tags.insert("Name".to_owned(), Some("my_.*"));
-            tags.insert("Intent".to_owned(), Some("my_.*"));
-            let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_true()
-        }
-
-        #[test]
-        fn filter_instance_with_tag_without_value_okay() {
-            let instance = create_instance();
-
-            let mut tags = HashMap::new();
-            tags.insert("Name".to_owned(), None);
-            let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_true()
-        }
-
-        #[test]
-        fn filter_instance_with_missing_tag() {
-            let instance = create_instance();
-
-            let mut tags = HashMap::new();
-            tags.insert("NoSuchTagName".to_owned(), Some("my_.*"));
-            let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_false()
-        }
-
-        #[test]
-        fn filter_instance_with_tags_fail() {
-            let instance = create_instance();
-
-            let mut tags = HashMap::new();
-            tags.insert("Name".to_owned(), Some("my_.*"));
-            tags.insert("Intent".to_owned(), Some("not_my_.*"));
-            let filter = FilterBuilder::new().tags(tags).build().expect("Failed to build filter");
-
-            assert_that(&filter.filter(&instance)).is_false()
-        }
-    }
-}
@@ -0,0 +1,200 @@
+//! Estimates the cost of instances from a price table.
+//!
+//! The price table contains on-demand prices per hour by region and instance type. A default table is bundled
+//! with ceres; `price_table` in the configuration file points to a custom table. Stopped instances do not cost
+//! anything -- attached volumes are not taken into account.
+
+use std::{collections::HashMap, fs, path::Path};
+use toml;
+
+use provider::InstanceDescriptor;
+
+/// Estimated hours per month, i.e., 365 days * 24 hours / 12 months
+pub const HOURS_PER_MONTH: f64 = 730.0;
+
+const BUNDLED_PRICE_TABLE: &str = include_str!("../../includes/instance_prices.toml");
+
+#[derive(Debug, Deserialize)]
+pub struct PriceTable {
+    /// Factor to estimate spot prices from on-demand prices
+    pub spot_factor: f64,
+    /// On-demand prices in USD per hour by region and instance type
+    pub prices:      HashMap<String, HashMap<String, f64>>,
+}
+
+impl PriceTable {
+    /// Loads the price table from `path` or the bundled price table if no path is given.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        match path {
+            Some(path) => PriceTable::from_file(path),
+            None => PriceTable::bundled(),
+        }
+    }
+
+    pub fn bundled() -> Result<Self> {
+        toml::from_str(BUNDLED_PRICE_TABLE).chain_err(|| ErrorKind::PriceTableError("bundled".to_owned()))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_string_lossy().to_string();
+        let content = fs::read_to_string(&path).chain_err(|| ErrorKind::PriceTableError(path.clone()))?;
+
+        toml::from_str(&content).chain_err(|| ErrorKind::PriceTableError(path))
+    }
+
+    /// Returns the estimated cost per hour of an instance or `None` if its price is unknown.
+    pub fn hourly_cost(&self, instance: &InstanceDescriptor) -> Option<f64> {
+        match instance.state.as_ref().map(String::as_str) {
+            Some("stopped") | Some("terminated") => return Some(0.0),
+            _ => {}
+        }
+
+        let region = instance.region.as_ref()?;
+        let instance_type = instance.instance_type.as_ref()?;
+        let price = self.prices.get(region)?.get(instance_type)?;
+
+        if instance.instance_lifecycle.as_ref().map(|x| x == "spot").unwrap_or(false) {
+            Some(price * self.spot_factor)
+        } else {
+            Some(*price)
+        }
+    }
+}
+
+/// Cost of a group of instances; instances with unknown price are counted but do not contribute to the cost.
+#[derive(Debug, Serialize)]
+pub struct CostSummary {
+    pub group:              Option<String>,
+    pub instances:          usize,
+    pub unpriced_instances: usize,
+    pub hourly_cost:        f64,
+    pub monthly_cost:       f64,
+}
+
+/// Sums up the costs of instances, which must have been set before, grouped by the value of the tag `group_by`.
+///
+/// Groups are sorted by name; instances without that tag are summarized in a last group without name.
+/// Terminated instances are ignored.
+pub fn summarize_costs(instances: &[InstanceDescriptor], group_by: Option<&str>) -> Vec<CostSummary> {
+    let mut groups: HashMap<Option<String>, Vec<&InstanceDescriptor>> = HashMap::new();
+    for instance in instances
+        .iter()
+        .filter(|x| x.state.as_ref().map(|s| s != "terminated").unwrap_or(true))
+    {
+        let group = group_by.and_then(|key| {
+            instance
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.get(key))
+                .map(|value| value.clone().unwrap_or_else(String::new))
+        });
+        groups.entry(group).or_insert_with(Vec::new).push(instance);
+    }
+
+    let mut summaries: Vec<_> = groups
+        .into_iter()
+        .map(|(group, instances)| {
+            let hourly_cost: f64 = instances.iter().filter_map(|x| x.cost).sum();
+            CostSummary {
+                group,
+                instances: instances.len(),
+                unpriced_instances: instances.iter().filter(|x| x.cost.is_none()).count(),
+                hourly_cost,
+                monthly_cost: hourly_cost * HOURS_PER_MONTH,
+            }
+        })
+        .collect();
+    // `None` sorts first, but instances without group shall come last
+    summaries.sort_by(|a, b| (a.group.is_none(), &a.group).cmp(&(b.group.is_none(), &b.group)));
+
+    summaries
+}
+
+error_chain! {
+    errors {
+        PriceTableError(path: String) {
+            description("Failed to load price table.")
+            display("Failed to load price table '{}'.", path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    fn instance(
+        instance_type: &str,
+        state: &str,
+        lifecycle: Option<&str>,
+        project: Option<&str>,
+    ) -> InstanceDescriptor {
+        let mut tags = HashMap::new();
+        if let Some(project) = project {
+            tags.insert("Project".to_owned(), Some(project.to_owned()));
+        }
+
+        InstanceDescriptor {
+            instance_lifecycle: lifecycle.map(ToOwned::to_owned),
+            instance_type: Some(instance_type.to_owned()),
+            region: Some("eu-central-1".to_owned()),
+            state: Some(state.to_owned()),
+            tags: Some(tags),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bundled_price_table_is_valid() {
+        let price_table = PriceTable::bundled();
+
+        assert_that(&price_table).is_ok();
+    }
+
+    #[test]
+    fn hourly_cost_of_spot_stopped_and_unknown_instances() {
+        let price_table = PriceTable::bundled().unwrap();
+
+        let on_demand = price_table.hourly_cost(&instance("m5.large", "running", None, None));
+        let spot = price_table.hourly_cost(&instance("m5.large", "running", Some("spot"), None));
+        let stopped = price_table.hourly_cost(&instance("m5.large", "stopped", None, None));
+        let unknown = price_table.hourly_cost(&instance("x1.unknown", "running", None, None));
+
+        assert_that(&on_demand).is_equal_to(Some(0.115));
+        assert_that(&spot.map(|x| (x * 10000.0).round())).is_equal_to(Some(345.0));
+        assert_that(&stopped).is_equal_to(Some(0.0));
+        assert_that(&unknown).is_none();
+    }
+
+    #[test]
+    fn summarize_costs_by_tag() {
+        let price_table = PriceTable::bundled().unwrap();
+        let mut instances = vec![
+            instance("m5.large", "running", None, Some("logimon")),
+            instance("m5.xlarge", "running", None, Some("logimon")),
+            instance("x1.unknown", "running", None, Some("logimon")),
+            instance("t3.micro", "running", None, None),
+            instance("t3.micro", "running", None, Some("centerdevice")),
+            instance("t3.micro", "terminated", None, Some("centerdevice")),
+        ];
+        for instance in &mut instances {
+            instance.cost = price_table.hourly_cost(instance);
+        }
+
+        let summaries = summarize_costs(&instances, Some("Project"));
+
+        let groups: Vec<_> = summaries
+            .iter()
+            .map(|x| (x.group.clone(), x.instances, x.unpriced_instances))
+            .collect();
+        assert_that(&groups).is_equal_to(vec![
+            (Some("centerdevice".to_owned()), 1, 0),
+            (Some("logimon".to_owned()), 3, 1),
+            (None, 1, 0),
+        ]);
+        assert_that(&summaries[1].hourly_cost).is_close_to(0.345, 0.00001);
+        assert_that(&summaries[1].monthly_cost).is_close_to(251.85, 0.00001);
+    }
+}
@@ -0,0 +1,269 @@
+//! Selection of instances by id, Name tag, or tag.
+//!
+//! Commands operating on instances accept instance selectors instead of plain instance ids:
+//!
+//! * `name:<glob>` selects instances whose Name tag matches the glob; `*` matches any characters, `?` a single one.
+//! * `tag:<key>=<value>` selects instances bearing the tag `key` with value `value`.
+//! * Anything else is an instance id.
+//!
+//! Name and tag selectors never select terminated instances. Selectors may be combined with a `Filter` which all
+//! selected instances must match.
+
+use regex::{self, Regex};
+use std::{fmt, str::FromStr};
+
+use provider::{filter::Filter, DescribeInstances, InstanceDescriptor, InstanceId};
+
+#[derive(Debug)]
+pub enum InstanceSelector {
+    Id(InstanceId),
+    Name { glob: String, regex: Regex },
+    Tag { key: String, value: String },
+}
+
+impl InstanceSelector {
+    pub fn matches(&self, instance: &InstanceDescriptor) -> bool {
+        match *self {
+            InstanceSelector::Id(ref id) => instance.instance_id.as_ref() == Some(id),
+            InstanceSelector::Name { ref regex, .. } => {
+                !is_terminated(instance) && tag_value(instance, "Name").map(|x| regex.is_match(x)).unwrap_or(false)
+            }
+            InstanceSelector::Tag { ref key, ref value } => {
+                !is_terminated(instance) && tag_value(instance, key) == Some(value)
+            }
+        }
+    }
+
+    pub fn is_id(&self) -> bool {
+        match *self {
+            InstanceSelector::Id(_) => true,
+            _ => false,
+        }
+    }
+}
+
+fn is_terminated(instance: &InstanceDescriptor) -> bool {
+    instance.state.as_ref().map(String::as_str) == Some("terminated")
+}
+
+fn tag_value<'a>(instance: &'a InstanceDescriptor, key: &str) -> Option<&'a String> {
+    instance.tags.as_ref().and_then(|tags| tags.get(key)).and_then(|value| value.as_ref())
+}
+
+impl FromStr for InstanceSelector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        if s.starts_with("name:") {
+            let glob = &s["name:".len()..];
+            let regex = glob_to_regex(glob).chain_err(|| ErrorKind::InvalidSelector(s.to_owned()))?;
+            Ok(InstanceSelector::Name {
+                glob: glob.to_owned(),
+                regex,
+            })
+        } else if s.starts_with("tag:") {
+            let mut parts = s["tag:".len()..].splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) if !key.is_empty() => Ok(InstanceSelector::Tag {
+                    key:   key.to_owned(),
+                    value: value.to_owned(),
+                }),
+                _ => Err(Error::from_kind(ErrorKind::InvalidSelector(s.to_owned()))),
+            }
+        } else {
+            Ok(InstanceSelector::Id(s.to_owned()))
+        }
+    }
+}
+
+impl fmt::Display for InstanceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InstanceSelector::Id(ref id) => write!(f, "{}", id),
+            InstanceSelector::Name { ref glob, .. } => write!(f, "name:{}", glob),
+            InstanceSelector::Tag { ref key, ref value } => write!(f, "tag:{}={}", key, value),
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> ::std::result::Result<Regex, regex::Error> {
+    let mut re = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+
+    Regex::new(&re)
+}
+
+/// Selects the instances matching any of `selectors` and `filter` in the order of the selectors.
+///
+/// Without selectors, all instances matching `filter` are selected. Fails if a selector or the filter selects no
+/// instance or if there are neither selectors nor a filter, e.g., because `-` read an empty list from stdin.
+pub fn select_instances<'a>(
+    instances: &'a [InstanceDescriptor],
+    selectors: &[InstanceSelector],
+    filter: Option<&Filter>,
+) -> Result<Vec<&'a InstanceDescriptor>> {
+    let matches_filter = |instance: &InstanceDescriptor| filter.map(|f| f.filter(instance)).unwrap_or(true);
+
+    if selectors.is_empty() && filter.is_none() {
+        return Err(Error::from_kind(ErrorKind::NoInstanceSelected("an empty list of selectors".to_owned())));
+    }
+    if selectors.is_empty() {
+        let selected: Vec<_> = instances.iter().filter(|i| matches_filter(i)).collect();
+        if selected.is_empty() {
+            return Err(Error::from_kind(ErrorKind::NoInstanceSelected("filter".to_owned())));
+        }
+        return Ok(selected);
+    }
+
+    let mut selected: Vec<&InstanceDescriptor> = Vec::new();
+    for selector in selectors {
+        let matches: Vec<_> = instances
+            .iter()
+            .filter(|i| selector.matches(i) && matches_filter(i))
+            .collect();
+        if matches.is_empty() {
+            return Err(Error::from_kind(ErrorKind::NoInstanceSelected(selector.to_string())));
+        }
+        for instance in matches {
+            if !selected.iter().any(|x| x.instance_id == instance.instance_id) {
+                selected.push(instance);
+            }
+        }
+    }
+
+    Ok(selected)
+}
+
+/// Resolves `selectors` and `filter` to instance ids using `provider`; cf. `select_instances`.
+///
+/// Plain instance ids without a filter are returned as they are without querying the provider.
+pub fn select_instance_ids<P: DescribeInstances + ?Sized>(
+    provider: &P,
+    selectors: &[InstanceSelector],
+    filter: Option<&Filter>,
+) -> Result<Vec<InstanceId>> {
+    if !selectors.is_empty() && filter.is_none() && selectors.iter().all(InstanceSelector::is_id) {
+        return Ok(selectors.iter().map(|x| x.to_string()).collect());
+    }
+
+    let instances = provider
+        .describe_instances()
+        .chain_err(|| ErrorKind::FailedToDescribeInstances)?;
+    let selected = select_instances(&instances, selectors, filter)?;
+
+    Ok(selected.into_iter().flat_map(|x| x.instance_id.clone()).collect())
+}
+
+error_chain! {
+    errors {
+        InvalidSelector(s: String) {
+            description("Invalid instance selector.")
+            display("Invalid instance selector '{}'; use an instance id, 'name:<glob>', or 'tag:<key>=<value>'.", s)
+        }
+        NoInstanceSelected(selector: String) {
+            description("No instance selected.")
+            display("No instance selected by {}.", selector)
+        }
+        FailedToDescribeInstances {
+            description("Failed to describe instances.")
+            display("Failed to describe instances.")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+    use std::collections::HashMap;
+
+    fn create_instance(id: &str, name: &str, intent: &str, state: &str) -> InstanceDescriptor {
+        let mut tags = HashMap::new();
+        tags.insert("Name".to_owned(), Some(name.to_owned()));
+        tags.insert("Intent".to_owned(), Some(intent.to_owned()));
+
+        InstanceDescriptor {
+            instance_id: Some(id.to_owned()),
+            state: Some(state.to_owned()),
+            tags: Some(tags),
+            ..Default::default()
+        }
+    }
+
+    fn instances() -> Vec<InstanceDescriptor> {
+        vec![
+            create_instance("i-1", "web-01", "webserver", "running"),
+            create_instance("i-2", "web-02", "webserver", "stopped"),
+            create_instance("i-3", "app-01", "appserver", "running"),
+            create_instance("i-4", "web-03", "webserver", "terminated"),
+        ]
+    }
+
+    fn selected_ids(selected: Result<Vec<&InstanceDescriptor>>) -> Vec<String> {
+        selected
+            .unwrap()
+            .into_iter()
+            .flat_map(|x| x.instance_id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn parse_selectors() {
+        assert_that(&"i-1".parse::<InstanceSelector>().map(|x| x.is_id())).is_ok().is_true();
+        assert_that(&"name:web-*".parse::<InstanceSelector>().map(|x| x.to_string()))
+            .is_ok()
+            .is_equal_to("name:web-*".to_owned());
+        assert_that(&"tag:Intent=web".parse::<InstanceSelector>().map(|x| x.to_string()))
+            .is_ok()
+            .is_equal_to("tag:Intent=web".to_owned());
+        assert_that(&"tag:Intent".parse::<InstanceSelector>()).is_err();
+    }
+
+    #[test]
+    fn select_by_name_glob_ignores_terminated_instances() {
+        let instances = instances();
+        let selectors = vec!["name:web-0?".parse().unwrap()];
+
+        let res = select_instances(&instances, &selectors, None);
+
+        assert_that(&selected_ids(res)).is_equal_to(vec!["i-1".to_owned(), "i-2".to_owned()]);
+    }
+
+    #[test]
+    fn select_by_tag_and_filter() {
+        let instances = instances();
+        let selectors = vec!["tag:Intent=webserver".parse().unwrap()];
+        let filter: Filter = "State == running".parse().unwrap();
+
+        let res = select_instances(&instances, &selectors, Some(&filter));
+
+        assert_that(&selected_ids(res)).is_equal_to(vec!["i-1".to_owned()]);
+    }
+
+    #[test]
+    fn select_without_match_fails() {
+        let instances = instances();
+        let selectors = vec!["i-3".parse().unwrap(), "name:db-*".parse().unwrap()];
+
+        let res = select_instances(&instances, &selectors, None);
+
+        assert_that(&res).is_err();
+    }
+
+    #[test]
+    fn select_without_selectors_and_filter_fails() {
+        let instances = instances();
+
+        let res = select_instances(&instances, &[], None);
+
+        assert_that(&res).is_err();
+    }
+}
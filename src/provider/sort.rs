@@ -0,0 +1,197 @@
+//! Sorting of instances by their description fields.
+
+use std::{cmp::Ordering, str::FromStr};
+
+use provider::{InstanceDescriptor, InstanceDescriptorFields};
+
+/// `SortBy` sorts instances by a description field, e.g., `LaunchTime` or `Tags:Name:desc`.
+///
+/// Instances without a value for the field are always sorted last. Numeric values are sorted numerically and before
+/// all other values which are sorted lexicographically.
+#[derive(Debug)]
+pub struct SortBy {
+    field:      InstanceDescriptorFields,
+    descending: bool,
+}
+
+impl SortBy {
+    pub fn field(&self) -> &InstanceDescriptorFields { &self.field }
+
+    pub fn sort(&self, instances: &mut [InstanceDescriptor]) {
+        instances.sort_by(|a, b| match (self.key(a), self.key(b)) {
+            (Some(ref a), Some(ref b)) if self.descending => b.cmp(a),
+            (Some(ref a), Some(ref b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+    }
+
+    fn key(&self, instance: &InstanceDescriptor) -> Option<Key> {
+        let value = match self.field {
+            // Sorts by the value of the first given tag
+            InstanceDescriptorFields::Tags(Some(ref names)) => names
+                .first()
+                .and_then(|name| instance.tags.as_ref().and_then(|tags| tags.get(name)))
+                .and_then(|value| value.clone()),
+            ref field => instance.field_values(field).map(|values| values.join(",")),
+        };
+
+        value.map(Key::new)
+    }
+}
+
+/// Sort key of a field value; all numbers come before all text, so this is a total order.
+///
+/// Launch times are formatted as RFC 3339 timestamps in UTC and thus, compare correctly as text.
+#[derive(Debug, PartialEq)]
+enum Key {
+    /// A finite number; NaN and infinity are text
+    Num(f64),
+    Text(String),
+}
+
+impl Key {
+    fn new(value: String) -> Key {
+        match value.parse::<f64>() {
+            Ok(x) if x.is_finite() => Key::Num(x),
+            _ => Key::Text(value),
+        }
+    }
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Key) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Key) -> Ordering {
+        match (self, other) {
+            // Finite numbers are always comparable
+            (&Key::Num(ref a), &Key::Num(ref b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (&Key::Num(_), &Key::Text(_)) => Ordering::Less,
+            (&Key::Text(_), &Key::Num(_)) => Ordering::Greater,
+            (&Key::Text(ref a), &Key::Text(ref b)) => a.cmp(b),
+        }
+    }
+}
+
+impl FromStr for SortBy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        let (field, descending) = match s.rfind(':') {
+            Some(i) if &s[i + 1..] == "desc" => (&s[..i], true),
+            Some(i) if &s[i + 1..] == "asc" => (&s[..i], false),
+            _ => (s, false),
+        };
+        let field = field
+            .parse::<InstanceDescriptorFields>()
+            .chain_err(|| ErrorKind::SortByParsingFailed(s.to_owned()))?;
+
+        Ok(SortBy { field, descending })
+    }
+}
+
+error_chain! {
+    errors {
+        SortByParsingFailed(s: String) {
+            description("Failed to parse sort order.")
+            display("Failed to parse sort order '{}'.", s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{Duration, TimeZone, Utc};
+    use spectral::prelude::*;
+    use std::collections::HashMap;
+
+    fn create_instance(id: &str, launched_days_ago: Option<i64>, cpus: &str) -> InstanceDescriptor {
+        let mut tags = HashMap::new();
+        tags.insert("Cpus".to_owned(), Some(cpus.to_owned()));
+
+        InstanceDescriptor {
+            instance_id: Some(id.to_owned()),
+            launch_time: launched_days_ago.map(|x| Utc.ymd(2020, 6, 30).and_hms(0, 0, 0) - Duration::days(x)),
+            tags: Some(tags),
+            ..Default::default()
+        }
+    }
+
+    fn instance_ids(instances: &[InstanceDescriptor]) -> Vec<&str> {
+        instances
+            .iter()
+            .map(|x| x.instance_id.as_ref().unwrap().as_str())
+            .collect()
+    }
+
+    #[test]
+    fn sort_by_launch_time_missing_last() {
+        let mut instances = vec![
+            create_instance("i-1", Some(1), "2"),
+            create_instance("i-2", None, "2"),
+            create_instance("i-3", Some(30), "2"),
+        ];
+        let sort_by: SortBy = "LaunchTime".parse().unwrap();
+
+        sort_by.sort(&mut instances);
+
+        assert_that(&instance_ids(&instances)).is_equal_to(vec!["i-3", "i-1", "i-2"]);
+    }
+
+    #[test]
+    fn sort_by_launch_time_descending_missing_last() {
+        let mut instances = vec![
+            create_instance("i-1", Some(1), "2"),
+            create_instance("i-2", None, "2"),
+            create_instance("i-3", Some(30), "2"),
+        ];
+        let sort_by: SortBy = "LaunchTime:desc".parse().unwrap();
+
+        sort_by.sort(&mut instances);
+
+        assert_that(&instance_ids(&instances)).is_equal_to(vec!["i-1", "i-3", "i-2"]);
+    }
+
+    #[test]
+    fn sort_by_tag_numerically() {
+        let mut instances = vec![
+            create_instance("i-1", None, "16"),
+            create_instance("i-2", None, "2"),
+            create_instance("i-3", None, "4"),
+        ];
+        let sort_by: SortBy = "Tags:Cpus".parse().unwrap();
+
+        sort_by.sort(&mut instances);
+
+        assert_that(&instance_ids(&instances)).is_equal_to(vec!["i-2", "i-3", "i-1"]);
+    }
+
+    #[test]
+    fn sort_by_tag_numbers_before_text() {
+        let mut instances = vec![
+            create_instance("i-1", None, "1a"),
+            create_instance("i-2", None, "10"),
+            create_instance("i-3", None, "NaN"),
+            create_instance("i-4", None, "9"),
+            create_instance("i-5", None, "inf"),
+            create_instance("i-6", None, "-1.5"),
+        ];
+        let sort_by: SortBy = "Tags:Cpus".parse().unwrap();
+
+        sort_by.sort(&mut instances);
+
+        assert_that(&instance_ids(&instances)).is_equal_to(vec!["i-6", "i-4", "i-2", "i-1", "i-3", "i-5"]);
+    }
+
+    #[test]
+    fn parse_sort_by_unknown_field_fails() {
+        assert_that(&"NoSuchField:desc".parse::<SortBy>()).is_err();
+    }
+}
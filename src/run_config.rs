@@ -3,6 +3,8 @@
 
 use std::path::Path;
 
+use config::CeresConfig;
+
 /// `RunConfig` contains run time configuration parameters.
 ///
 /// In contrast to `config::Config` it is meant to store parameters for individual executions of
@@ -12,4 +14,34 @@ pub struct RunConfig<'a> {
     pub color:          bool,
     pub active_profile: String,
     pub active_config:  &'a Path,
+    pub all_profiles:   bool,
+    /// Ignores cached instance descriptions
+    pub refresh:        bool,
+    /// Uses cached instance descriptions only
+    pub offline:        bool,
+}
+
+impl<'a> RunConfig<'a> {
+    /// Returns the names of the profiles to run for.
+    ///
+    /// This is the active profile with "default" resolved to the configured default profile, or,
+    /// if `all_profiles` is set, all configured profiles in alphabetical order.
+    pub fn profile_names(&self, config: &CeresConfig) -> Vec<String> {
+        if self.all_profiles {
+            let mut names: Vec<_> = config.profiles.keys().cloned().collect();
+            names.sort();
+            names
+        } else {
+            vec![self.active_profile_name(config).to_owned()]
+        }
+    }
+
+    /// Returns the name of the active profile with "default" resolved to the configured default profile.
+    pub fn active_profile_name<'b>(&'b self, config: &'b CeresConfig) -> &'b str {
+        if self.active_profile == "default" {
+            &config.default_profile
+        } else {
+            &self.active_profile
+        }
+    }
 }
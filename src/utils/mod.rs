@@ -2,38 +2,67 @@ use std::{net::IpAddr, path::PathBuf};
 
 pub mod cli {
     use serde_json;
-    use std::io;
+    use std::io::{self, Write};
 
     #[derive(Debug, Deserialize)]
     struct Instance {
         instance_id: String,
     }
 
-    pub fn read_instance_ids(ids: &[&str]) -> Result<Vec<String>> {
+    pub fn read_instance_ids(ids: &[&str]) -> Result<Vec<String>> { read_instance_ids_from(ids, io::stdin()) }
+
+    /// Reads the instance ids from `stdin` if `ids` is just `-`.
+    pub fn read_instance_ids_from<R: io::Read>(ids: &[&str], stdin: R) -> Result<Vec<String>> {
         let instance_ids: Vec<_> = ids.iter().map(|&s| s.to_string()).collect();
 
         // Let's check if we shall read instance ids from stdin
         if instance_ids.len() == 1 && instance_ids[0] == "-" {
-            read_instance_ids_from_stdin()
+            read_instance_ids_from_stdin(stdin)
         } else {
             Ok(instance_ids)
         }
     }
 
-    fn read_instance_ids_from_stdin() -> Result<Vec<String>> {
-        let instances: Vec<Instance> =
-            serde_json::from_reader(io::stdin()).chain_err(|| ErrorKind::FailedToReadStdin)?;
+    fn read_instance_ids_from_stdin<R: io::Read>(stdin: R) -> Result<Vec<String>> {
+        let instances: Vec<Instance> = serde_json::from_reader(stdin).chain_err(|| ErrorKind::FailedToReadStdin)?;
 
         let instance_ids: Vec<String> = instances.into_iter().map(|i| i.instance_id).collect();
 
         Ok(instance_ids)
     }
 
+    /// Lists `choices` numbered from 1 and asks the user to choose one; returns the index of the chosen element.
+    ///
+    /// This is the fallback of `picker::pick` if there is no terminal.
+    pub fn ask_for_choice<T: AsRef<str>>(question: &str, choices: &[T]) -> Result<usize> {
+        for (i, choice) in choices.iter().enumerate() {
+            println!("{:>3}) {}", i + 1, choice.as_ref());
+        }
+
+        let stdin = io::stdin();
+        loop {
+            print!("{} [1-{}]: ", question, choices.len());
+            io::stdout().flush().chain_err(|| ErrorKind::FailedToReadChoice)?;
+
+            let mut answer = String::new();
+            if stdin.read_line(&mut answer).chain_err(|| ErrorKind::FailedToReadChoice)? == 0 {
+                return Err(Error::from_kind(ErrorKind::FailedToReadChoice));
+            }
+            match answer.trim().parse::<usize>() {
+                Ok(n) if n >= 1 && n <= choices.len() => return Ok(n - 1),
+                _ => println!("Please enter a number between 1 and {}.", choices.len()),
+            }
+        }
+    }
+
     error_chain! {
         errors {
             FailedToReadStdin {
                 description("Failed to read instance ids from stdin")
             }
+            FailedToReadChoice {
+                description("Failed to read choice")
+            }
         }
     }
 }
@@ -44,7 +73,7 @@ pub mod command {
     use std::{fs::File, time::Duration};
     use subprocess::{Exec, ExitStatus as SubprocessExitStatus, Redirection};
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     pub struct Command {
         pub id:      String,
         pub cmd:     String,
@@ -54,14 +83,17 @@ pub mod command {
         pub timeout: Option<Duration>,
     }
 
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Serialize, Deserialize)]
     pub struct CommandResult {
         pub id:          String,
         pub log:         PathBuf,
         pub exit_status: ExitStatus,
+        /// Command which has been run; not part of the output
+        #[serde(skip)]
+        pub command:     Option<Command>,
     }
 
-    #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize)]
+    #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
     pub enum ExitStatus {
         Exited(u32),
         Signaled(u8),
@@ -92,6 +124,7 @@ pub mod command {
     impl Command {
         pub fn run<T: Fn() -> ()>(self, progress: Option<T>) -> Result<CommandResult> {
             debug!("Executing command '{:?}'", self);
+            let command = self.clone();
             let cmd = self.cmd.clone();
             let mut c = if let Some(ref args) = self.args {
                 Exec::cmd(&cmd).args(args)
@@ -123,6 +156,7 @@ pub mod command {
                             id:          self.id,
                             log:         self.log,
                             exit_status: exit_status.into(),
+                            command:     Some(command),
                         });
                     }
                     timeout = Some(count_down);
@@ -136,6 +170,7 @@ pub mod command {
                         id:          self.id,
                         log:         self.log,
                         exit_status: exit_status.into(),
+                        command:     Some(command),
                     });
                 }
             }
@@ -143,11 +178,158 @@ pub mod command {
     }
 }
 
+pub mod picker {
+    use super::*;
+
+    use console::{style, Key, Term};
+    use std::io;
+
+    /// Maximum number of items shown at once
+    const MAX_VISIBLE_ITEMS: usize = 15;
+
+    /// Lets the user pick one of `items` in the terminal; returns the index of the picked item or `None` if the user
+    /// aborted.
+    ///
+    /// Typing filters the items fuzzily, i.e., an item matches if it contains the typed characters in this order
+    /// ignoring case and white space. Arrow keys move the selection, Return picks the selected item, and Escape
+    /// aborts. Fails if there is nothing to pick from.
+    pub fn pick<T: AsRef<str>>(prompt: &str, items: &[T]) -> Result<Option<usize>> {
+        if items.is_empty() {
+            return Err(Error::from_kind(ErrorKind::NothingToPick));
+        }
+        let term = Term::stderr();
+        if !term.is_term() {
+            return Err(Error::from_kind(ErrorKind::NoTerminal));
+        }
+        let (rows, cols) = term.size();
+
+        let mut picker = Picker {
+            term,
+            prompt,
+            items,
+            visible:  MAX_VISIBLE_ITEMS.min((rows as usize).saturating_sub(2)).max(1),
+            width:    (cols as usize).saturating_sub(2).max(10),
+            query:    String::new(),
+            selected: 0,
+            rendered: 0,
+        };
+        picker.term.hide_cursor().chain_err(|| ErrorKind::FailedToPick)?;
+        let picked = picker.run();
+        picker.term.show_cursor().chain_err(|| ErrorKind::FailedToPick)?;
+
+        picked.chain_err(|| ErrorKind::FailedToPick)
+    }
+
+    struct Picker<'a, T: 'a> {
+        term:     Term,
+        prompt:   &'a str,
+        items:    &'a [T],
+        visible:  usize,
+        width:    usize,
+        query:    String,
+        selected: usize,
+        rendered: usize,
+    }
+
+    impl<'a, T: AsRef<str>> Picker<'a, T> {
+        fn run(&mut self) -> io::Result<Option<usize>> {
+            let picked = loop {
+                let matches = fuzzy_filter(&self.query, self.items);
+                self.selected = self.selected.min(matches.len().saturating_sub(1));
+                self.render(&matches)?;
+
+                match self.term.read_key()? {
+                    Key::Enter => {
+                        if let Some(&i) = matches.get(self.selected) {
+                            break Some(i);
+                        }
+                    }
+                    Key::Escape | Key::Char('\u{3}') => break None,
+                    Key::ArrowUp => self.selected = self.selected.saturating_sub(1),
+                    // Moving beyond the last match is corrected before rendering
+                    Key::ArrowDown => self.selected += 1,
+                    Key::Backspace => {
+                        self.query.pop();
+                        self.selected = 0;
+                    }
+                    Key::Char(c) if !c.is_control() => {
+                        self.query.push(c);
+                        self.selected = 0;
+                    }
+                    _ => {}
+                }
+            };
+            self.term.clear_last_lines(self.rendered)?;
+
+            Ok(picked)
+        }
+
+        fn render(&mut self, matches: &[usize]) -> io::Result<()> {
+            self.term.clear_last_lines(self.rendered)?;
+
+            // Scroll as far as necessary to show the selected item
+            let offset = (self.selected + 1).saturating_sub(self.visible);
+            self.term.write_line(&format!(
+                "{} [{}/{}] {}",
+                style(self.prompt).bold(),
+                matches.len(),
+                self.items.len(),
+                self.query
+            ))?;
+            for (pos, &i) in matches.iter().enumerate().skip(offset).take(self.visible) {
+                let line: String = self.items[i].as_ref().chars().take(self.width).collect();
+                if pos == self.selected {
+                    self.term.write_line(&format!("{} {}", style(">").bold(), style(line).reverse()))?;
+                } else {
+                    self.term.write_line(&format!("  {}", line))?;
+                }
+            }
+            self.rendered = 1 + matches.len().saturating_sub(offset).min(self.visible);
+
+            Ok(())
+        }
+    }
+
+    /// Returns the indices of the items containing the characters of `query` in this order ignoring case and white
+    /// space.
+    pub fn fuzzy_filter<T: AsRef<str>>(query: &str, items: &[T]) -> Vec<usize> {
+        let query: Vec<char> = query
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        items
+            .iter()
+            .enumerate()
+            .filter(|&(_, item)| {
+                let mut chars = item.as_ref().chars().flat_map(char::to_lowercase);
+                query.iter().all(|q| chars.any(|c| c == *q))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
 pub mod run {
     use super::*;
 
     use clams::prelude::*;
-    use std::{fs::File, sync::mpsc::channel, thread};
+    use console::{style, Color};
+    use serde_json;
+    use std::{
+        cmp,
+        collections::HashSet,
+        fmt,
+        fs::File,
+        io::{Read, Seek, SeekFrom},
+        path::Path,
+        process,
+        str::FromStr,
+        sync::{mpsc::channel, Mutex},
+        thread,
+        time::Duration,
+    };
 
     use output::{
         instances::{JsonOutputCommandResults, OutputCommandResults, TableOutputCommandResults},
@@ -189,6 +371,95 @@ pub mod run {
             .collect()
     }
 
+    /// Colors of the prefixes of streamed output lines; cf. `run_with_stream`
+    const STREAM_COLORS: &[Color] = &[
+        Color::Cyan,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Red,
+    ];
+
+    /// Runs the commands in parallel and prints their output lines live to stderr, each prefixed by the id of its
+    /// command; stdout is left to the output of the results.
+    ///
+    /// The output is still written to the commands' logs, too.
+    pub fn run_with_stream(commands: Vec<Command>) -> Result<Vec<CommandResult>> {
+        let mut results = Vec::new();
+        let width = commands.iter().map(|c| c.id.chars().count()).max().unwrap_or(0);
+
+        for (i, cmd) in commands.into_iter().enumerate() {
+            let (sender, receiver) = channel();
+            results.push(receiver);
+
+            let color = STREAM_COLORS[i % STREAM_COLORS.len()];
+            let prefix = style(format!("{:width$} |", cmd.id, width = width)).fg(color).to_string();
+            let streamer = Mutex::new(LogStreamer::new(prefix, cmd.log.clone()));
+            let _ = thread::spawn(move || {
+                let res = cmd.run(Some(|| streamer.lock().unwrap().stream(false)));
+                streamer.lock().unwrap().stream(true);
+
+                sender.send(res).unwrap();
+            });
+        }
+
+        results
+            .iter()
+            .map(|r| {
+                r.recv().unwrap()
+                    // TODO: Error should contain the command.
+                    .map_err(|e| Error::with_chain(e, ErrorKind::FailedToRunCommand("<nyi>".to_owned())))
+            })
+            .collect()
+    }
+
+    /// Prints the lines appended to a log with a prefix to stderr
+    struct LogStreamer {
+        prefix:  String,
+        log:     PathBuf,
+        offset:  u64,
+        pending: Vec<u8>,
+    }
+
+    impl LogStreamer {
+        fn new(prefix: String, log: PathBuf) -> LogStreamer {
+            LogStreamer {
+                prefix,
+                log,
+                offset: 0,
+                pending: Vec::new(),
+            }
+        }
+
+        /// Prints the complete lines appended since the last call; `finish` prints an incomplete last line, too.
+        fn stream(&mut self, finish: bool) {
+            if let Ok(mut file) = File::open(&self.log) {
+                let mut appended = Vec::new();
+                if file.seek(SeekFrom::Start(self.offset)).is_ok() {
+                    if let Ok(n) = file.read_to_end(&mut appended) {
+                        self.offset += n as u64;
+                        self.pending.extend(appended);
+                    }
+                }
+            }
+
+            while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.pending.drain(..=pos).collect();
+                self.print(&line[..pos]);
+            }
+            if finish && !self.pending.is_empty() {
+                let line: Vec<u8> = self.pending.drain(..).collect();
+                self.print(&line);
+            }
+        }
+
+        fn print(&self, line: &[u8]) {
+            let line = String::from_utf8_lossy(line);
+            eprintln!("{} {}", self.prefix, line.trim_end_matches('\r'));
+        }
+    }
+
     pub fn run_with_progress(commands: Vec<Command>) -> Result<Vec<CommandResult>> {
         let mut results = Vec::new();
         let m = MultiProgress::new();
@@ -243,6 +514,274 @@ pub mod run {
             .collect()
     }
 
+    /// Number of targets per batch of a rolling run given as count, e.g., `5`, or as percentage of all targets, e.g.,
+    /// `20%`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum BatchSize {
+        Count(usize),
+        Percent(usize),
+    }
+
+    impl BatchSize {
+        /// Returns the number of targets per batch for `total` targets; at least 1.
+        pub fn of(self, total: usize) -> usize {
+            let size = match self {
+                BatchSize::Count(count) => count,
+                BatchSize::Percent(percent) => (total * percent + 99) / 100,
+            };
+            size.max(1)
+        }
+    }
+
+    impl FromStr for BatchSize {
+        type Err = Error;
+
+        fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+            let invalid = || Error::from_kind(ErrorKind::InvalidBatchSize(s.to_owned()));
+            if s.ends_with('%') {
+                match s[..s.len() - 1].parse() {
+                    Ok(percent) if percent > 0 && percent <= 100 => Ok(BatchSize::Percent(percent)),
+                    _ => Err(invalid()),
+                }
+            } else {
+                match s.parse() {
+                    Ok(count) if count > 0 => Ok(BatchSize::Count(count)),
+                    _ => Err(invalid()),
+                }
+            }
+        }
+    }
+
+    /// Settings of a rolling run; cf. `run_in_batches`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Rolling {
+        pub batch_size:   BatchSize,
+        /// Skips the remaining batches once commands failed on more targets
+        pub max_failures: Option<usize>,
+        /// Pauses between batches
+        pub pause:        Option<Duration>,
+    }
+
+    impl Default for Rolling {
+        fn default() -> Self {
+            Rolling {
+                batch_size:   BatchSize::Percent(100),
+                max_failures: None,
+                pause:        None,
+            }
+        }
+    }
+
+    /// Results of a rolling run
+    #[derive(Debug)]
+    pub struct RollingResults<'a, T: 'a> {
+        pub results: Vec<CommandResult>,
+        /// Targets of the batches which have been skipped
+        pub skipped: &'a [T],
+        /// Error of the batch which stopped the run; its targets count as skipped
+        pub error:   Option<Error>,
+    }
+
+    /// Runs `run_batch` for consecutive batches of `targets` one after another.
+    ///
+    /// After each batch but the last, the run pauses and then calls `gate` with the targets and the results of the
+    /// batch just run. The remaining batches are skipped if `gate` fails or commands failed on more targets than
+    /// allowed by `rolling`. If `run_batch` fails, the run stops, too, and still returns the results of the batches
+    /// completed so far.
+    pub fn run_in_batches<'a, T, R, G, E>(
+        targets: &'a [T],
+        rolling: &Rolling,
+        mut run_batch: R,
+        mut gate: G,
+    ) -> RollingResults<'a, T>
+    where
+        R: FnMut(&[T]) -> Result<Vec<CommandResult>>,
+        G: FnMut(&[T], &[CommandResult]) -> ::std::result::Result<(), E>,
+        E: fmt::Display,
+    {
+        let batch_size = rolling.batch_size.of(targets.len());
+        let batches = (targets.len() + batch_size - 1) / batch_size;
+
+        let mut results: Vec<CommandResult> = Vec::new();
+        let mut error = None;
+        let mut done = 0;
+        for (i, batch) in targets.chunks(batch_size).enumerate() {
+            if batches > 1 {
+                info!("Running batch {} of {}.", i + 1, batches);
+            }
+            let batch_results = match run_batch(batch) {
+                Ok(batch_results) => batch_results,
+                Err(e) => {
+                    error!("Batch failed: {}; skipping remaining batches.", e);
+                    error = Some(e);
+                    break;
+                }
+            };
+            let first_of_batch = results.len();
+            results.extend(batch_results);
+            done += batch.len();
+
+            // A target may run several commands, but counts as failed only once
+            let failures: HashSet<&str> = failed_ids(&results).into_iter().collect();
+            if rolling.max_failures.map_or(false, |max| failures.len() > max) {
+                error!("Commands failed on {} targets; skipping remaining batches.", failures.len());
+                break;
+            }
+            if done == targets.len() {
+                break;
+            }
+
+            if let Some(pause) = rolling.pause {
+                info!("Pausing for {} sec.", pause.as_secs());
+                thread::sleep(pause);
+            }
+            if let Err(e) = gate(batch, &results[first_of_batch..]) {
+                error!("Gate failed: {}; skipping remaining batches.", e);
+                break;
+            }
+        }
+
+        RollingResults {
+            results,
+            skipped: &targets[done..],
+            error,
+        }
+    }
+
+    /// Upper bound of the delay before a retry
+    const MAX_RETRY_DELAY_SECS: u64 = 3600;
+
+    /// Retries of failed commands with exponential backoff; cf. `run_with_retries`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Retries {
+        pub count:   usize,
+        /// Delay before the first retry; doubles with every further retry
+        pub backoff: Duration,
+    }
+
+    impl Default for Retries {
+        fn default() -> Self {
+            Retries {
+                count:   0,
+                backoff: Duration::from_secs(5),
+            }
+        }
+    }
+
+    impl Retries {
+        /// Returns the delay before retry `attempt` starting at 1; at most an hour.
+        pub fn delay(&self, attempt: usize) -> Duration {
+            let exponent = cmp::min(attempt.saturating_sub(1), 16) as u32;
+            let max = Duration::from_secs(MAX_RETRY_DELAY_SECS);
+            self.backoff
+                .checked_mul(2u32.pow(exponent))
+                .map_or(max, |delay| cmp::min(delay, max))
+        }
+    }
+
+    /// Runs `commands` by `run` and runs failed commands again up to `retries.count` times.
+    ///
+    /// Before each retry the run backs off for `retries.delay`. Results of retried commands replace their previous
+    /// results and keep their position, so the results always correspond to `commands`. Each retry logs to a log of
+    /// its own with the suffix `.retry-<attempt>`. If a retry fails to run, the results of the previous attempt are
+    /// kept and the remaining retries are skipped.
+    pub fn run_with_retries<F>(commands: Vec<Command>, retries: &Retries, mut run: F) -> Result<Vec<CommandResult>>
+    where
+        F: FnMut(Vec<Command>) -> Result<Vec<CommandResult>>,
+    {
+        let mut results = run(commands)?;
+
+        for attempt in 1..=retries.count {
+            let failed: Vec<usize> = results
+                .iter()
+                .enumerate()
+                .filter(|&(_, r)| !r.exit_status.success() && r.command.is_some())
+                .map(|(i, _)| i)
+                .collect();
+            if failed.is_empty() {
+                break;
+            }
+
+            let delay = retries.delay(attempt);
+            info!(
+                "Retrying {} failed commands in {} sec; attempt {} of {}.",
+                failed.len(),
+                delay.as_secs(),
+                attempt,
+                retries.count
+            );
+            thread::sleep(delay);
+
+            let commands = failed
+                .iter()
+                .flat_map(|&i| results[i].command.clone())
+                .map(|mut command| {
+                    command.log = retry_log(&command.log, attempt);
+                    command
+                })
+                .collect();
+            let retried = match run(commands) {
+                Ok(retried) => retried,
+                Err(e) => {
+                    warn!("Failed to retry commands because {}; skipping remaining retries.", e);
+                    break;
+                }
+            };
+            for (i, result) in failed.into_iter().zip(retried) {
+                results[i] = result;
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn retry_log(log: &Path, attempt: usize) -> PathBuf {
+        let mut retry_log = log.as_os_str().to_owned();
+        retry_log.push(format!(".retry-{}", attempt));
+        PathBuf::from(retry_log)
+    }
+
+    /// Reads command results as output by `JsonOutputCommandResults`.
+    pub fn read_results<R: Read>(reader: R) -> Result<Vec<CommandResult>> {
+        serde_json::from_reader(reader).chain_err(|| ErrorKind::FailedToReadResults)
+    }
+
+    /// Returns the ids of the commands which did not exit successfully.
+    pub fn failed_ids(results: &[CommandResult]) -> Vec<&str> {
+        results
+            .iter()
+            .filter(|r| !r.exit_status.success())
+            .map(|r| r.id.as_str())
+            .collect()
+    }
+
+    /// Runs the local shell command `command` as gate between batches; cf. `run_in_batches`.
+    ///
+    /// The ids of the instances of the batch just run are passed in the environment variable `CERES_INSTANCE_IDS`
+    /// separated by spaces. The gate passes if the command exits successfully.
+    pub fn run_gate_command(command: &str, instance_ids: &[&str]) -> Result<()> {
+        debug!("Running gate command '{}'.", command);
+        let status = process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("CERES_INSTANCE_IDS", instance_ids.join(" "))
+            .status()
+            .chain_err(|| ErrorKind::FailedToRunCommand(command.to_owned()))?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::from_kind(ErrorKind::GateCommandFailed(command.to_owned())))
+        }
+    }
+
+    /// Logs the ids of targets skipped by a rolling run.
+    pub fn output_skipped(skipped: &[&str]) {
+        if !skipped.is_empty() {
+            warn!("Skipped {} instances: {}.", skipped.len(), skipped.join(", "));
+        }
+    }
+
     pub fn output_results(output_type: OutputType, show_all: bool, results: &[CommandResult]) -> Result<()> {
         let mut stdout = ::std::io::stdout();
 
@@ -307,6 +846,34 @@ pub mod ssh {
         Err(Error::with_chain(err, ErrorKind::FailedToExecuteSsh))
     }
 
+    /// Formats a jump host for ssh's `-J` option as `[user@]host[:port]`.
+    pub fn jump_host(host: &str, user: Option<&str>, port: Option<u16>) -> String {
+        let mut jump_host = String::new();
+        if let Some(user) = user {
+            jump_host.push_str(user);
+            jump_host.push('@');
+        }
+        // IPv6 addresses must be bracketed to separate the port
+        if host.contains(':') {
+            jump_host.push_str(&format!("[{}]", host));
+        } else {
+            jump_host.push_str(host);
+        }
+        if let Some(port) = port {
+            jump_host.push_str(&format!(":{}", port));
+        }
+
+        jump_host
+    }
+
+    /// Checks whether `ssh_opts` already route the connection through a jump host or proxy command.
+    pub fn sets_jump_host(ssh_opts: &[&str]) -> bool {
+        ssh_opts.iter().any(|opt| {
+            let lower = opt.to_lowercase();
+            opt.starts_with("-J") || lower.contains("proxyjump") || lower.contains("proxycommand")
+        })
+    }
+
     pub fn build_ssh_command_to_instances(
         instances: &[InstanceDescriptor],
         use_public_ip: bool,
@@ -318,15 +885,7 @@ pub mod ssh {
         let commands: Result<Vec<_>> = instances
             .iter()
             .map(|i| {
-                let ip_addr: IpAddr = if use_public_ip {
-                    i.public_ip_address.as_ref()
-                } else {
-                    i.private_ip_address.as_ref()
-                }
-                    .map(|ip| ip.parse())
-                    // TODO Fix me!
-                    .chain_err(|| ErrorKind::FailedToBuildSshCommand)?
-                    .chain_err(|| ErrorKind::FailedToBuildSshCommand)?;
+                let ip_addr = instance_ip_addr(i, use_public_ip)?;
                 let instance_id = i
                     .instance_id
                     .as_ref()
@@ -347,6 +906,19 @@ pub mod ssh {
         commands
     }
 
+    /// Returns the public or private IP address of `instance`.
+    pub fn instance_ip_addr(instance: &InstanceDescriptor, use_public_ip: bool) -> Result<IpAddr> {
+        if use_public_ip {
+            instance.public_ip_address.as_ref()
+        } else {
+            instance.private_ip_address.as_ref()
+        }
+            .map(|ip| ip.parse())
+            // TODO Fix me!
+            .chain_err(|| ErrorKind::FailedToBuildSshCommand)?
+            .chain_err(|| ErrorKind::FailedToBuildSshCommand)
+    }
+
     pub fn build_ssh_command_to_instance(
         instance_id: &str,
         ip_addr: &IpAddr,
@@ -395,6 +967,391 @@ pub mod ssh {
     }
 }
 
+pub mod copy {
+    use super::*;
+
+    use std::{fs, path::Path, str::FromStr, time::Duration};
+    use tempfile;
+
+    use provider::InstanceDescriptor;
+    use utils::{command::Command, ssh::instance_ip_addr};
+
+    /// A local path or a path on instances as given by `local:<path>` resp. `instance:<path>`; paths without prefix are
+    /// local.
+    #[derive(Debug, PartialEq)]
+    pub enum Location {
+        Local(String),
+        Instance(String),
+    }
+
+    impl FromStr for Location {
+        type Err = Error;
+
+        fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+            let location = if s.starts_with("instance:") {
+                Location::Instance(s["instance:".len()..].to_owned())
+            } else if s.starts_with("local:") {
+                Location::Local(s["local:".len()..].to_owned())
+            } else {
+                Location::Local(s.to_owned())
+            };
+            match location {
+                Location::Local(ref path) | Location::Instance(ref path) if path.is_empty() => {
+                    Err(Error::from_kind(ErrorKind::InvalidCopyLocation(s.to_owned())))
+                }
+                _ => Ok(location),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Tool {
+        Scp,
+        Rsync,
+    }
+
+    /// How to copy files
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct CopyOptions {
+        pub tool:      Tool,
+        /// Copies directories recursively
+        pub recursive: bool,
+    }
+
+    /// A file transfer between the local host and instances
+    #[derive(Debug, PartialEq)]
+    pub enum Transfer {
+        /// Copies a local path to a path on each instance
+        Push { local: String, remote: String },
+        /// Copies a path from each instance to a local path
+        Pull { remote: String, local: String },
+    }
+
+    impl Transfer {
+        /// Creates a transfer from `source` to `destination` of which exactly one has to be on the instances.
+        pub fn new(source: Location, destination: Location) -> Result<Transfer> {
+            match (source, destination) {
+                (Location::Local(local), Location::Instance(remote)) => Ok(Transfer::Push { local, remote }),
+                (Location::Instance(remote), Location::Local(local)) => Ok(Transfer::Pull { remote, local }),
+                _ => Err(Error::from_kind(ErrorKind::InvalidTransfer)),
+            }
+        }
+    }
+
+    /// Builds a copy command for each instance.
+    ///
+    /// When pulling from more than one instance, each instance's files are copied to a sub-directory of the local
+    /// destination named by its instance id; cf. `create_local_dirs`.
+    pub fn build_copy_command_to_instances(
+        instances: &[InstanceDescriptor],
+        use_public_ip: bool,
+        login_name: Option<&String>,
+        ssh_opts: &[&str],
+        transfer: &Transfer,
+        options: CopyOptions,
+        timeout: Duration,
+    ) -> Result<Vec<Command>> {
+        let per_instance_dirs = instances.len() > 1;
+        instances
+            .iter()
+            .map(|i| {
+                let ip_addr = instance_ip_addr(i, use_public_ip)?;
+                let instance_id = i
+                    .instance_id
+                    .as_ref()
+                    .chain_err(|| ErrorKind::FailedToBuildCopyCommand)?;
+                let on_instance = |path: &str| remote_path(&ip_addr, login_name.map(String::as_str), path);
+                let (source, destination) = match *transfer {
+                    Transfer::Push { ref local, remote: ref path } => (local.to_owned(), on_instance(path)),
+                    Transfer::Pull { remote: ref path, ref local } if per_instance_dirs => {
+                        let dir = Path::new(local).join(instance_id);
+                        // The trailing slash makes scp and rsync copy into the directory
+                        (on_instance(path), format!("{}/", dir.to_string_lossy()))
+                    }
+                    Transfer::Pull { remote: ref path, ref local } => (on_instance(path), local.to_owned()),
+                };
+
+                let command = build_copy_command(instance_id, ssh_opts, options, source, destination, timeout)?;
+                trace!("copy command for instance {}: {:#?}", instance_id, command);
+                Ok(command)
+            })
+            .collect()
+    }
+
+    /// Creates the local sub-directories the commands of `build_copy_command_to_instances` copy into when pulling
+    /// from more than one instance; this has to be done right before running the commands.
+    pub fn create_local_dirs(instances: &[InstanceDescriptor], transfer: &Transfer) -> Result<()> {
+        let local = match *transfer {
+            Transfer::Pull { ref local, .. } if instances.len() > 1 => local,
+            _ => return Ok(()),
+        };
+        for instance_id in instances.iter().flat_map(|i| i.instance_id.as_ref()) {
+            let dir = Path::new(local).join(instance_id);
+            fs::create_dir_all(&dir)
+                .chain_err(|| ErrorKind::FailedToCreateLocalDir(dir.to_string_lossy().to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    fn build_copy_command(
+        instance_id: &str,
+        ssh_opts: &[&str],
+        options: CopyOptions,
+        source: String,
+        destination: String,
+        timeout: Duration,
+    ) -> Result<Command> {
+        let (cmd, mut args) = match options.tool {
+            Tool::Scp => {
+                let mut args = vec!["-q".to_owned(), "-p".to_owned()];
+                if options.recursive {
+                    args.push("-r".to_owned());
+                }
+                args.extend(scp_opts(ssh_opts));
+                ("scp", args)
+            }
+            Tool::Rsync => {
+                let archive = if options.recursive { "--archive" } else { "--perms" };
+                let mut ssh = vec!["ssh".to_owned()];
+                ssh.extend(ssh_opts.iter().map(|&s| quote(s)));
+                ("rsync", vec![archive.to_owned(), "--times".to_owned(), "-e".to_owned(), ssh.join(" ")])
+            }
+        };
+        args.push(source);
+        args.push(destination);
+
+        let log_path = tempfile::NamedTempFile::new()
+            .chain_err(|| ErrorKind::FailedToBuildCopyCommand)?
+            .path()
+            .to_path_buf();
+        Ok(Command {
+            id:      instance_id.to_owned(),
+            cmd:     cmd.to_owned(),
+            args:    Some(args),
+            cwd:     None,
+            log:     log_path,
+            timeout: Some(timeout),
+        })
+    }
+
+    /// Translates ssh options to scp: scp sets the port by `-P` and the login name by `-o User=<login>`, because its
+    /// `-p` preserves modification times and its `-l` limits the bandwidth. All other options are passed unchanged.
+    fn scp_opts(ssh_opts: &[&str]) -> Vec<String> {
+        let mut opts = Vec::new();
+        let mut iter = ssh_opts.iter();
+        while let Some(&opt) = iter.next() {
+            match opt {
+                "-p" => {
+                    opts.push("-P".to_owned());
+                    opts.extend(iter.next().map(|&port| port.to_owned()));
+                }
+                "-l" => {
+                    opts.push("-o".to_owned());
+                    opts.extend(iter.next().map(|&login| format!("User={}", login)));
+                }
+                _ if opt.starts_with("-p") => opts.push(format!("-P{}", &opt[2..])),
+                _ if opt.starts_with("-l") => {
+                    opts.push("-o".to_owned());
+                    opts.push(format!("User={}", &opt[2..]));
+                }
+                _ => opts.push(opt.to_owned()),
+            }
+        }
+
+        opts
+    }
+
+    /// Formats `path` on the host `ip_addr` as `[user@]host:path`; IPv6 addresses are bracketed.
+    fn remote_path(ip_addr: &IpAddr, login_name: Option<&str>, path: &str) -> String {
+        let host = match *ip_addr {
+            IpAddr::V4(ref ip) => ip.to_string(),
+            IpAddr::V6(ref ip) => format!("[{}]", ip),
+        };
+        match login_name {
+            Some(user) => format!("{}@{}:{}", user, host, path),
+            None => format!("{}:{}", host, path),
+        }
+    }
+
+    /// Quotes `arg` for rsync's remote shell command line if it contains white space or quotes; rsync splits this
+    /// command line itself and unquotes doubled quotes.
+    fn quote(arg: &str) -> String {
+        if arg.chars().any(|c| c.is_whitespace() || c == '\'' || c == '"') {
+            format!("'{}'", arg.replace('\'', "''"))
+        } else {
+            arg.to_owned()
+        }
+    }
+}
+
+pub mod wait {
+    use super::*;
+
+    use clams::prelude::*;
+    use std::{
+        error::Error as StdError,
+        sync::{mpsc::channel, Arc},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use provider::{InstanceId, Provider};
+
+    const POLL_INTERVAL_SECS: u64 = 5;
+    const TICK_MILLIS: u64 = 250;
+    /// States of snapshots and images that will never change to the target state.
+    const FAILED_STATES: &[&str] = &["error", "failed"];
+
+    #[derive(Debug, Serialize)]
+    pub struct WaitResult {
+        pub id:      String,
+        pub state:   Option<String>,
+        pub reached: bool,
+    }
+
+    /// Polls each instance until it has reached `target_state` or `timeout` has passed.
+    ///
+    /// Fails with the instances and their last known states if any instance did not reach the target state in
+    /// time.
+    pub fn wait_for_state(
+        provider: Arc<dyn Provider>,
+        instance_ids: &[InstanceId],
+        target_state: &str,
+        timeout: Duration,
+        use_progress_bar: bool,
+    ) -> Result<Vec<WaitResult>> {
+        wait_for(instance_ids, target_state, timeout, use_progress_bar, move |instance_id| {
+            provider.describe_instance(instance_id).map(|x| x.state)
+        })
+    }
+
+    /// Polls the state of each resource, e.g., an instance or a snapshot, via `describe` until it has reached
+    /// `target_state` or `timeout` has passed.
+    ///
+    /// Fails with the resources and their last known states if any resource did not reach the target state.
+    pub fn wait_for<F, E>(
+        ids: &[String],
+        target_state: &str,
+        timeout: Duration,
+        use_progress_bar: bool,
+        describe: F,
+    ) -> Result<Vec<WaitResult>>
+    where
+        F: Fn(&str) -> ::std::result::Result<Option<String>, E> + Send + Sync + 'static,
+        E: StdError + Send + 'static,
+    {
+        let describe = Arc::new(describe);
+        let mut results = Vec::new();
+        let m = MultiProgress::new();
+
+        for id in ids {
+            let (sender, receiver) = channel();
+            results.push((id.clone(), receiver));
+
+            let pb = if use_progress_bar {
+                let pb = m.add(ProgressBar::new_spinner());
+                pb.set_style(ProgressStyle::default_clams_spinner());
+                pb.set_prefix(id);
+                pb.set_message(&format!("Waiting for state '{}'", target_state));
+                Some(pb)
+            } else {
+                None
+            };
+
+            let describe = Arc::clone(&describe);
+            let id = id.clone();
+            let target_state = target_state.to_owned();
+            let _ = thread::spawn(move || {
+                let progress = |state: &str| {
+                    if let Some(ref pb) = pb {
+                        pb.set_message(&format!("Waiting for state '{}', currently '{}'", target_state, state));
+                        pb.inc(1);
+                    }
+                };
+
+                let res = poll(describe.as_ref(), &id, &target_state, timeout, progress);
+
+                let finish_msg = match res {
+                    Ok(WaitResult { reached: true, .. }) => format!("{}.", "Done".green()),
+                    Ok(ref result) => format!(
+                        "{} in state '{}'.",
+                        "Failed".red(),
+                        result.state.as_ref().map(String::as_str).unwrap_or("unknown")
+                    ),
+                    Err(ref e) => format!("{} ({})", "Error".red(), e),
+                };
+                match pb {
+                    Some(pb) => pb.finish_with_message(&finish_msg),
+                    None => info!("{}: {}", id, finish_msg),
+                }
+
+                // The receiver only vanishes if waiting has been aborted
+                let _ = sender.send(res);
+            });
+        }
+        if use_progress_bar {
+            m.join().chain_err(|| ErrorKind::FailedToWaitFor(ids.join(", ")))?;
+        }
+
+        let results = results
+            .iter()
+            .map(|&(ref id, ref receiver)| {
+                receiver
+                    .recv()
+                    .chain_err(|| ErrorKind::FailedToWaitFor(id.to_owned()))
+                    .and_then(|x| x)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let not_reached: Vec<_> = results
+            .iter()
+            .filter(|x| !x.reached)
+            .map(|x| format!("{} ({})", x.id, x.state.as_ref().map(String::as_str).unwrap_or("unknown")))
+            .collect();
+        if !not_reached.is_empty() {
+            return Err(Error::from_kind(ErrorKind::TargetStateNotReached(
+                target_state.to_owned(),
+                not_reached.join(", "),
+            )));
+        }
+
+        Ok(results)
+    }
+
+    fn poll<D, E, F>(describe: &D, id: &str, target_state: &str, timeout: Duration, progress: F) -> Result<WaitResult>
+    where
+        D: Fn(&str) -> ::std::result::Result<Option<String>, E>,
+        E: StdError + Send + 'static,
+        F: Fn(&str),
+    {
+        let start = Instant::now();
+        let mut last_poll: Option<Instant> = None;
+        let mut state: Option<String> = None;
+
+        loop {
+            if last_poll.map_or(true, |x| x.elapsed() >= Duration::from_secs(POLL_INTERVAL_SECS)) {
+                state = describe(id).chain_err(|| ErrorKind::FailedToWaitFor(id.to_owned()))?;
+                last_poll = Some(Instant::now());
+            }
+
+            let current = state.as_ref().map(String::as_str);
+            let reached = current == Some(target_state);
+            let failed = current.map_or(false, |x| FAILED_STATES.contains(&x));
+            if reached || failed || start.elapsed() >= timeout {
+                return Ok(WaitResult {
+                    id: id.to_owned(),
+                    state,
+                    reached,
+                });
+            }
+
+            progress(current.unwrap_or("unknown"));
+            thread::sleep(Duration::from_millis(TICK_MILLIS));
+        }
+    }
+}
+
 error_chain! {
     errors {
         FailedToBuildSshCommand {
@@ -403,6 +1360,20 @@ error_chain! {
         FailedToExecuteSsh {
             description("Failed to execute ssh")
         }
+        FailedToBuildCopyCommand {
+            description("Failed to build copy command")
+        }
+        InvalidCopyLocation(location: String) {
+            description("Invalid copy location")
+            display("Invalid copy location '{}'; use 'local:<path>' or 'instance:<path>'", location)
+        }
+        InvalidTransfer {
+            description("Exactly one of source and destination has to be on the instances")
+        }
+        FailedToCreateLocalDir(path: String) {
+            description("Failed to create local directory")
+            display("Failed to create local directory '{}'", path)
+        }
         FailedToInitLogging {
             description("Failed to init logging framework")
         }
@@ -413,9 +1384,37 @@ error_chain! {
         FailedToRunCommands {
             description("Failed to run commands")
         }
+        InvalidBatchSize(batch_size: String) {
+            description("Invalid batch size")
+            display("Invalid batch size '{}'; use a count or a percentage like '20%'", batch_size)
+        }
+        GateCommandFailed(cmd: String) {
+            description("Gate command failed")
+            display("Gate command '{}' failed", cmd)
+        }
+        FailedToReadResults {
+            description("Failed to read command results")
+        }
         FailedToOutput{
             description("Failed to output")
         }
+        FailedToPick {
+            description("Failed to pick from list")
+        }
+        NoTerminal {
+            description("Interactive picking requires a terminal")
+        }
+        NothingToPick {
+            description("Nothing to pick from")
+        }
+        FailedToWaitFor(id: String) {
+            description("Failed to wait for state change")
+            display("Failed to wait for state change of '{}'", id)
+        }
+        TargetStateNotReached(state: String, ids: String) {
+            description("Target state not reached")
+            display("Did not reach state '{}' in time: {}", state, ids)
+        }
     }
 }
 
@@ -427,9 +1426,367 @@ mod tests {
     use std::{
         fs::File,
         io::{BufRead, BufReader},
+        time::Duration,
     };
     use tempfile::NamedTempFile;
 
+    use provider::InstanceDescriptor;
+
+    #[test]
+    fn copy_transfer_from_locations() {
+        let push = copy::Transfer::new("logs".parse().unwrap(), "instance:/tmp/logs".parse().unwrap());
+        let pull = copy::Transfer::new("instance:/var/log/syslog".parse().unwrap(), "local:logs".parse().unwrap());
+        let remote = copy::Transfer::new("instance:/a".parse().unwrap(), "instance:/b".parse().unwrap());
+
+        assert_that(&push).is_ok().is_equal_to(copy::Transfer::Push {
+            local:  "logs".to_owned(),
+            remote: "/tmp/logs".to_owned(),
+        });
+        assert_that(&pull).is_ok().is_equal_to(copy::Transfer::Pull {
+            remote: "/var/log/syslog".to_owned(),
+            local:  "logs".to_owned(),
+        });
+        assert_that(&remote).is_err();
+    }
+
+    #[test]
+    fn build_rsync_command_to_instance() {
+        let instance = InstanceDescriptor {
+            instance_id: Some("i-1".to_owned()),
+            private_ip_address: Some("10.0.0.1".to_owned()),
+            ..Default::default()
+        };
+        let transfer = copy::Transfer::Push {
+            local:  "app.conf".to_owned(),
+            remote: "/etc/app.conf".to_owned(),
+        };
+        let options = copy::CopyOptions {
+            tool:      copy::Tool::Rsync,
+            recursive: false,
+        };
+        let login_name = "ubuntu".to_owned();
+        let ssh_opts = ["-J", "bastion", "-o", "ConnectTimeout 5"];
+
+        let commands = copy::build_copy_command_to_instances(
+            &[instance],
+            false,
+            Some(&login_name),
+            &ssh_opts,
+            &transfer,
+            options,
+            Duration::from_secs(10),
+        )
+        .unwrap();
+
+        assert_that(&commands).has_length(1);
+        assert_that(&commands[0].cmd).is_equal_to("rsync".to_owned());
+        assert_that(&commands[0].args).is_equal_to(Some(vec![
+            "--perms".to_owned(),
+            "--times".to_owned(),
+            "-e".to_owned(),
+            "ssh -J bastion -o 'ConnectTimeout 5'".to_owned(),
+            "app.conf".to_owned(),
+            "ubuntu@10.0.0.1:/etc/app.conf".to_owned(),
+        ]));
+    }
+
+    #[test]
+    fn build_scp_command_translates_ssh_opts() {
+        let instance = InstanceDescriptor {
+            instance_id: Some("i-1".to_owned()),
+            private_ip_address: Some("10.0.0.1".to_owned()),
+            ..Default::default()
+        };
+        let transfer = copy::Transfer::Pull {
+            remote: "/var/log/syslog".to_owned(),
+            local:  "logs".to_owned(),
+        };
+        let options = copy::CopyOptions {
+            tool:      copy::Tool::Scp,
+            recursive: false,
+        };
+        let ssh_opts = ["-p", "2222", "-lubuntu", "-i", "id_rsa"];
+
+        let commands = copy::build_copy_command_to_instances(
+            &[instance],
+            false,
+            None,
+            &ssh_opts,
+            &transfer,
+            options,
+            Duration::from_secs(10),
+        )
+        .unwrap();
+
+        assert_that(&commands[0].args).is_equal_to(Some(vec![
+            "-q".to_owned(),
+            "-p".to_owned(),
+            "-P".to_owned(),
+            "2222".to_owned(),
+            "-o".to_owned(),
+            "User=ubuntu".to_owned(),
+            "-i".to_owned(),
+            "id_rsa".to_owned(),
+            "10.0.0.1:/var/log/syslog".to_owned(),
+            "logs".to_owned(),
+        ]));
+    }
+
+    #[test]
+    fn fuzzy_filter_matches_characters_in_order() {
+        let items = vec!["webserver-01  i-1  10.0.1.11", "app_server-01  i-2  10.0.1.12", "webserver-02  i-3"];
+
+        assert_that(&picker::fuzzy_filter("wbs01", &items)).is_equal_to(vec![0]);
+        assert_that(&picker::fuzzy_filter("SERVER 01", &items)).is_equal_to(vec![0, 1]);
+        assert_that(&picker::fuzzy_filter("10w", &items)).is_empty();
+        assert_that(&picker::fuzzy_filter("", &items)).has_length(3);
+    }
+
+    #[test]
+    fn jump_host_formats_user_host_and_port() {
+        assert_that(&ssh::jump_host("bastion.example.com", None, None)).is_equal_to("bastion.example.com".to_owned());
+        assert_that(&ssh::jump_host("10.0.0.1", Some("jump"), Some(2222))).is_equal_to("jump@10.0.0.1:2222".to_owned());
+        assert_that(&ssh::jump_host("fe80::1", None, Some(22))).is_equal_to("[fe80::1]:22".to_owned());
+    }
+
+    #[test]
+    fn sets_jump_host_detects_jump_options() {
+        assert_that(&ssh::sets_jump_host(&["-J", "bastion"])).is_true();
+        assert_that(&ssh::sets_jump_host(&["-oProxyJump=bastion"])).is_true();
+        assert_that(&ssh::sets_jump_host(&["-o", "proxycommand ssh -W %h:%p bastion"])).is_true();
+        assert_that(&ssh::sets_jump_host(&["-v", "-p", "22"])).is_false();
+    }
+
+    #[test]
+    fn batch_size_from_count_or_percentage() {
+        assert_that(&"5".parse::<run::BatchSize>().map(|x| x.of(30))).is_ok().is_equal_to(5);
+        assert_that(&"20%".parse::<run::BatchSize>().map(|x| x.of(30))).is_ok().is_equal_to(6);
+        assert_that(&"10%".parse::<run::BatchSize>().map(|x| x.of(3))).is_ok().is_equal_to(1);
+        assert_that(&"0".parse::<run::BatchSize>()).is_err();
+        assert_that(&"150%".parse::<run::BatchSize>()).is_err();
+    }
+
+    fn command_result(id: &str, exit_code: u32) -> command::CommandResult {
+        command::CommandResult {
+            id:          id.to_owned(),
+            log:         PathBuf::new(),
+            exit_status: command::ExitStatus::Exited(exit_code),
+            command:     None,
+        }
+    }
+
+    #[test]
+    fn run_in_batches_skips_remaining_batches_after_max_failures() {
+        let targets = ["i-1", "i-2", "i-3", "i-4", "i-5"];
+        let rolling = run::Rolling {
+            batch_size: run::BatchSize::Count(2),
+            max_failures: Some(0),
+            ..Default::default()
+        };
+        let mut gated = Vec::new();
+
+        let res = run::run_in_batches(
+            &targets,
+            &rolling,
+            |batch| Ok(batch.iter().map(|id| command_result(id, if *id == "i-4" { 1 } else { 0 })).collect()),
+            |batch, _| {
+                gated.push(batch.len());
+                Ok::<(), String>(())
+            },
+        );
+
+        assert_that(&res.results).has_length(4);
+        assert_that(&res.skipped.to_vec()).is_equal_to(vec!["i-5"]);
+        assert_that(&gated).is_equal_to(vec![2]);
+    }
+
+    #[test]
+    fn run_in_batches_skips_remaining_batches_if_gate_fails() {
+        let targets = ["i-1", "i-2", "i-3"];
+        let rolling = run::Rolling {
+            batch_size: run::BatchSize::Count(1),
+            ..Default::default()
+        };
+
+        let res = run::run_in_batches(
+            &targets,
+            &rolling,
+            |batch| Ok(batch.iter().map(|id| command_result(id, 0)).collect()),
+            |_, _| Err("unhealthy"),
+        );
+
+        assert_that(&res.results).has_length(1);
+        assert_that(&res.skipped.to_vec()).is_equal_to(vec!["i-2", "i-3"]);
+        assert_that(&res.error.is_none()).is_true();
+    }
+
+    #[test]
+    fn run_in_batches_counts_failures_per_target() {
+        let targets = ["i-1", "i-2", "i-3"];
+        let rolling = run::Rolling {
+            batch_size: run::BatchSize::Count(1),
+            max_failures: Some(1),
+            ..Default::default()
+        };
+
+        let res = run::run_in_batches(
+            &targets,
+            &rolling,
+            |batch| Ok(vec![command_result(batch[0], 1), command_result(batch[0], 1)]),
+            |_, _| Ok::<(), String>(()),
+        );
+
+        assert_that(&res.results).has_length(4);
+        assert_that(&res.skipped.to_vec()).is_equal_to(vec!["i-3"]);
+    }
+
+    #[test]
+    fn run_in_batches_keeps_results_of_completed_batches_if_batch_fails() {
+        let targets = ["i-1", "i-2", "i-3"];
+        let rolling = run::Rolling {
+            batch_size: run::BatchSize::Count(1),
+            ..Default::default()
+        };
+
+        let res = run::run_in_batches(
+            &targets,
+            &rolling,
+            |batch| match batch[0] {
+                "i-2" => Err(Error::from_kind(ErrorKind::FailedToRunCommands)),
+                id => Ok(vec![command_result(id, 0)]),
+            },
+            |_, _| Ok::<(), String>(()),
+        );
+
+        assert_that(&res.results).has_length(1);
+        assert_that(&res.skipped.to_vec()).is_equal_to(vec!["i-2", "i-3"]);
+        assert_that(&res.error.is_some()).is_true();
+    }
+
+    #[test]
+    fn run_with_retries_reruns_only_failed_commands() {
+        let command = |id: &str| command::Command {
+            id:      id.to_owned(),
+            cmd:     "/bin/true".to_owned(),
+            args:    None,
+            cwd:     None,
+            log:     PathBuf::from(format!("{}.log", id)),
+            timeout: None,
+        };
+        let retries = run::Retries {
+            count:   2,
+            backoff: Duration::from_millis(0),
+        };
+        let mut runs: Vec<Vec<String>> = Vec::new();
+
+        let res = run::run_with_retries(vec![command("i-1"), command("i-2"), command("i-3")], &retries, |commands| {
+            runs.push(commands.iter().map(|c| c.id.clone()).collect());
+            let results = commands
+                .into_iter()
+                .map(|c| command::CommandResult {
+                    exit_status: command::ExitStatus::Exited(if c.id == "i-2" && runs.len() < 3 { 1 } else { 0 }),
+                    id:          c.id.clone(),
+                    log:         c.log.clone(),
+                    command:     Some(c),
+                })
+                .collect();
+            Ok(results)
+        })
+        .unwrap();
+
+        assert_that(&run::failed_ids(&res)).is_empty();
+        assert_that(&res.iter().map(|r| r.id.as_str()).collect::<Vec<_>>()).is_equal_to(vec!["i-1", "i-2", "i-3"]);
+        assert_that(&runs).is_equal_to(vec![
+            vec!["i-1".to_owned(), "i-2".to_owned(), "i-3".to_owned()],
+            vec!["i-2".to_owned()],
+            vec!["i-2".to_owned()],
+        ]);
+        assert_that(&res[1].log).is_equal_to(PathBuf::from("i-2.log.retry-2"));
+    }
+
+    #[test]
+    fn run_with_retries_keeps_results_if_retry_fails() {
+        let retries = run::Retries {
+            count:   2,
+            backoff: Duration::from_millis(0),
+        };
+        let mut runs = 0;
+
+        let res = run::run_with_retries(Vec::new(), &retries, |_| {
+            runs += 1;
+            if runs > 1 {
+                return Err(Error::from_kind(ErrorKind::FailedToRunCommands));
+            }
+            let mut result = command_result("i-1", 1);
+            result.command = Some(command::Command {
+                id:      "i-1".to_owned(),
+                cmd:     "/bin/false".to_owned(),
+                args:    None,
+                cwd:     None,
+                log:     PathBuf::new(),
+                timeout: None,
+            });
+            Ok(vec![result])
+        });
+
+        assert_that(&res).is_ok().has_length(1);
+        assert_that(&runs).is_equal_to(2);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        let retries = run::Retries {
+            count:   20,
+            backoff: Duration::from_secs(::std::u64::MAX / 2),
+        };
+
+        assert_that(&retries.delay(1)).is_equal_to(Duration::from_secs(3600));
+        assert_that(&run::Retries::default().delay(3)).is_equal_to(Duration::from_secs(20));
+    }
+
+    #[test]
+    fn read_results_of_json_output() {
+        let json = r#"[
+            {"id": "i-1", "log": "/tmp/1.log", "exit_status": {"Exited": 0}},
+            {"id": "i-2", "log": "/tmp/2.log", "exit_status": {"Exited": 255}},
+            {"id": "i-3", "log": "/tmp/3.log", "exit_status": "Undetermined"}
+        ]"#;
+
+        let res = run::read_results(json.as_bytes());
+
+        assert_that(&res).is_ok();
+        assert_that(&run::failed_ids(&res.unwrap())).is_equal_to(vec!["i-2", "i-3"]);
+    }
+
+    #[test]
+    fn run_with_stream_records_logs() {
+        let commands = vec![
+            command::Command {
+                id:      "one".to_owned(),
+                cmd:     "/bin/echo".to_owned(),
+                args:    Some(vec!["first line".to_owned()]),
+                cwd:     None,
+                log:     NamedTempFile::new().unwrap().path().to_path_buf(),
+                timeout: None,
+            },
+            command::Command {
+                id:      "two".to_owned(),
+                cmd:     "/bin/echo".to_owned(),
+                args:    Some(vec!["second line".to_owned()]),
+                cwd:     None,
+                log:     NamedTempFile::new().unwrap().path().to_path_buf(),
+                timeout: None,
+            },
+        ];
+
+        let res = run::run_with_stream(commands).unwrap();
+
+        assert_that(&res).has_length(2);
+        let log = BufReader::new(File::open(&res[1].log).unwrap()).lines().next().unwrap().unwrap();
+        assert_that(&log).is_equal_to("second line".to_owned());
+        assert_that(&res[1].exit_status.success()).is_true();
+    }
+
     #[test]
     fn run_non_existing_command() {
         let tmpfile = NamedTempFile::new().unwrap().path().to_path_buf();
@@ -5,3 +5,59 @@ cargo run -- completions --shell bash > /dev/null 2>&1 || (echo "Bash completion
 cargo run -- completions --shell fish > /dev/null 2>&1 || (echo "Fish completions failed."; exit 1)
 cargo run -- completions --shell zsh > /dev/null 2>&1  || (echo "Zsh completions failed."; exit 1)
 
+for shell in bash fish zsh; do
+    cargo run -- completions --shell ${shell} 2> /dev/null | grep -q "__complete" || { echo "Dynamic ${shell} completions missing."; exit 1; }
+done
+
+# Dynamic completions
+tmp_dir=$(mktemp -d)
+trap 'rm -rf "${tmp_dir}"' EXIT
+cp tests/inventory/instances.toml "${tmp_dir}/"
+cat > "${tmp_dir}/ceres.conf" <<CONF
+default_profile = 'test'
+
+[github]
+token = 'XXXXX'
+
+[pivotal]
+token = 'XXXXX'
+
+[logging]
+default = 'warn'
+ceres = 'warn'
+
+[cache]
+dir = '${tmp_dir}/cache'
+
+[status_pages]
+
+[profiles."test"]
+local_base_dir = '$(pwd)/tests/base_dir'
+
+[profiles."test".issue_tracker]
+github_org = 'XXXXX'
+github_repo = 'XXXXX'
+project_number = 1
+default_issue_template_name = 'ops_issue.md'
+local_issue_template_path = 'XXXXX'
+
+[profiles."test".story_tracker]
+project_id = 1
+
+[profiles."test".provider]
+type = 'inventory'
+path = '${tmp_dir}/instances.toml'
+
+[profiles."test".health]
+base_domain = 'example.com'
+CONF
+
+complete() {
+    cargo run -- --config "${tmp_dir}/ceres.conf" __complete -- "$@" 2> /dev/null
+}
+
+complete --profile "" | grep -q "^test$" || { echo "Profile completions failed."; exit 1; }
+complete instances ssh "" | grep -q "^i-00000000000000001	webserver-01$" || { echo "Instance completions failed."; exit 1; }
+complete infrastructure asp build -p "" | grep -q "^logimon$" || { echo "Project completions failed."; exit 1; }
+complete infrastructure asp build -p logimon -r "" | grep -q "^grafana$" || { echo "Resource completions failed."; exit 1; }
+[ -z "$(complete instances list "")" ] || { echo "Unexpected dynamic completions."; exit 1; }
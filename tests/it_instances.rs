@@ -1,4 +1,139 @@
 extern crate ceres;
+extern crate spectral;
+extern crate tempfile;
+
+use ceres::provider::{
+    filter::Filter,
+    inventory::Inventory,
+    DescribeInstance,
+    DescribeInstances,
+    StartInstances,
+    StopInstances,
+    TerminateInstances,
+};
+use ceres::utils::wait::wait_for_state;
+use spectral::prelude::*;
+use std::{fs, sync::Arc, time::Duration};
+use tempfile::TempDir;
+
+fn inventory_from_fixture(dir: &TempDir, file_name: &str) -> Inventory {
+    let path = dir.path().join(file_name);
+    fs::copy(format!("tests/inventory/{}", file_name), &path).expect("Failed to copy inventory fixture");
+
+    Inventory { path }
+}
+
+#[test]
+fn describe_instances_from_toml_inventory() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.toml");
+
+    let instances = inventory.describe_instances();
+
+    assert_that(&instances).is_ok().has_length(3);
+}
+
+#[test]
+fn describe_instances_from_json_inventory() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.json");
+
+    let instances = inventory.describe_instances();
+
+    assert_that(&instances).is_ok().has_length(2);
+}
+
+#[test]
+fn describe_unknown_instance_fails() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.toml");
+
+    let instance = inventory.describe_instance("i-does-not-exist");
+
+    assert_that(&instance).is_err();
+}
+
+#[test]
+fn filter_inventory_instances() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.toml");
+    let filter: Filter = "State=running,Tags=AnsibleHostGroup=app_.*".parse().unwrap();
+
+    let instances = inventory.describe_instances().unwrap();
+    let selected: Vec<_> = instances.iter().filter(|i| filter.filter(i)).collect();
+
+    assert_that(&selected).has_length(1);
+    assert_that(&selected[0].instance_id)
+        .is_some()
+        .is_equal_to("i-00000000000000002".to_owned());
+}
 
 #[test]
-fn noop_okay() {}
+fn filter_inventory_instances_by_expression() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.toml");
+    let filter: Filter = "Tags:AnsibleHostGroup == app_server and not State == running".parse().unwrap();
+
+    let instances = inventory.describe_instances().unwrap();
+    let selected: Vec<_> = instances.iter().filter(|i| filter.filter(i)).collect();
+
+    assert_that(&selected).has_length(1);
+    assert_that(&selected[0].instance_id)
+        .is_some()
+        .is_equal_to("i-00000000000000003".to_owned());
+}
+
+#[test]
+fn stop_and_start_inventory_instances() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.toml");
+    let instance_ids = vec!["i-00000000000000001".to_owned()];
+
+    let changes = inventory.stop_instances(false, false, &instance_ids).unwrap();
+    assert_that(&changes[0].previous_state).is_equal_to("running".to_owned());
+    assert_that(&changes[0].current_state).is_equal_to("stopped".to_owned());
+    let instance = inventory.describe_instance("i-00000000000000001").unwrap();
+    assert_that(&instance.state).is_some().is_equal_to("stopped".to_owned());
+
+    let changes = inventory.start_instances(false, &instance_ids).unwrap();
+    assert_that(&changes[0].current_state).is_equal_to("running".to_owned());
+    let instance = inventory.describe_instance("i-00000000000000001").unwrap();
+    assert_that(&instance.state).is_some().is_equal_to("running".to_owned());
+}
+
+#[test]
+fn terminate_inventory_instances_in_json() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.json");
+    let instance_ids = vec!["i-00000000000000002".to_owned()];
+
+    let _ = inventory.terminate_instances(false, &instance_ids).unwrap();
+
+    let instance = inventory.describe_instance("i-00000000000000002").unwrap();
+    assert_that(&instance.state).is_some().is_equal_to("terminated".to_owned());
+    let instances = inventory.describe_instances().unwrap();
+    assert_that(&instances).has_length(2);
+}
+
+#[test]
+fn wait_for_stopped_inventory_instances() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.toml");
+    let instance_ids = vec!["i-00000000000000001".to_owned()];
+    let _ = inventory.stop_instances(false, false, &instance_ids).unwrap();
+
+    let results = wait_for_state(Arc::new(inventory), &instance_ids, "stopped", Duration::from_secs(5), false);
+
+    assert_that(&results).is_ok().has_length(1);
+}
+
+#[test]
+fn wait_for_inventory_instances_times_out() {
+    let dir = TempDir::new().unwrap();
+    let inventory = inventory_from_fixture(&dir, "instances.toml");
+    let instance_ids = vec!["i-00000000000000001".to_owned()];
+
+    let results = wait_for_state(Arc::new(inventory), &instance_ids, "stopped", Duration::from_secs(0), false);
+
+    assert_that(&results).is_err();
+}